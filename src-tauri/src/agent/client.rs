@@ -0,0 +1,31 @@
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::UnixStream;
+use std::path::Path;
+use std::time::Duration;
+
+use super::protocol::{Request, Response};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Send one request to the agent listening on `path`
+/// Errors mean no agent is reachable there.
+pub fn request(path: &Path, request: &Request) -> Result<Response, String> {
+    request_with_timeout(path, request, TIMEOUT)
+}
+
+/// Like `request`, for requests that wait on the user, e.g. an approval prompt
+pub fn request_with_timeout(path: &Path, request: &Request, timeout: Duration) -> Result<Response, String> {
+    let mut stream = UnixStream::connect(path).map_err(|e| format!("代理未运行: {}", e))?;
+    stream.set_read_timeout(Some(timeout)).map_err(|e| e.to_string())?;
+    stream.set_write_timeout(Some(TIMEOUT)).map_err(|e| e.to_string())?;
+
+    let line = serde_json::to_string(request).map_err(|e| e.to_string())?;
+    writeln!(stream, "{}", line).map_err(|e| e.to_string())?;
+
+    let mut reply = String::new();
+    BufReader::new(stream).read_line(&mut reply).map_err(|e| e.to_string())?;
+    if reply.is_empty() {
+        return Err("代理拒绝了连接".to_string());
+    }
+    serde_json::from_str(&reply).map_err(|e| format!("代理返回了无效的响应: {}", e))
+}
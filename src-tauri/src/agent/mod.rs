@@ -0,0 +1,180 @@
+#[cfg(unix)]
+pub mod client;
+pub mod protocol;
+#[cfg(unix)]
+pub mod server;
+
+use std::path::{Path, PathBuf};
+#[cfg(unix)]
+use std::{fs, thread};
+#[cfg(unix)]
+use tauri::{AppHandle, Manager};
+#[cfg(unix)]
+use crate::browser;
+
+use crate::commands::entries::query_entries;
+use crate::commands::groups::query_groups;
+use crate::crypto::Session;
+use crate::db::Database;
+use crate::models::{Entry, EntryFilter};
+use protocol::{AgentStatus, Request, Response};
+
+/// Socket of the agent serving the vault at `db_path`, one per vault
+pub fn socket_path(db_path: &Path) -> PathBuf {
+    let dir = match dirs::runtime_dir() {
+        Some(dir) => dir.join("one-password"),
+        // Without XDG_RUNTIME_DIR (macOS), fall back to a per-user folder in the temp dir
+        None => std::env::temp_dir().join(format!("one-password-{}", user_tag())),
+    };
+    let id = crate::sync::engine::content_hash(db_path.to_string_lossy().as_bytes());
+    dir.join(format!("agent-{}.sock", id))
+}
+
+/// Answer a read request from the unlocked session
+/// Entries are read with the same queries the desktop commands use; secrets are only
+/// returned for a single entry at a time.
+pub fn handle(db: &Database, session: &Session, request: &Request) -> Response {
+    let result = match request {
+        Request::Status => serde_json::to_value(AgentStatus {
+            unlocked: session.is_unlocked(),
+            vault: db.path().display().to_string(),
+            pid: std::process::id(),
+        })
+        .map_err(|e| e.to_string()),
+        Request::Lock => {
+            session.lock();
+            Ok(serde_json::Value::Null)
+        }
+        Request::Stop => Ok(serde_json::Value::Null),
+        Request::BrowserPair { .. } | Request::BrowserCredentials { .. } => {
+            Err("浏览器扩展需要桌面应用正在运行".to_string())
+        }
+        Request::Entries { filter } => {
+            if !session.is_unlocked() {
+                return Response::Locked;
+            }
+            session.touch();
+            db.conn
+                .lock()
+                .map_err(|e| e.to_string())
+                .and_then(|conn| query_entries(&conn, filter))
+                .map(|entries| entries.into_iter().map(without_secrets).collect::<Vec<_>>())
+                .and_then(|entries| serde_json::to_value(entries).map_err(|e| e.to_string()))
+        }
+        Request::Entry { id } => {
+            if !session.is_unlocked() {
+                return Response::Locked;
+            }
+            session.touch();
+            let filter = EntryFilter {
+                entry_ids: Some(vec![id.clone()]),
+                ..Default::default()
+            };
+            db.conn
+                .lock()
+                .map_err(|e| e.to_string())
+                .and_then(|conn| query_entries(&conn, &filter))
+                .and_then(|entries| entries.into_iter().next().ok_or_else(|| "条目不存在".to_string()))
+                .and_then(|entry| serde_json::to_value(entry).map_err(|e| e.to_string()))
+        }
+        Request::Groups => {
+            if !session.is_unlocked() {
+                return Response::Locked;
+            }
+            session.touch();
+            db.conn
+                .lock()
+                .map_err(|e| e.to_string())
+                .and_then(|conn| query_groups(&conn))
+                .and_then(|groups| serde_json::to_value(groups).map_err(|e| e.to_string()))
+        }
+    };
+
+    match result {
+        Ok(data) => Response::Ok { data },
+        Err(message) => Response::Error { message },
+    }
+}
+
+/// Helper: entry as listed to agent clients, without its password and concealed values
+fn without_secrets(mut entry: Entry) -> Entry {
+    entry.password.clear();
+    for field in entry.fields.iter_mut().filter(|f| f.kind.is_secret()) {
+        field.value.clear();
+    }
+    entry
+}
+
+/// Serve the desktop app's session until the app switches to another vault
+/// The agent follows the app's lock state; CLI agents for the vault are taken over.
+#[cfg(unix)]
+pub fn start_for_app(app: &AppHandle) {
+    let path = socket_path(&app.state::<Database>().path());
+    let listener = match server::bind(&path) {
+        Ok(listener) => listener,
+        Err(e) => {
+            eprintln!("Failed to start unlock agent: {}", e);
+            return;
+        }
+    };
+
+    let app = app.clone();
+    thread::spawn(move || {
+        server::serve(
+            listener,
+            |request| match request {
+                Request::Stop => Response::Error {
+                    message: "代理由桌面应用管理，请在应用中锁定".to_string(),
+                },
+                Request::Lock => {
+                    crate::commands::auth::lock_app(&app);
+                    Response::Ok { data: serde_json::Value::Null }
+                }
+                Request::BrowserPair { extension_id, browser, public_key } => {
+                    browser::pair(&app, extension_id, browser, public_key)
+                }
+                Request::BrowserCredentials { extension_id, payload } => {
+                    browser::credentials(&app, extension_id, payload)
+                }
+                Request::Entry { .. } => confirm_entry(&app, request),
+                _ => handle(&app.state::<Database>(), &app.state::<Session>(), request),
+            },
+            || socket_path(&app.state::<Database>().path()) == path,
+        );
+        let _ = fs::remove_file(&path);
+    });
+}
+
+/// Hand out an entry's secrets once the user approves, like the browser extension's requests
+#[cfg(unix)]
+fn confirm_entry(app: &AppHandle, request: &Request) -> Response {
+    let response = handle(&app.state::<Database>(), &app.state::<Session>(), request);
+    if let Response::Ok { data } = &response {
+        let title = data.get("title").and_then(|t| t.as_str()).unwrap_or_default();
+        let message = format!("命令行请求读取条目「{}」的密码和隐藏字段。\n\n允许吗?", title);
+        if !browser::confirm(app, "命令行读取密码", &message) {
+            return Response::Error {
+                message: "用户拒绝了请求".to_string(),
+            };
+        }
+    }
+    response
+}
+
+/// Move the desktop agent to the vault the app just switched to
+#[cfg(unix)]
+pub fn restart_for_app(app: &AppHandle, old_db_path: &Path) {
+    // Any request wakes the old agent, which then sees the vault changed and exits
+    let _ = client::request(&socket_path(old_db_path), &Request::Status);
+    start_for_app(app);
+}
+
+#[cfg(unix)]
+fn user_tag() -> String {
+    unsafe { libc::geteuid() }.to_string()
+}
+
+#[cfg(not(unix))]
+fn user_tag() -> String {
+    std::env::var("USERNAME").unwrap_or_default()
+}
@@ -0,0 +1,48 @@
+use serde::{Deserialize, Serialize};
+
+use crate::models::EntryFilter;
+
+/// One request per connection, sent as a single JSON line
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum Request {
+    Status,
+    /// Matching entries with passwords and concealed field values left empty
+    Entries { filter: EntryFilter },
+    /// One entry with its secrets; the desktop app asks the user first
+    Entry { id: String },
+    Groups,
+    Lock,
+    /// Pair a browser extension by X25519 key agreement, after the user approves
+    BrowserPair {
+        extension_id: String,
+        browser: String,
+        /// Extension's public key, hex encoded
+        public_key: String,
+    },
+    /// Credentials for a page, encrypted with the pairing key both ways
+    BrowserCredentials { extension_id: String, payload: String },
+    /// Stop serving; the desktop app takes over an agent started by the CLI this way
+    Stop,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "camelCase")]
+pub enum Response {
+    Ok {
+        #[serde(default)]
+        data: serde_json::Value,
+    },
+    /// The vault is locked; the client has to ask for the master password
+    Locked,
+    Error { message: String },
+}
+
+/// Reply of a `Status` request
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AgentStatus {
+    pub unlocked: bool,
+    pub vault: String,
+    pub pid: u32,
+}
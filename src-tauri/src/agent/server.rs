@@ -0,0 +1,135 @@
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+use std::os::unix::io::AsRawFd;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use super::client;
+use super::protocol::{Request, Response};
+
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+/// Requests are tiny; anything bigger is not a client of ours
+const MAX_REQUEST_LEN: u64 = 64 * 1024;
+
+/// Bind the agent socket, replacing a stale socket or an agent that is still running
+pub fn bind(path: &Path) -> Result<UnixListener, String> {
+    if let Some(dir) = path.parent() {
+        fs::DirBuilder::new()
+            .recursive(true)
+            .mode(0o700)
+            .create(dir)
+            .map_err(|e| format!("无法创建代理目录: {}", e))?;
+    }
+
+    if path.exists() {
+        // Ask a running agent to hand over; a socket nobody answers on is stale
+        if let Ok(Response::Error { message }) = client::request(path, &Request::Stop) {
+            return Err(message);
+        }
+        fs::remove_file(path).map_err(|e| format!("无法移除旧的代理套接字: {}", e))?;
+    }
+
+    let listener = UnixListener::bind(path).map_err(|e| format!("无法启动代理: {}", e))?;
+    fs::set_permissions(path, fs::Permissions::from_mode(0o600)).map_err(|e| e.to_string())?;
+    Ok(listener)
+}
+
+/// Answer requests until a `Stop` request is accepted or `keep_running` turns false
+/// Connections from other users are dropped without a reply. Each connection is read and
+/// answered on its own thread, so a slow client or a request waiting for the user to confirm
+/// a prompt doesn't hold up the others. The socket file is left to whoever stopped the agent,
+/// since a successor may already have bound the path.
+pub fn serve(
+    listener: UnixListener,
+    handler: impl Fn(&Request) -> Response + Sync,
+    keep_running: impl Fn() -> bool,
+) {
+    let stopped = AtomicBool::new(false);
+    let own_path = listener
+        .local_addr()
+        .ok()
+        .and_then(|addr| addr.as_pathname().map(Path::to_path_buf));
+
+    thread::scope(|scope| {
+        for stream in listener.incoming() {
+            if stopped.load(Ordering::SeqCst) {
+                break;
+            }
+            let Ok(stream) = stream else { continue };
+            if !peer_is_owner(&stream) {
+                continue;
+            }
+
+            let (handler, stopped, own_path) = (&handler, &stopped, &own_path);
+            scope.spawn(move || {
+                let request = match read_request(&stream) {
+                    Ok(request) => request,
+                    Err(message) => return reply(stream, &Response::Error { message }),
+                };
+                let response = handler(&request);
+                if matches!((&request, &response), (Request::Stop, Response::Ok { .. })) {
+                    // Wake the accept loop before replying, while the socket is still ours
+                    stopped.store(true, Ordering::SeqCst);
+                    if let Some(path) = own_path {
+                        let _ = UnixStream::connect(path);
+                    }
+                }
+                reply(stream, &response);
+            });
+
+            if !keep_running() {
+                break;
+            }
+        }
+    });
+}
+
+fn reply(mut stream: UnixStream, response: &Response) {
+    if let Ok(line) = serde_json::to_string(response) {
+        let _ = writeln!(stream, "{}", line);
+    }
+}
+
+fn read_request(stream: &UnixStream) -> Result<Request, String> {
+    stream.set_read_timeout(Some(READ_TIMEOUT)).map_err(|e| e.to_string())?;
+
+    let mut line = String::new();
+    BufReader::new(std::io::Read::take(stream, MAX_REQUEST_LEN))
+        .read_line(&mut line)
+        .map_err(|e| e.to_string())?;
+    serde_json::from_str(&line).map_err(|e| format!("无效的请求: {}", e))
+}
+
+/// Helper: only the user running the agent may talk to it
+pub(crate) fn peer_is_owner(stream: &UnixStream) -> bool {
+    let uid = unsafe { libc::geteuid() };
+    peer_uid(stream) == Some(uid)
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn peer_uid(stream: &UnixStream) -> Option<libc::uid_t> {
+    let mut cred = libc::ucred { pid: 0, uid: 0, gid: 0 };
+    let mut len = std::mem::size_of::<libc::ucred>() as libc::socklen_t;
+    let result = unsafe {
+        libc::getsockopt(
+            stream.as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_PEERCRED,
+            &mut cred as *mut libc::ucred as *mut libc::c_void,
+            &mut len,
+        )
+    };
+    (result == 0).then_some(cred.uid)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn peer_uid(stream: &UnixStream) -> Option<libc::uid_t> {
+    let mut uid: libc::uid_t = 0;
+    let mut gid: libc::gid_t = 0;
+    let result = unsafe { libc::getpeereid(stream.as_raw_fd(), &mut uid, &mut gid) };
+    (result == 0).then_some(uid)
+}
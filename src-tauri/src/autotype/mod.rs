@@ -0,0 +1,153 @@
+pub mod sequence;
+#[cfg(target_os = "linux")]
+mod wayland;
+#[cfg(target_os = "linux")]
+mod x11;
+
+use rusqlite::{Connection, OptionalExtension};
+use std::sync::Mutex;
+use std::thread;
+#[cfg(target_os = "linux")]
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::models::Entry;
+use sequence::Action;
+
+/// Pause between keystrokes until the sequence sets its own with `{DELAY=ms}`
+#[cfg(target_os = "linux")]
+const DEFAULT_KEY_DELAY_MS: u64 = 10;
+/// Time for the target window to take the focus before typing starts
+#[cfg(target_os = "linux")]
+const START_DELAY: Duration = Duration::from_millis(300);
+
+/// X11 window that was focused before Quick Access opened
+#[derive(Default)]
+pub struct AutoTypeTarget(Mutex<Option<u32>>);
+
+/// A way of sending keystrokes to the focused window
+#[cfg(target_os = "linux")]
+trait Keyboard {
+    fn type_char(&mut self, c: char) -> Result<(), String>;
+    fn press(&mut self, key: sequence::Key) -> Result<(), String>;
+}
+
+/// Remember the focused window before one of ours takes the focus
+/// Only X11 exposes it; on Wayland the compositor returns the focus when Quick Access hides.
+pub fn remember_target(app: &AppHandle) {
+    #[cfg(target_os = "linux")]
+    if std::env::var_os("WAYLAND_DISPLAY").is_none() {
+        if let Some(window) = x11::active_window() {
+            if let Ok(mut target) = app.state::<AutoTypeTarget>().0.lock() {
+                *target = Some(window);
+            }
+        }
+    }
+    #[cfg(not(target_os = "linux"))]
+    let _ = app;
+}
+
+/// Sequence for an entry: its own, else its group's, else the configured default
+pub fn resolve_sequence(conn: &Connection, entry: &Entry) -> Result<String, String> {
+    if let Some(sequence) = &entry.autotype_sequence {
+        return Ok(sequence.clone());
+    }
+
+    if let Some(group_id) = &entry.group_id {
+        let group_sequence: Option<String> = conn
+            .query_row(
+                "SELECT autotype_sequence FROM groups WHERE id = ?1",
+                [group_id],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| e.to_string())?
+            .flatten();
+        if let Some(sequence) = group_sequence {
+            return Ok(sequence);
+        }
+    }
+
+    Ok(default_sequence(conn))
+}
+
+pub fn default_sequence(conn: &Connection) -> String {
+    crate::settings::get_text(conn, "autotype_default_sequence")
+}
+
+/// Hide Quick Access and type the entry into the window that was focused before it
+/// Typing happens in the background; failures are reported with the `autotype-failed` event.
+pub fn perform(app: &AppHandle, entry: &Entry, sequence: &str) -> Result<(), String> {
+    let actions = sequence::expand(&sequence::parse(sequence)?, entry)?;
+
+    if let Some(window) = app.get_webview_window("quick-access") {
+        window.hide().map_err(|e| e.to_string())?;
+    }
+    let target = app
+        .state::<AutoTypeTarget>()
+        .0
+        .lock()
+        .map_err(|e| e.to_string())?
+        .take();
+
+    let app = app.clone();
+    thread::spawn(move || {
+        if let Err(e) = run(target, &actions) {
+            eprintln!("Auto-type failed: {}", e);
+            let _ = app.emit("autotype-failed", e);
+        }
+    });
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+fn run(target: Option<u32>, actions: &[Action]) -> Result<(), String> {
+    // Typing into whatever happens to be focused could leak the password, so stop if the switch fails
+    if let Some(window) = target {
+        x11::activate(window)?;
+    }
+    thread::sleep(START_DELAY);
+
+    let mut keyboard = open_keyboard(actions)?;
+    type_actions(keyboard.as_mut(), actions)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn run(_target: Option<u32>, _actions: &[Action]) -> Result<(), String> {
+    Err("自动输入目前仅支持 Linux".to_string())
+}
+
+/// Virtual keyboard on Wayland, XTest on X11 or when the compositor lacks the protocol (XWayland)
+#[cfg(target_os = "linux")]
+fn open_keyboard(actions: &[Action]) -> Result<Box<dyn Keyboard>, String> {
+    if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        match wayland::WaylandKeyboard::connect(actions) {
+            Ok(keyboard) => return Ok(Box::new(keyboard)),
+            Err(e) if std::env::var_os("DISPLAY").is_none() => return Err(e),
+            Err(e) => eprintln!("Wayland virtual keyboard unavailable, falling back to XTest: {}", e),
+        }
+    }
+    Ok(Box::new(x11::X11Keyboard::connect()?))
+}
+
+#[cfg(target_os = "linux")]
+fn type_actions(keyboard: &mut dyn Keyboard, actions: &[Action]) -> Result<(), String> {
+    let mut key_delay = Duration::from_millis(DEFAULT_KEY_DELAY_MS);
+    for action in actions {
+        match action {
+            Action::Type(text) => {
+                for c in text.chars() {
+                    keyboard.type_char(c)?;
+                    thread::sleep(key_delay);
+                }
+            }
+            Action::Press(key) => {
+                keyboard.press(*key)?;
+                thread::sleep(key_delay);
+            }
+            Action::Delay(ms) => thread::sleep(Duration::from_millis((*ms).min(10_000))),
+            Action::KeyDelay(ms) => key_delay = Duration::from_millis((*ms).min(1_000)),
+        }
+    }
+    Ok(())
+}
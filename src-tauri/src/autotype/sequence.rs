@@ -0,0 +1,221 @@
+use crate::entry_types;
+use crate::models::Entry;
+use crate::totp;
+
+/// Sequence used when neither the entry nor its group sets one
+pub const DEFAULT_SEQUENCE: &str = "{USERNAME}{TAB}{PASSWORD}{ENTER}";
+
+/// Keys that are pressed rather than typed as text
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    Tab,
+    Enter,
+    Space,
+    Backspace,
+    Delete,
+    Escape,
+    Up,
+    Down,
+    Left,
+    Right,
+    Home,
+    End,
+}
+
+impl Key {
+    fn parse(name: &str) -> Option<Self> {
+        Some(match name {
+            "TAB" => Key::Tab,
+            "ENTER" => Key::Enter,
+            "SPACE" => Key::Space,
+            "BACKSPACE" | "BS" => Key::Backspace,
+            "DELETE" | "DEL" => Key::Delete,
+            "ESC" => Key::Escape,
+            "UP" => Key::Up,
+            "DOWN" => Key::Down,
+            "LEFT" => Key::Left,
+            "RIGHT" => Key::Right,
+            "HOME" => Key::Home,
+            "END" => Key::End,
+            _ => return None,
+        })
+    }
+
+    /// X11 keysym
+    pub fn keysym(self) -> u32 {
+        match self {
+            Key::Tab => 0xff09,
+            Key::Enter => 0xff0d,
+            Key::Space => 0x0020,
+            Key::Backspace => 0xff08,
+            Key::Delete => 0xffff,
+            Key::Escape => 0xff1b,
+            Key::Up => 0xff52,
+            Key::Down => 0xff54,
+            Key::Left => 0xff51,
+            Key::Right => 0xff53,
+            Key::Home => 0xff50,
+            Key::End => 0xff57,
+        }
+    }
+}
+
+/// Entry values a sequence can refer to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Field {
+    Username,
+    Password,
+    Url,
+    Title,
+    Notes,
+    Totp,
+    /// `{S:name}`, a typed or custom field
+    Custom(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Token {
+    Text(String),
+    Field(Field),
+    /// Key pressed the given number of times, e.g. `{TAB 2}`
+    Key(Key, u32),
+    /// `{DELAY 500}` pauses once
+    Delay(u64),
+    /// `{DELAY=50}` sets the pause between keystrokes from here on
+    KeyDelay(u64),
+}
+
+/// What the keyboard backends perform, with field values filled in
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+    Type(String),
+    Press(Key),
+    Delay(u64),
+    KeyDelay(u64),
+}
+
+/// Parse a KeePass-style sequence such as `{USERNAME}{TAB}{PASSWORD}{DELAY 200}{ENTER}`
+/// `{{}` and `{}}` type literal braces.
+pub fn parse(sequence: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut text = String::new();
+    let mut rest = sequence;
+
+    while let Some(start) = rest.find(['{', '}']) {
+        text.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        if rest.starts_with('}') {
+            return Err("自动输入序列中有多余的 \"}\"".to_string());
+        }
+        // `{}}` is a literal closing brace, so look for the end after the first character inside
+        let first_len = rest[1..].chars().next().map_or(0, char::len_utf8);
+        let end = rest[1 + first_len..]
+            .find('}')
+            .map(|i| i + 1 + first_len)
+            .ok_or("自动输入序列中缺少 \"}\"")?;
+        let placeholder = &rest[1..end];
+        rest = &rest[end + 1..];
+
+        if placeholder == "{" || placeholder == "}" {
+            text.push_str(placeholder);
+            continue;
+        }
+        if !text.is_empty() {
+            tokens.push(Token::Text(std::mem::take(&mut text)));
+        }
+        tokens.push(parse_placeholder(placeholder)?);
+    }
+    text.push_str(rest);
+    if !text.is_empty() {
+        tokens.push(Token::Text(text));
+    }
+
+    Ok(tokens)
+}
+
+fn parse_placeholder(placeholder: &str) -> Result<Token, String> {
+    if let Some(name) = placeholder.strip_prefix("S:").or_else(|| placeholder.strip_prefix("s:")) {
+        return Ok(Token::Field(Field::Custom(name.to_string())));
+    }
+
+    let unknown = || format!("未知的自动输入占位符: {{{}}}", placeholder);
+    let upper = placeholder.trim().to_uppercase();
+
+    if let Some(ms) = upper.strip_prefix("DELAY=") {
+        return ms.trim().parse().map(Token::KeyDelay).map_err(|_| unknown());
+    }
+    let (name, count) = match upper.split_once(' ') {
+        Some((name, count)) => (name, Some(count.trim().parse::<u64>().map_err(|_| unknown())?)),
+        None => (upper.as_str(), None),
+    };
+
+    if name == "DELAY" {
+        return count.map(Token::Delay).ok_or_else(unknown);
+    }
+    if let Some(key) = Key::parse(name) {
+        let count = count.unwrap_or(1);
+        if count == 0 || count > 100 {
+            return Err(unknown());
+        }
+        return Ok(Token::Key(key, count as u32));
+    }
+    if count.is_some() {
+        return Err(unknown());
+    }
+
+    let field = match name {
+        "USERNAME" | "USER" => Field::Username,
+        "PASSWORD" => Field::Password,
+        "URL" => Field::Url,
+        "TITLE" => Field::Title,
+        "NOTES" => Field::Notes,
+        "TOTP" => Field::Totp,
+        _ => return Err(unknown()),
+    };
+    Ok(Token::Field(field))
+}
+
+/// Fill in the entry's values, generating the TOTP code now
+pub fn expand(tokens: &[Token], entry: &Entry) -> Result<Vec<Action>, String> {
+    let mut actions = Vec::new();
+    for token in tokens {
+        match token {
+            Token::Text(text) => actions.push(Action::Type(text.clone())),
+            Token::Field(field) => {
+                let value = match field {
+                    Field::Username => entry.username.clone(),
+                    Field::Password => entry.password.clone(),
+                    Field::Url => entry.url.clone(),
+                    Field::Title => entry.title.clone(),
+                    Field::Notes => entry.notes.clone(),
+                    Field::Totp => totp::entry_code(entry)?,
+                    Field::Custom(name) => {
+                        let value = entry_types::field_value(entry, name);
+                        if value.is_empty() && !entry.fields.iter().any(|f| f.name == *name) {
+                            return Err(format!("条目没有字段: {}", name));
+                        }
+                        value.to_string()
+                    }
+                };
+                if !value.is_empty() {
+                    actions.push(Action::Type(value));
+                }
+            }
+            Token::Key(key, count) => actions.extend((0..*count).map(|_| Action::Press(*key))),
+            Token::Delay(ms) => actions.push(Action::Delay(*ms)),
+            Token::KeyDelay(ms) => actions.push(Action::KeyDelay(*ms)),
+        }
+    }
+    Ok(actions)
+}
+
+/// X11 keysym typing a character
+pub fn char_keysym(c: char) -> u32 {
+    match c {
+        '\n' => Key::Enter.keysym(),
+        '\t' => Key::Tab.keysym(),
+        ' '..='~' | '\u{a0}'..='\u{ff}' => c as u32,
+        _ => 0x0100_0000 + c as u32,
+    }
+}
@@ -0,0 +1,187 @@
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::Write;
+use std::os::fd::AsFd;
+use std::thread;
+use std::time::{Duration, Instant};
+use wayland_client::protocol::{wl_registry, wl_seat};
+use wayland_client::{delegate_noop, Connection, Dispatch, EventQueue, QueueHandle};
+
+use super::sequence::{char_keysym, Action};
+use super::Keyboard;
+
+#[allow(dead_code, non_camel_case_types, non_upper_case_globals, unused_imports, clippy::all)]
+mod protocol {
+    use wayland_client;
+    use wayland_client::protocol::*;
+
+    pub mod __interfaces {
+        use wayland_client::protocol::__interfaces::*;
+        wayland_scanner::generate_interfaces!("protocols/virtual-keyboard-unstable-v1.xml");
+    }
+    use self::__interfaces::*;
+
+    wayland_scanner::generate_client_code!("protocols/virtual-keyboard-unstable-v1.xml");
+}
+
+use protocol::zwp_virtual_keyboard_manager_v1::ZwpVirtualKeyboardManagerV1;
+use protocol::zwp_virtual_keyboard_v1::ZwpVirtualKeyboardV1;
+
+/// `wl_keyboard.keymap_format.xkb_v1`
+const KEYMAP_FORMAT_XKB_V1: u32 = 1;
+/// XKB keycodes are evdev codes plus 8
+const XKB_OFFSET: u32 = 8;
+
+#[derive(Default)]
+struct Globals {
+    seat: Option<wl_seat::WlSeat>,
+    manager: Option<ZwpVirtualKeyboardManagerV1>,
+}
+
+impl Dispatch<wl_registry::WlRegistry, ()> for Globals {
+    fn event(
+        state: &mut Self,
+        registry: &wl_registry::WlRegistry,
+        event: wl_registry::Event,
+        _: &(),
+        _: &Connection,
+        qh: &QueueHandle<Self>,
+    ) {
+        if let wl_registry::Event::Global { name, interface, .. } = event {
+            match interface.as_str() {
+                "wl_seat" if state.seat.is_none() => state.seat = Some(registry.bind(name, 1, qh, ())),
+                "zwp_virtual_keyboard_manager_v1" => state.manager = Some(registry.bind(name, 1, qh, ())),
+                _ => {}
+            }
+        }
+    }
+}
+
+delegate_noop!(Globals: ignore wl_seat::WlSeat);
+delegate_noop!(Globals: ZwpVirtualKeyboardManagerV1);
+delegate_noop!(Globals: ZwpVirtualKeyboardV1);
+
+/// Types through the virtual keyboard protocol (wlroots compositors, KDE Plasma)
+/// The keymap is generated for the characters of one sequence, so any character can be typed
+/// regardless of the user's layout.
+pub struct WaylandKeyboard {
+    connection: Connection,
+    queue: EventQueue<Globals>,
+    globals: Globals,
+    keyboard: ZwpVirtualKeyboardV1,
+    keycodes: HashMap<u32, u32>,
+    started: Instant,
+}
+
+impl WaylandKeyboard {
+    /// Connect to the compositor and upload a keymap for the keysyms in `actions`
+    pub fn connect(actions: &[Action]) -> Result<Self, String> {
+        let connection = Connection::connect_to_env().map_err(|e| format!("无法连接 Wayland: {}", e))?;
+        let mut queue = connection.new_event_queue();
+        let qh = queue.handle();
+        connection.display().get_registry(&qh, ());
+
+        let mut globals = Globals::default();
+        queue.roundtrip(&mut globals).map_err(|e| e.to_string())?;
+
+        let seat = globals.seat.as_ref().ok_or("Wayland 会话没有输入设备")?;
+        let manager = globals
+            .manager
+            .as_ref()
+            .ok_or("当前 Wayland 桌面不支持虚拟键盘协议 (zwp_virtual_keyboard_v1)")?;
+        let keyboard = manager.create_virtual_keyboard(seat, &qh, ());
+
+        let mut keysyms: Vec<u32> = Vec::new();
+        for action in actions {
+            let syms: Vec<u32> = match action {
+                Action::Type(text) => text.chars().map(char_keysym).collect(),
+                Action::Press(key) => vec![key.keysym()],
+                _ => continue,
+            };
+            for sym in syms {
+                if !keysyms.contains(&sym) {
+                    keysyms.push(sym);
+                }
+            }
+        }
+        let keycodes = keysyms
+            .iter()
+            .enumerate()
+            .map(|(i, sym)| (*sym, i as u32 + 1))
+            .collect();
+
+        let keymap = keymap_file(&keysyms)?;
+        let size = keymap.metadata().map_err(|e| e.to_string())?.len() as u32;
+        keyboard.keymap(KEYMAP_FORMAT_XKB_V1, keymap.as_fd(), size);
+        keyboard.modifiers(0, 0, 0, 0);
+        queue.roundtrip(&mut globals).map_err(|e| e.to_string())?;
+
+        Ok(WaylandKeyboard {
+            connection,
+            queue,
+            globals,
+            keyboard,
+            keycodes,
+            started: Instant::now(),
+        })
+    }
+
+    fn tap_keysym(&mut self, keysym: u32) -> Result<(), String> {
+        let code = *self.keycodes.get(&keysym).ok_or("键盘映射中缺少按键")?;
+        let time = self.started.elapsed().as_millis() as u32;
+        self.keyboard.key(time, code, 1);
+        self.keyboard.key(time + 1, code, 0);
+        self.connection.flush().map_err(|e| e.to_string())
+    }
+}
+
+impl Keyboard for WaylandKeyboard {
+    fn type_char(&mut self, c: char) -> Result<(), String> {
+        self.tap_keysym(char_keysym(c))
+    }
+
+    fn press(&mut self, key: super::sequence::Key) -> Result<(), String> {
+        self.tap_keysym(key.keysym())
+    }
+}
+
+impl Drop for WaylandKeyboard {
+    fn drop(&mut self) {
+        // Let the compositor process the last keys before the keyboard goes away
+        let _ = self.queue.roundtrip(&mut self.globals);
+        thread::sleep(Duration::from_millis(20));
+        self.keyboard.destroy();
+        let _ = self.connection.flush();
+    }
+}
+
+/// XKB keymap with one keycode per keysym, written to an unlinked file for the compositor to map
+fn keymap_file(keysyms: &[u32]) -> Result<File, String> {
+    let mut keymap = String::from("xkb_keymap {\nxkb_keycodes \"(unnamed)\" {\nminimum = 8;\n");
+    keymap.push_str(&format!("maximum = {};\n", keysyms.len() as u32 + XKB_OFFSET + 1));
+    for i in 1..=keysyms.len() {
+        keymap.push_str(&format!("<K{}> = {};\n", i, i as u32 + XKB_OFFSET));
+    }
+    keymap.push_str("};\nxkb_types \"(unnamed)\" { include \"complete\" };\n");
+    keymap.push_str("xkb_compatibility \"(unnamed)\" { include \"complete\" };\n");
+    keymap.push_str("xkb_symbols \"(unnamed)\" {\n");
+    for (i, sym) in keysyms.iter().enumerate() {
+        keymap.push_str(&format!("key <K{}> {{[ 0x{:08x} ]}};\n", i + 1, sym));
+    }
+    keymap.push_str("};\n};\n");
+
+    let dir = dirs::runtime_dir().unwrap_or_else(std::env::temp_dir);
+    let path = dir.join(format!("one-password-keymap-{}", std::process::id()));
+    let mut file = File::options()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&path)
+        .map_err(|e| format!("无法创建键盘映射: {}", e))?;
+    let _ = fs::remove_file(&path);
+    file.write_all(keymap.as_bytes())
+        .and_then(|_| file.write_all(&[0]))
+        .map_err(|e| format!("无法写入键盘映射: {}", e))?;
+    Ok(file)
+}
@@ -0,0 +1,194 @@
+use std::thread;
+use std::time::{Duration, Instant};
+use x11rb::connection::{Connection, RequestConnection as _};
+use x11rb::wrapper::ConnectionExt as _;
+use x11rb::protocol::xproto::{
+    self, AtomEnum, ClientMessageEvent, ConnectionExt as _, EventMask, Keycode, Keysym, Window,
+};
+use x11rb::protocol::xtest::ConnectionExt as _;
+use x11rb::rust_connection::RustConnection;
+use x11rb::CURRENT_TIME;
+
+use super::sequence::{char_keysym, Key};
+use super::Keyboard;
+
+const SHIFT_L: Keysym = 0xffe1;
+
+/// Types through the XTest extension
+/// Characters missing from the current layout are typed by temporarily remapping an unused keycode.
+pub struct X11Keyboard {
+    conn: RustConnection,
+    root: Window,
+    min_keycode: Keycode,
+    keysyms_per_keycode: u8,
+    mapping: Vec<Keysym>,
+    scratch: Option<Keycode>,
+    shift: Option<Keycode>,
+}
+
+impl X11Keyboard {
+    pub fn connect() -> Result<Self, String> {
+        let (conn, screen) = x11rb::connect(None).map_err(|e| format!("无法连接 X11: {}", e))?;
+        let root = conn.setup().roots[screen].root;
+        let min_keycode = conn.setup().min_keycode;
+        let max_keycode = conn.setup().max_keycode;
+
+        conn.extension_information(x11rb::protocol::xtest::X11_EXTENSION_NAME)
+            .map_err(|e| e.to_string())?
+            .ok_or("X 服务器不支持 XTest 扩展")?;
+
+        let reply = conn
+            .get_keyboard_mapping(min_keycode, max_keycode - min_keycode + 1)
+            .map_err(|e| e.to_string())?
+            .reply()
+            .map_err(|e| e.to_string())?;
+
+        let mut keyboard = X11Keyboard {
+            conn,
+            root,
+            min_keycode,
+            keysyms_per_keycode: reply.keysyms_per_keycode,
+            mapping: reply.keysyms,
+            scratch: None,
+            shift: None,
+        };
+        keyboard.shift = keyboard.find(SHIFT_L).map(|(code, _)| code);
+        // Highest keycode without any symbol, restored when done
+        keyboard.scratch = keyboard
+            .mapping
+            .chunks(keyboard.keysyms_per_keycode as usize)
+            .rposition(|syms| syms.iter().all(|s| *s == 0))
+            .map(|i| keyboard.min_keycode + i as u8);
+        Ok(keyboard)
+    }
+
+    /// Keycode producing the keysym and whether Shift is needed
+    fn find(&self, keysym: Keysym) -> Option<(Keycode, bool)> {
+        let per = self.keysyms_per_keycode as usize;
+        self.mapping.chunks(per).enumerate().find_map(|(i, syms)| {
+            let code = self.min_keycode + i as u8;
+            match syms.iter().take(2).position(|s| *s == keysym) {
+                Some(0) => Some((code, false)),
+                Some(_) => Some((code, true)),
+                None => None,
+            }
+        })
+    }
+
+    fn fake_key(&self, code: Keycode, press: bool) -> Result<(), String> {
+        let kind = if press { xproto::KEY_PRESS_EVENT } else { xproto::KEY_RELEASE_EVENT };
+        self.conn
+            .xtest_fake_input(kind, code, CURRENT_TIME, self.root, 0, 0, 0)
+            .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    fn tap_keysym(&mut self, keysym: Keysym) -> Result<(), String> {
+        let (code, shift) = match self.find(keysym) {
+            Some(found) => found,
+            None => {
+                let scratch = self.scratch.ok_or("没有可用于输入该字符的空闲键位")?;
+                let syms = vec![keysym; self.keysyms_per_keycode as usize];
+                self.conn
+                    .change_keyboard_mapping(1, scratch, self.keysyms_per_keycode, &syms)
+                    .map_err(|e| e.to_string())?;
+                self.conn.sync().map_err(|e| e.to_string())?;
+                // Clients pick up the new mapping asynchronously
+                thread::sleep(Duration::from_millis(30));
+                (scratch, false)
+            }
+        };
+
+        let shift = if shift { self.shift } else { None };
+        if let Some(shift) = shift {
+            self.fake_key(shift, true)?;
+        }
+        self.fake_key(code, true)?;
+        self.fake_key(code, false)?;
+        if let Some(shift) = shift {
+            self.fake_key(shift, false)?;
+        }
+        self.conn.sync().map_err(|e| e.to_string())
+    }
+}
+
+impl Keyboard for X11Keyboard {
+    fn type_char(&mut self, c: char) -> Result<(), String> {
+        self.tap_keysym(char_keysym(c))
+    }
+
+    fn press(&mut self, key: Key) -> Result<(), String> {
+        self.tap_keysym(key.keysym())
+    }
+}
+
+impl Drop for X11Keyboard {
+    fn drop(&mut self) {
+        if let Some(scratch) = self.scratch {
+            let syms = vec![0; self.keysyms_per_keycode as usize];
+            let _ = self
+                .conn
+                .change_keyboard_mapping(1, scratch, self.keysyms_per_keycode, &syms);
+            let _ = self.conn.sync();
+        }
+    }
+}
+
+/// Window that has the input focus according to the window manager, unless it belongs to this process
+pub fn active_window() -> Option<u32> {
+    let (conn, screen) = x11rb::connect(None).ok()?;
+    let root = conn.setup().roots[screen].root;
+    let window = get_window_property(&conn, root, "_NET_ACTIVE_WINDOW")?;
+    if window == 0 {
+        return None;
+    }
+    let pid = get_window_property(&conn, window, "_NET_WM_PID");
+    (pid != Some(std::process::id())).then_some(window)
+}
+
+/// Ask the window manager to focus the window and wait until it has
+pub fn activate(window: u32) -> Result<(), String> {
+    let (conn, screen) = x11rb::connect(None).map_err(|e| format!("无法连接 X11: {}", e))?;
+    let root = conn.setup().roots[screen].root;
+    let atom = intern(&conn, "_NET_ACTIVE_WINDOW").ok_or("窗口管理器不支持 _NET_ACTIVE_WINDOW")?;
+
+    // Source indication 2: request from a pager, so focus stealing prevention lets it through
+    let event = ClientMessageEvent::new(32, window, atom, [2, CURRENT_TIME, 0, 0, 0]);
+    conn.send_event(
+        false,
+        root,
+        EventMask::SUBSTRUCTURE_REDIRECT | EventMask::SUBSTRUCTURE_NOTIFY,
+        event,
+    )
+    .map_err(|e| e.to_string())?;
+    conn.flush().map_err(|e| e.to_string())?;
+
+    let deadline = Instant::now() + Duration::from_millis(1000);
+    while Instant::now() < deadline {
+        if get_window_property(&conn, root, "_NET_ACTIVE_WINDOW") == Some(window) {
+            return Ok(());
+        }
+        thread::sleep(Duration::from_millis(20));
+    }
+    Err("无法切换回之前的窗口".to_string())
+}
+
+fn intern(conn: &RustConnection, name: &str) -> Option<u32> {
+    conn.intern_atom(true, name.as_bytes())
+        .ok()?
+        .reply()
+        .ok()
+        .map(|r| r.atom)
+        .filter(|atom| *atom != 0)
+}
+
+/// First 32-bit value of a window or cardinal property
+fn get_window_property(conn: &RustConnection, window: Window, name: &str) -> Option<u32> {
+    let atom = intern(conn, name)?;
+    conn.get_property(false, window, atom, AtomEnum::ANY, 0, 1)
+        .ok()?
+        .reply()
+        .ok()?
+        .value32()?
+        .next()
+}
@@ -0,0 +1,242 @@
+use chrono::{Local, NaiveDateTime, TimeZone};
+use rusqlite::backup::Progress;
+use rusqlite::{Connection, DatabaseName};
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::keys::{self, BackupKey};
+use crate::crypto::{self, VaultKey, SALT_LEN};
+use crate::db::{migrations, Database};
+use crate::models::BackupSettings;
+
+/// Encrypted backup layout: magic | format version | key salt | payload
+/// Version 1 payload: nonce || ciphertext under the vault key.
+/// Version 2 payload: backup secret key wrapped with the vault key | data sealed to the backup public key.
+const MAGIC: &[u8; 4] = b"OPBK";
+const FORMAT_VERSION: u8 = 2;
+const HEADER_LEN: usize = MAGIC.len() + 1 + SALT_LEN;
+
+const FILE_PREFIX: &str = "one-password-";
+const FILE_EXTENSION: &str = ".opbak";
+const NAME_TIME_FORMAT: &str = "%Y%m%d-%H%M%S";
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupInfo {
+    pub file_name: String,
+    pub created_at: i64,
+    pub size: u64,
+}
+
+/// Directory backups are written to for the given settings
+pub fn backup_dir(db: &Database, settings: &BackupSettings) -> PathBuf {
+    if settings.directory.trim().is_empty() {
+        // One folder per vault file so vaults sharing a directory keep separate backups
+        let path = db.path();
+        let stem = path.file_stem().map(|s| s.to_os_string()).unwrap_or_default();
+        path.with_file_name("backups").join(stem)
+    } else {
+        PathBuf::from(settings.directory.trim())
+    }
+}
+
+/// Write an encrypted snapshot of the database and verify it can be read back
+/// Only the public backup key is needed, so this also works while the vault is locked.
+pub fn create_backup(db: &Database, backup_key: &BackupKey, dir: &Path) -> Result<BackupInfo, String> {
+    fs::create_dir_all(dir).map_err(|e| format!("无法创建备份目录: {}", e))?;
+
+    let now = Local::now();
+    let file_name = format!("{}{}{}", FILE_PREFIX, now.format(NAME_TIME_FORMAT), FILE_EXTENSION);
+    let path = dir.join(&file_name);
+
+    // The online backup API copies a consistent snapshot including WAL contents
+    let plain = with_temp_file(db, |tmp| {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        conn.backup(DatabaseName::Main, tmp, None)
+            .map_err(|e| format!("数据库快照失败: {}", e))?;
+        drop(conn);
+
+        // Switch the copy out of WAL mode so the single file is self-contained
+        Connection::open(tmp)
+            .and_then(|copy| copy.pragma_update(None, "journal_mode", "DELETE"))
+            .map_err(|e| format!("数据库快照失败: {}", e))?;
+
+        fs::read(tmp).map_err(|e| e.to_string())
+    })?;
+
+    let (sealed, key) = keys::seal(&backup_key.public, &plain)?;
+    let mut data = Vec::with_capacity(HEADER_LEN + keys::WRAPPED_LEN + sealed.len());
+    data.extend_from_slice(MAGIC);
+    data.push(FORMAT_VERSION);
+    data.extend_from_slice(&backup_key.salt);
+    data.extend_from_slice(&backup_key.wrapped_secret);
+    data.extend_from_slice(&sealed);
+
+    // Write to a partial file first so an interrupted write never looks like a backup
+    let partial = dir.join(format!("{}.partial", file_name));
+    fs::write(&partial, &data).map_err(|e| format!("无法写入备份文件: {}", e))?;
+    fs::rename(&partial, &path).map_err(|e| format!("无法写入备份文件: {}", e))?;
+
+    if let Err(e) = verify_backup(db, &path, &key) {
+        let _ = fs::remove_file(&path);
+        return Err(format!("备份校验失败: {}", e));
+    }
+
+    Ok(BackupInfo {
+        file_name,
+        created_at: now.timestamp(),
+        size: data.len() as u64,
+    })
+}
+
+/// Decrypt a backup just written with the symmetric key it was sealed with and check its contents
+fn verify_backup(db: &Database, path: &Path, key: &VaultKey) -> Result<(), String> {
+    let data = fs::read(path).map_err(|e| e.to_string())?;
+    let (version, _, payload) = split_header(&data)?;
+    if version != FORMAT_VERSION || payload.len() < keys::WRAPPED_LEN {
+        return Err("不是有效的备份文件".to_string());
+    }
+    let sealed = &payload[keys::WRAPPED_LEN..];
+    let plain = crypto::decrypt(key, keys::sealed_payload(sealed))?;
+
+    with_temp_file(db, |tmp| {
+        fs::write(tmp, &plain).map_err(|e| e.to_string())?;
+        check_integrity(tmp)
+    })
+}
+
+/// List backups in the directory, newest first
+pub fn list_backups(dir: &Path) -> Result<Vec<BackupInfo>, String> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut backups = Vec::new();
+    for item in fs::read_dir(dir).map_err(|e| format!("无法读取备份目录: {}", e))? {
+        let item = item.map_err(|e| e.to_string())?;
+        let file_name = item.file_name().to_string_lossy().to_string();
+        let Some(created_at) = parse_backup_time(&file_name) else {
+            continue;
+        };
+        let size = item.metadata().map(|m| m.len()).unwrap_or(0);
+        backups.push(BackupInfo {
+            file_name,
+            created_at,
+            size,
+        });
+    }
+
+    backups.sort_by_key(|b| std::cmp::Reverse(b.created_at));
+    Ok(backups)
+}
+
+/// Replace the current database with the contents of a backup
+/// Backup settings of the current database are kept so restoring never turns backups off.
+/// Returns the key the backup was encrypted with, which matches the restored master password.
+pub fn restore_backup(db: &Database, path: &Path, password: &str) -> Result<VaultKey, String> {
+    let data = fs::read(path).map_err(|e| format!("无法读取备份文件: {}", e))?;
+    let (version, salt, payload) = split_header(&data)?;
+    let key = crypto::derive_key(password, &salt)?;
+    let plain = if version == 1 {
+        crypto::decrypt(&key, payload)?
+    } else {
+        if payload.len() < keys::WRAPPED_LEN {
+            return Err("不是有效的备份文件".to_string());
+        }
+        let (wrapped, sealed) = payload.split_at(keys::WRAPPED_LEN);
+        let secret = keys::unwrap_secret(&key, wrapped)?;
+        crypto::decrypt(&keys::sealed_key(&secret, sealed)?, keys::sealed_payload(sealed))?
+    };
+
+    with_temp_file(db, |tmp| {
+        fs::write(tmp, &plain).map_err(|e| e.to_string())?;
+        check_integrity(tmp)?;
+
+        let mut conn = db.conn.lock().map_err(|e| e.to_string())?;
+
+        let mut stmt = conn
+            .prepare("SELECT key, value FROM settings WHERE key LIKE 'backup\\_%' ESCAPE '\\'")
+            .map_err(|e| e.to_string())?;
+        let backup_settings = stmt
+            .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))
+            .map_err(|e| e.to_string())?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())?;
+        drop(stmt);
+
+        conn.restore(DatabaseName::Main, tmp, None::<fn(Progress)>)
+            .map_err(|e| format!("恢复备份失败: {}", e))?;
+        db.invalidate_entries();
+
+        for (k, v) in &backup_settings {
+            conn.execute(
+                "INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)",
+                (k, v),
+            )
+            .map_err(|e| e.to_string())?;
+        }
+
+        Ok(())
+    })?;
+
+    // Bring backups taken by older versions up to the current schema
+    migrations::run(db).map_err(|e| e.to_string())?;
+
+    Ok(key)
+}
+
+/// Helper: split a backup file into its format version, key salt and encrypted payload
+fn split_header(data: &[u8]) -> Result<(u8, [u8; SALT_LEN], &[u8]), String> {
+    if data.len() < HEADER_LEN || &data[..MAGIC.len()] != MAGIC {
+        return Err("不是有效的备份文件".to_string());
+    }
+    let version = data[MAGIC.len()];
+    if !(1..=FORMAT_VERSION).contains(&version) {
+        return Err(format!("不支持的备份文件版本: {}", version));
+    }
+
+    let mut salt = [0u8; SALT_LEN];
+    salt.copy_from_slice(&data[MAGIC.len() + 1..HEADER_LEN]);
+    Ok((version, salt, &data[HEADER_LEN..]))
+}
+
+/// Helper: run a closure with a temporary file next to data.db that is always removed afterwards
+/// Plaintext copies stay on the same volume as the database instead of the backup target.
+fn with_temp_file<T>(db: &Database, f: impl FnOnce(&Path) -> Result<T, String>) -> Result<T, String> {
+    let tmp = db
+        .path()
+        .with_file_name(format!(".backup-{}.tmp", uuid::Uuid::new_v4()));
+    let result = f(&tmp);
+    let _ = fs::remove_file(&tmp);
+    result
+}
+
+/// Helper: make sure a database file is readable and consistent
+fn check_integrity(path: &Path) -> Result<(), String> {
+    let conn = Connection::open(path).map_err(|e| e.to_string())?;
+
+    let result: String = conn
+        .query_row("PRAGMA integrity_check", [], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+    if result != "ok" {
+        return Err(format!("数据库完整性检查失败: {}", result));
+    }
+
+    conn.query_row("SELECT COUNT(*) FROM entries", [], |row| row.get::<_, i64>(0))
+        .map_err(|e| format!("备份中缺少条目数据: {}", e))?;
+
+    Ok(())
+}
+
+/// Helper: read the creation time encoded in a backup file name
+fn parse_backup_time(file_name: &str) -> Option<i64> {
+    let stamp = file_name
+        .strip_prefix(FILE_PREFIX)?
+        .strip_suffix(FILE_EXTENSION)?;
+    let naive = NaiveDateTime::parse_from_str(stamp, NAME_TIME_FORMAT).ok()?;
+    Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .map(|dt| dt.timestamp())
+}
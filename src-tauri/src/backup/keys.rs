@@ -0,0 +1,116 @@
+use chacha20poly1305::aead::OsRng;
+use rusqlite::{Connection, OptionalExtension};
+use sha2::{Digest, Sha256};
+use x25519_dalek::{EphemeralSecret, PublicKey, StaticSecret};
+
+use crate::crypto::{self, VaultKey, SALT_LEN};
+
+const KEY_CONTEXT: &[u8] = b"one-password-backup";
+/// Length of the backup secret key wrapped with the vault key: nonce | key | tag
+pub const WRAPPED_LEN: usize = 24 + 32 + 16;
+
+/// Key pair backups are sealed to
+/// Only the public half is needed to write a backup, so scheduled backups keep running while
+/// the vault is locked and its key is wiped. The secret half is stored wrapped with the vault key.
+pub struct BackupKey {
+    pub public: PublicKey,
+    /// Salt of the vault key the secret half is wrapped with
+    pub salt: [u8; SALT_LEN],
+    pub wrapped_secret: Vec<u8>,
+}
+
+/// Stored backup key pair, None before the vault was first unlocked
+pub fn load(conn: &Connection) -> Result<Option<BackupKey>, String> {
+    let get = |key: &str| -> Result<Option<Vec<u8>>, String> {
+        let value: Option<String> = conn
+            .query_row("SELECT value FROM settings WHERE key = ?1", [key], |row| row.get(0))
+            .optional()
+            .map_err(|e| e.to_string())?;
+        Ok(value.and_then(|hex| crypto::from_hex(&hex)))
+    };
+
+    let (Some(public), Some(salt), Some(wrapped_secret)) =
+        (get("backup_public_key")?, get("backup_key_salt")?, get("backup_secret_key")?)
+    else {
+        return Ok(None);
+    };
+    let public = <[u8; 32]>::try_from(public).map_err(|_| "备份公钥已损坏".to_string())?;
+    let salt = <[u8; SALT_LEN]>::try_from(salt).map_err(|_| "备份公钥已损坏".to_string())?;
+    Ok(Some(BackupKey {
+        public: PublicKey::from(public),
+        salt,
+        wrapped_secret,
+    }))
+}
+
+/// Make sure a key pair wrapped with `key` exists, called whenever the vault key is set
+/// A pair wrapped with another key (after a password change, restore or joining sync) is replaced;
+/// older backups keep their own wrapped copy and still restore with the password of their time.
+pub fn ensure(conn: &Connection, key: &VaultKey) -> Result<(), String> {
+    if let Some(stored) = load(conn)? {
+        if stored.salt == key.salt && unwrap_secret(key, &stored.wrapped_secret).is_ok() {
+            return Ok(());
+        }
+    }
+
+    let secret = StaticSecret::random_from_rng(OsRng);
+    let public = PublicKey::from(&secret);
+    let wrapped = crypto::encrypt(key, secret.as_bytes())?;
+    let values = [
+        ("backup_public_key", crypto::to_hex(public.as_bytes())),
+        ("backup_key_salt", crypto::to_hex(&key.salt)),
+        ("backup_secret_key", crypto::to_hex(&wrapped)),
+    ];
+    for (name, value) in &values {
+        conn.execute(
+            "INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)",
+            (name, value),
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+pub fn unwrap_secret(key: &VaultKey, wrapped: &[u8]) -> Result<StaticSecret, String> {
+    let bytes = crypto::decrypt(key, wrapped)?;
+    let bytes = <[u8; 32]>::try_from(bytes.as_slice()).map_err(|_| "备份密钥已损坏".to_string())?;
+    Ok(StaticSecret::from(bytes))
+}
+
+/// Encrypt for the backup public key: ephemeral public key | nonce || ciphertext
+/// Also returns the symmetric key, so the caller can verify what it wrote.
+pub fn seal(public: &PublicKey, plaintext: &[u8]) -> Result<(Vec<u8>, VaultKey), String> {
+    let ephemeral = EphemeralSecret::random_from_rng(OsRng);
+    let ephemeral_public = PublicKey::from(&ephemeral);
+    let shared = ephemeral.diffie_hellman(public);
+    let key = symmetric_key(shared.as_bytes(), &ephemeral_public, public);
+
+    let mut out = ephemeral_public.as_bytes().to_vec();
+    out.extend_from_slice(&crypto::encrypt(&key, plaintext)?);
+    Ok((out, key))
+}
+
+/// Symmetric key of data written by `seal`, recovered with the backup secret key
+pub fn sealed_key(secret: &StaticSecret, sealed: &[u8]) -> Result<VaultKey, String> {
+    let ephemeral_public = sealed
+        .get(..32)
+        .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+        .map(PublicKey::from)
+        .ok_or("加密数据已损坏")?;
+    let shared = secret.diffie_hellman(&ephemeral_public);
+    Ok(symmetric_key(shared.as_bytes(), &ephemeral_public, &PublicKey::from(secret)))
+}
+
+/// Ciphertext part of data written by `seal`
+pub fn sealed_payload(sealed: &[u8]) -> &[u8] {
+    sealed.get(32..).unwrap_or_default()
+}
+
+fn symmetric_key(shared: &[u8], ephemeral: &PublicKey, recipient: &PublicKey) -> VaultKey {
+    let mut hasher = Sha256::new();
+    hasher.update(KEY_CONTEXT);
+    hasher.update(shared);
+    hasher.update(ephemeral.as_bytes());
+    hasher.update(recipient.as_bytes());
+    VaultKey::from_bytes(hasher.finalize().into())
+}
@@ -0,0 +1,6 @@
+pub mod archive;
+pub mod keys;
+pub mod rotation;
+pub mod scheduler;
+
+pub use archive::BackupInfo;
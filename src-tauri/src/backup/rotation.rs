@@ -0,0 +1,53 @@
+use chrono::{Datelike, Local, TimeZone};
+use std::collections::HashSet;
+
+use super::archive::BackupInfo;
+use crate::models::BackupSettings;
+
+/// Pick the backups that fall outside the daily/weekly/monthly retention
+/// Within every kept day, week and month the newest backup survives; the newest backup
+/// overall is always kept. `backups` must be sorted newest first.
+pub fn expired<'a>(backups: &'a [BackupInfo], settings: &BackupSettings) -> Vec<&'a BackupInfo> {
+    let mut keep: HashSet<&str> = HashSet::new();
+
+    if let Some(newest) = backups.first() {
+        keep.insert(&newest.file_name);
+    }
+
+    keep_newest_per_bucket(backups, settings.keep_daily, &mut keep, |dt| {
+        (dt.year(), dt.ordinal())
+    });
+    keep_newest_per_bucket(backups, settings.keep_weekly, &mut keep, |dt| {
+        let week = dt.iso_week();
+        (week.year(), week.week())
+    });
+    keep_newest_per_bucket(backups, settings.keep_monthly, &mut keep, |dt| {
+        (dt.year(), dt.month())
+    });
+
+    backups
+        .iter()
+        .filter(|b| !keep.contains(b.file_name.as_str()))
+        .collect()
+}
+
+fn keep_newest_per_bucket<'a>(
+    backups: &'a [BackupInfo],
+    count: i32,
+    keep: &mut HashSet<&'a str>,
+    bucket: impl Fn(chrono::DateTime<Local>) -> (i32, u32),
+) {
+    let mut seen: HashSet<(i32, u32)> = HashSet::new();
+
+    for backup in backups {
+        if seen.len() >= count.max(0) as usize {
+            break;
+        }
+        let Some(dt) = Local.timestamp_opt(backup.created_at, 0).single() else {
+            continue;
+        };
+        if seen.insert(bucket(dt)) {
+            keep.insert(&backup.file_name);
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use std::fs;
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+use super::{archive, keys, rotation};
+use crate::commands::settings::read_backup_settings;
+use crate::db::Database;
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Start the background thread that writes scheduled backups
+pub fn start(app: AppHandle) {
+    thread::spawn(move || loop {
+        thread::sleep(CHECK_INTERVAL);
+        if let Err(e) = run_if_due(&app) {
+            eprintln!("Scheduled backup failed: {}", e);
+        }
+    });
+}
+
+/// Write a backup when the interval has passed, then apply retention
+fn run_if_due(app: &AppHandle) -> Result<(), String> {
+    let db = app.state::<Database>();
+
+    let (settings, backup_key) = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        (read_backup_settings(&conn), keys::load(&conn)?)
+    };
+    if !settings.enabled {
+        return Ok(());
+    }
+
+    // The backup key pair is created the first time the vault is unlocked
+    let Some(backup_key) = backup_key else {
+        return Ok(());
+    };
+
+    let dir = archive::backup_dir(&db, &settings);
+    let backups = archive::list_backups(&dir)?;
+    let now = chrono::Utc::now().timestamp();
+    if let Some(latest) = backups.first() {
+        if now - latest.created_at < i64::from(settings.interval_hours) * 3600 {
+            return Ok(());
+        }
+    }
+
+    archive::create_backup(&db, &backup_key, &dir)?;
+    prune(&dir, &settings)
+}
+
+/// Delete backups outside the configured retention
+pub fn prune(dir: &std::path::Path, settings: &crate::models::BackupSettings) -> Result<(), String> {
+    let backups = archive::list_backups(dir)?;
+    for backup in rotation::expired(&backups, settings) {
+        fs::remove_file(dir.join(&backup.file_name))
+            .map_err(|e| format!("无法删除旧备份 {}: {}", backup.file_name, e))?;
+    }
+    Ok(())
+}
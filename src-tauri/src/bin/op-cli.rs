@@ -0,0 +1,4 @@
+// Command line access to the vault for shell scripts and CI jobs
+fn main() -> std::process::ExitCode {
+    one_password_lib::cli::run()
+}
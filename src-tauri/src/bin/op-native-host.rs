@@ -0,0 +1,4 @@
+// Native messaging host connecting browser extensions to the running desktop app
+fn main() -> std::process::ExitCode {
+    one_password_lib::native_host::run()
+}
@@ -0,0 +1,195 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+use x25519_dalek::{EphemeralSecret, PublicKey};
+
+use crate::agent::protocol::Response;
+use crate::commands::entries::query_entries;
+use crate::crypto::{self, Session, VaultKey};
+use crate::db::Database;
+use crate::models::EntryFilter;
+use crate::urlmatch;
+
+const KEY_CONTEXT: &[u8] = b"one-password-browser-pairing";
+/// Requests older than this are rejected so a captured one can't be replayed later
+const MAX_REQUEST_AGE_SECS: i64 = 60;
+
+/// Decrypted body of a credentials request
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CredentialsRequest {
+    origin: String,
+    timestamp: i64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BrowserCredential {
+    id: String,
+    title: String,
+    url: String,
+    username: String,
+    password: String,
+}
+
+/// Pair an extension after the user compares the pairing code and approves
+pub fn pair(app: &AppHandle, extension_id: &str, browser: &str, public_key: &str) -> Response {
+    let Some(extension_key) = crypto::from_hex(public_key).and_then(|k| <[u8; 32]>::try_from(k).ok()) else {
+        return error("无效的扩展公钥");
+    };
+
+    let message = format!(
+        "浏览器扩展请求与本应用配对。\n\n浏览器: {}\n扩展: {}\n配对码: {}\n\n请确认扩展中显示的配对码与此一致。",
+        browser,
+        extension_id,
+        pairing_code(&extension_key)
+    );
+    if !confirm(app, "浏览器扩展配对", &message) {
+        return error("用户拒绝了配对");
+    }
+
+    let secret = EphemeralSecret::random_from_rng(chacha20poly1305::aead::OsRng);
+    let own_public = PublicKey::from(&secret);
+    let shared = secret.diffie_hellman(&PublicKey::from(extension_key));
+    let key = derive_pairing_key(shared.as_bytes());
+
+    let db = app.state::<Database>();
+    let saved = db.conn.lock().map_err(|e| e.to_string()).and_then(|conn| {
+        conn.execute(
+            "INSERT OR REPLACE INTO browser_pairings (extension_id, browser, key, created_at, last_used_at)
+             VALUES (?1, ?2, ?3, ?4, NULL)",
+            rusqlite::params![extension_id, browser, crypto::to_hex(&key), chrono::Utc::now().timestamp()],
+        )
+        .map_err(|e| e.to_string())
+    });
+
+    match saved {
+        Ok(_) => Response::Ok {
+            data: serde_json::json!({ "publicKey": crypto::to_hex(own_public.as_bytes()) }),
+        },
+        Err(message) => error(&message),
+    }
+}
+
+/// Return the logins matching a page, once the user approves the request
+pub fn credentials(app: &AppHandle, extension_id: &str, payload: &str) -> Response {
+    let db = app.state::<Database>();
+    let session = app.state::<Session>();
+
+    let pairing: Option<(String, String)> = db.conn.lock().ok().and_then(|conn| {
+        conn.query_row(
+            "SELECT browser, key FROM browser_pairings WHERE extension_id = ?1",
+            [extension_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .ok()
+    });
+    let Some((browser, key)) = pairing else {
+        return error("扩展尚未配对");
+    };
+    let Some(key) = crypto::from_hex(&key).and_then(|k| <[u8; 32]>::try_from(k).ok()) else {
+        return error("配对信息已损坏，请重新配对");
+    };
+    let key = VaultKey::from_bytes(key);
+
+    // Decrypting proves the request comes from the paired extension
+    let request: CredentialsRequest = match crypto::from_hex(payload)
+        .ok_or_else(|| "无效的请求".to_string())
+        .and_then(|data| crypto::decrypt(&key, &data))
+        .and_then(|plain| serde_json::from_slice(&plain).map_err(|e| e.to_string()))
+    {
+        Ok(request) => request,
+        Err(_) => return error("请求无法验证，请重新配对"),
+    };
+    if (chrono::Utc::now().timestamp() - request.timestamp).abs() > MAX_REQUEST_AGE_SECS {
+        return error("请求已过期");
+    }
+
+    if !session.is_unlocked() {
+        return Response::Locked;
+    }
+
+    let matches: Vec<BrowserCredential> = match db
+        .conn
+        .lock()
+        .map_err(|e| e.to_string())
+        .and_then(|conn| query_entries(&conn, &EntryFilter::default()))
+    {
+        Ok(entries) => urlmatch::rank_entries(entries, &request.origin)
+            .into_iter()
+            .map(|m| BrowserCredential {
+                id: m.entry.id,
+                title: m.entry.title,
+                url: m.matched_url,
+                username: m.entry.username,
+                password: m.entry.password,
+            })
+            .collect(),
+        Err(message) => return error(&message),
+    };
+
+    // Nothing to hand out, so nothing to approve
+    if !matches.is_empty() {
+        let titles: Vec<String> = matches
+            .iter()
+            .map(|c| format!("  {} ({})", c.title, c.username))
+            .collect();
+        let message = format!(
+            "{} 扩展请求以下条目的登录信息:\n\n网站: {}\n{}\n\n允许填充吗?",
+            browser,
+            request.origin,
+            titles.join("\n")
+        );
+        if !confirm(app, "浏览器自动填充", &message) {
+            return error("用户拒绝了请求");
+        }
+    }
+
+    if let Ok(conn) = db.conn.lock() {
+        let _ = conn.execute(
+            "UPDATE browser_pairings SET last_used_at = ?1 WHERE extension_id = ?2",
+            rusqlite::params![chrono::Utc::now().timestamp(), extension_id],
+        );
+    }
+
+    let encrypted = serde_json::to_vec(&serde_json::json!({ "entries": matches }))
+        .map_err(|e| e.to_string())
+        .and_then(|plain| crypto::encrypt(&key, &plain));
+    match encrypted {
+        Ok(data) => Response::Ok {
+            data: serde_json::json!({ "payload": crypto::to_hex(&data) }),
+        },
+        Err(message) => error(&message),
+    }
+}
+
+/// Six digits both sides display so the user can tell the right extension is pairing
+pub fn pairing_code(extension_key: &[u8]) -> String {
+    let digest = Sha256::digest(extension_key);
+    let value = u32::from_be_bytes([digest[0], digest[1], digest[2], digest[3]]);
+    format!("{:06}", value % 1_000_000)
+}
+
+fn derive_pairing_key(shared: &[u8]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(KEY_CONTEXT);
+    hasher.update(shared);
+    hasher.finalize().into()
+}
+
+/// Helper: blocking approve/deny dialog, answered on the desktop
+pub(crate) fn confirm(app: &AppHandle, title: &str, message: &str) -> bool {
+    app.dialog()
+        .message(message)
+        .title(title)
+        .kind(MessageDialogKind::Warning)
+        .buttons(MessageDialogButtons::OkCancelCustom("允许".to_string(), "拒绝".to_string()))
+        .blocking_show()
+}
+
+fn error(message: &str) -> Response {
+    Response::Error {
+        message: message.to_string(),
+    }
+}
@@ -0,0 +1,182 @@
+use serde::de::DeserializeOwned;
+use std::fs;
+use std::io::Write;
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use super::args::Args;
+use super::{open_vault, positional, print_json, read_master_password, CliError, CliResult, EXIT_ERROR, EXIT_USAGE};
+use crate::agent::protocol::{AgentStatus, Request, Response};
+use crate::agent::{self, client, server};
+use crate::crypto::Session;
+use crate::settings;
+
+const START_TIMEOUT: Duration = Duration::from_secs(5);
+const WATCHDOG_INTERVAL: Duration = Duration::from_secs(5);
+/// How long `get` waits for the user to approve reading secrets in the desktop app
+const PROMPT_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// `op-cli agent start|stop|status`
+pub(super) fn run(args: &Args) -> CliResult {
+    match positional(args, "start|stop|status")? {
+        "start" => start(args),
+        "stop" => stop(args),
+        "status" => status(args),
+        // Started by `agent start` with the password on stdin
+        "serve" => serve(),
+        other => Err(CliError::new(EXIT_USAGE, format!("未知的代理命令: {}", other))),
+    }
+}
+
+/// Socket of an agent that holds the selected vault unlocked, if any
+pub(super) fn unlocked_socket() -> CliResult<Option<PathBuf>> {
+    let path = socket_path()?;
+    Ok(match query_status(&path) {
+        Some(status) if status.unlocked => Some(path),
+        _ => None,
+    })
+}
+
+/// Send a read request to the agent and decode the reply
+pub(super) fn fetch<T: DeserializeOwned>(path: &Path, request: &Request) -> CliResult<T> {
+    decode(client::request(path, request)?)
+}
+
+/// Like `fetch`, for requests the desktop app asks the user to approve
+pub(super) fn fetch_prompted<T: DeserializeOwned>(path: &Path, request: &Request) -> CliResult<T> {
+    decode(client::request_with_timeout(path, request, PROMPT_TIMEOUT)?)
+}
+
+fn decode<T: DeserializeOwned>(response: Response) -> CliResult<T> {
+    match response {
+        Response::Ok { data } => Ok(serde_json::from_value(data).map_err(|e| e.to_string())?),
+        Response::Locked => Err(CliError::new(EXIT_ERROR, "代理已锁定，请重试")),
+        Response::Error { message } => Err(CliError::from(message)),
+    }
+}
+
+fn start(args: &Args) -> CliResult {
+    let path = socket_path()?;
+    if let Some(status) = query_status(&path).filter(|s| s.unlocked) {
+        println!("代理已在运行 (pid {})", status.pid);
+        return Ok(());
+    }
+
+    // Check the password here so a typo is reported instead of a silent exit
+    let password = read_master_password(args)?;
+    super::unlock_vault(&super::vault_path()?, &password)?;
+
+    let mut command = Command::new(std::env::current_exe().map_err(|e| e.to_string())?);
+    command.args(["agent", "serve"]);
+    if let Some(vault) = args.value("vault") {
+        command.args(["--vault", vault]);
+    }
+    // Own process group, so closing the terminal doesn't take the agent down
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .process_group(0)
+        .spawn()
+        .map_err(|e| format!("无法启动代理: {}", e))?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        writeln!(stdin, "{}", password).map_err(|e| e.to_string())?;
+    }
+
+    let started = std::time::Instant::now();
+    while started.elapsed() < START_TIMEOUT {
+        if let Some(status) = query_status(&path).filter(|s| s.unlocked && s.pid == child.id()) {
+            println!("代理已启动 (pid {})", status.pid);
+            return Ok(());
+        }
+        if let Ok(Some(_)) = child.try_wait() {
+            break;
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+
+    Err(CliError::new(EXIT_ERROR, "代理未能启动，桌面应用可能正在提供代理"))
+}
+
+fn serve() -> CliResult {
+    let args = Args::default();
+    let database = open_vault(&args)?;
+
+    // 0 means never auto-lock, like in the desktop app
+    let auto_lock_minutes: u64 = {
+        let conn = database.conn.lock().map_err(|e| e.to_string())?;
+        settings::get_int(&conn, "auto_lock_minutes").max(0) as u64
+    };
+
+    let path = agent::socket_path(&database.path());
+    let listener = server::bind(&path)?;
+    let session = Arc::new(Session::default());
+    session.unlock();
+
+    // Exit once locked or idle for longer than the auto-lock timeout
+    let watched = Arc::clone(&session);
+    let watched_path = path.clone();
+    thread::spawn(move || loop {
+        thread::sleep(WATCHDOG_INTERVAL);
+        let expired = match watched.idle_for() {
+            None => true,
+            Some(idle) => auto_lock_minutes > 0 && idle > Duration::from_secs(auto_lock_minutes * 60),
+        };
+        if expired {
+            let _ = fs::remove_file(&watched_path);
+            std::process::exit(0);
+        }
+    });
+
+    server::serve(listener, |request| agent::handle(&database, &session, request), || true);
+    Ok(())
+}
+
+fn stop(args: &Args) -> CliResult {
+    let path = socket_path()?;
+    match client::request(&path, &Request::Stop) {
+        Ok(Response::Ok { .. }) => {
+            let _ = fs::remove_file(&path);
+            if !args.flag("json") {
+                println!("代理已停止");
+            }
+            Ok(())
+        }
+        Ok(Response::Error { message }) => Err(CliError::from(message)),
+        Ok(Response::Locked) | Err(_) => {
+            if !args.flag("json") {
+                println!("代理未运行");
+            }
+            Ok(())
+        }
+    }
+}
+
+fn status(args: &Args) -> CliResult {
+    let status = query_status(&socket_path()?);
+    if args.flag("json") {
+        return print_json(&status);
+    }
+
+    match status {
+        Some(status) => {
+            let state = if status.unlocked { "已解锁" } else { "已锁定" };
+            println!("代理运行中 (pid {}), {}: {}", status.pid, state, status.vault);
+            Ok(())
+        }
+        None => Err(CliError::new(EXIT_ERROR, "代理未运行")),
+    }
+}
+
+fn query_status(path: &Path) -> Option<AgentStatus> {
+    fetch(path, &Request::Status).ok()
+}
+
+fn socket_path() -> CliResult<PathBuf> {
+    Ok(agent::socket_path(&super::vault_path()?))
+}
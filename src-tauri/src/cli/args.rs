@@ -0,0 +1,127 @@
+use std::collections::HashMap;
+
+/// Options that take a value; every other `--flag` is a switch
+const VALUE_OPTIONS: [&str; 13] = [
+    "vault", "field", "fields", "group", "type", "template", "title", "url", "username", "password", "notes", "length",
+    "output",
+];
+
+/// Command line split into a command, positional arguments and options
+#[derive(Debug, Default)]
+pub struct Args {
+    pub command: Option<String>,
+    pub positional: Vec<String>,
+    options: HashMap<String, String>,
+    switches: Vec<String>,
+}
+
+impl Args {
+    pub fn parse(args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut parsed = Args::default();
+        let mut args = args.peekable();
+
+        while let Some(arg) = args.next() {
+            if arg == "--" {
+                parsed.positional.extend(args.by_ref());
+                break;
+            }
+
+            let Some(flag) = arg.strip_prefix("--") else {
+                if arg == "-h" {
+                    parsed.switches.push("help".to_string());
+                } else if parsed.command.is_none() {
+                    parsed.command = Some(arg);
+                } else {
+                    parsed.positional.push(arg);
+                }
+                continue;
+            };
+
+            match flag.split_once('=') {
+                Some((name, value)) => {
+                    parsed.options.insert(name.to_string(), value.to_string());
+                }
+                None if VALUE_OPTIONS.contains(&flag) => {
+                    let value = args.next().ok_or_else(|| format!("--{} 需要一个值", flag))?;
+                    parsed.options.insert(flag.to_string(), value);
+                }
+                None => parsed.switches.push(flag.to_string()),
+            }
+        }
+
+        Ok(parsed)
+    }
+
+    pub fn value(&self, name: &str) -> Option<&str> {
+        self.options.get(name).map(String::as_str)
+    }
+
+    pub fn flag(&self, name: &str) -> bool {
+        self.switches.iter().any(|s| s == name)
+    }
+
+    /// Reject options the command does not understand, so typos don't go unnoticed
+    pub fn check_known(&self, allowed: &[&str]) -> Result<(), String> {
+        const GLOBAL: [&str; 4] = ["vault", "json", "password-stdin", "help"];
+        for name in self.options.keys().chain(self.switches.iter()) {
+            if !GLOBAL.contains(&name.as_str()) && !allowed.contains(&name.as_str()) {
+                return Err(format!("未知选项: --{}", name));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(line: &str) -> Result<Args, String> {
+        Args::parse(line.split_whitespace().map(str::to_string))
+    }
+
+    #[test]
+    fn splits_command_positionals_and_options() {
+        let args = parse("get Mail --field username --json").unwrap();
+        assert_eq!(args.command.as_deref(), Some("get"));
+        assert_eq!(args.positional, ["Mail"]);
+        assert_eq!(args.value("field"), Some("username"));
+        assert!(args.flag("json"));
+    }
+
+    #[test]
+    fn type_takes_a_value() {
+        let args = parse("list --type login").unwrap();
+        assert_eq!(args.value("type"), Some("login"));
+        assert!(args.positional.is_empty());
+        assert!(!args.flag("type"));
+    }
+
+    #[test]
+    fn template_takes_a_value() {
+        let args = parse("add Mail --template tpl-1 --username me").unwrap();
+        assert_eq!(args.positional, ["Mail"]);
+        assert_eq!(args.value("template"), Some("tpl-1"));
+        assert_eq!(args.value("username"), Some("me"));
+    }
+
+    #[test]
+    fn values_can_follow_an_equals_sign() {
+        let args = parse("list --group=Work --type=card").unwrap();
+        assert_eq!(args.value("group"), Some("Work"));
+        assert_eq!(args.value("type"), Some("card"));
+    }
+
+    #[test]
+    fn missing_values_and_unknown_options_are_errors() {
+        assert!(parse("list --type").is_err());
+        assert!(parse("list --typo").unwrap().check_known(&["group", "type"]).is_err());
+        assert!(parse("list --type login --json").unwrap().check_known(&["group", "type"]).is_ok());
+    }
+
+    #[test]
+    fn double_dash_ends_options() {
+        let args = parse("get -- --not-an-option").unwrap();
+        assert_eq!(args.positional, ["--not-an-option"]);
+    }
+}
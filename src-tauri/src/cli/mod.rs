@@ -0,0 +1,620 @@
+#[cfg(unix)]
+mod agent;
+mod args;
+
+use serde::Serialize;
+use std::fs;
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use crate::commands::entries::{apply_entry_update, insert_entry, query_entries};
+use crate::commands::export::{export_json, import_json, ExportField, ExportOptions};
+use crate::commands::groups::query_groups;
+use crate::commands::templates::query_templates;
+use crate::crypto::{generate_password, PasswordOptions};
+use crate::db::{self, Database};
+use crate::models::{CreateEntryDto, Entry, EntryFilter, EntryTemplate, EntryType, Group, UpdateEntryDto};
+#[cfg(unix)]
+use crate::agent::protocol::Request;
+use args::Args;
+
+const EXIT_ERROR: u8 = 1;
+const EXIT_USAGE: u8 = 2;
+const EXIT_AUTH: u8 = 3;
+const EXIT_NOT_FOUND: u8 = 4;
+const EXIT_AMBIGUOUS: u8 = 5;
+
+const USAGE: &str = "\
+用法: op-cli <命令> [参数] [选项]
+
+命令:
+  list                         列出条目 (--group <分组> --favorites --type login|creditCard|sshKey|...)
+  get <标题|ID>                显示条目 (--field password|username|url|notes|title)
+  add <标题>                   新建条目 (--group --template <模板> --url --username --password --notes --generate)
+  edit <标题|ID>               修改条目 (--title --group --url --username --password --notes --generate)
+  rm <标题|ID>                 删除条目
+  search <关键词>              搜索标题、用户名和网址
+  generate                     生成密码 (--length <N> --no-lowercase --no-uppercase --no-digits --no-symbols)
+  export                       导出 JSON (--output <文件> --group <分组> --favorites --fields title,username,...)
+  import <文件>                导入 JSON (--overwrite --dry-run)
+  agent start|stop|status      解锁代理: 在自动锁定时间内 list/get/search 无需再输入主密码
+
+全局选项:
+  --vault <名称|路径>          使用指定的保险库
+  --json                       以 JSON 输出
+  --password-stdin             从标准输入读取主密码 (标准输入不是终端时默认如此)
+
+退出码: 0 成功, 1 错误, 2 用法错误, 3 主密码错误, 4 未找到条目, 5 匹配到多个条目";
+
+/// Error with the process exit code it maps to
+struct CliError {
+    code: u8,
+    message: String,
+}
+
+impl CliError {
+    fn new(code: u8, message: impl Into<String>) -> Self {
+        CliError {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+impl From<String> for CliError {
+    fn from(message: String) -> Self {
+        CliError::new(EXIT_ERROR, message)
+    }
+}
+
+type CliResult<T = ()> = Result<T, CliError>;
+
+/// Entry as listed by `list` and `search`; passwords are only printed by `get`
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct EntrySummary<'a> {
+    id: &'a str,
+    title: &'a str,
+    username: &'a str,
+    url: &'a str,
+    group: Option<&'a str>,
+    is_favorite: bool,
+}
+
+/// Where read commands get their data from
+enum Reader {
+    Local(Database),
+    #[cfg(unix)]
+    Agent(PathBuf),
+}
+
+impl Reader {
+    fn open(args: &Args) -> CliResult<Self> {
+        #[cfg(unix)]
+        if let Some(path) = agent::unlocked_socket()? {
+            return Ok(Reader::Agent(path));
+        }
+        open_vault(args).map(Reader::Local)
+    }
+
+    fn entries(&self, filter: &EntryFilter) -> CliResult<Vec<Entry>> {
+        match self {
+            Reader::Local(db) => {
+                let conn = db.conn.lock().map_err(|e| e.to_string())?;
+                Ok(query_entries(&conn, filter)?)
+            }
+            #[cfg(unix)]
+            Reader::Agent(path) => agent::fetch(path, &Request::Entries { filter: filter.clone() }),
+        }
+    }
+
+    /// The entry with its secrets; the agent only lists entries without them
+    fn with_secrets(&self, entry: Entry) -> CliResult<Entry> {
+        match self {
+            Reader::Local(_) => Ok(entry),
+            #[cfg(unix)]
+            Reader::Agent(path) => agent::fetch_prompted(path, &Request::Entry { id: entry.id }),
+        }
+    }
+
+    fn groups(&self) -> CliResult<Vec<Group>> {
+        match self {
+            Reader::Local(db) => {
+                let conn = db.conn.lock().map_err(|e| e.to_string())?;
+                Ok(query_groups(&conn)?)
+            }
+            #[cfg(unix)]
+            Reader::Agent(path) => agent::fetch(path, &Request::Groups),
+        }
+    }
+}
+
+/// Entry point of the `op-cli` binary
+pub fn run() -> ExitCode {
+    let args = match Args::parse(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("错误: {}", e);
+            return ExitCode::from(EXIT_USAGE);
+        }
+    };
+
+    let Some(command) = args.command.clone() else {
+        println!("{}", USAGE);
+        return if args.flag("help") {
+            ExitCode::SUCCESS
+        } else {
+            ExitCode::from(EXIT_USAGE)
+        };
+    };
+    if args.flag("help") {
+        println!("{}", USAGE);
+        return ExitCode::SUCCESS;
+    }
+
+    match execute(&command, &args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("错误: {}", e.message);
+            ExitCode::from(e.code)
+        }
+    }
+}
+
+fn execute(command: &str, args: &Args) -> CliResult {
+    // The only command that doesn't need the vault
+    if command == "generate" {
+        return generate(args);
+    }
+
+    let allowed: &[&str] = match command {
+        "list" => &["group", "favorites", "type"],
+        "get" => &["field"],
+        "add" => &["group", "template", "url", "username", "password", "notes", "generate", "length"],
+        "edit" => &["title", "group", "url", "username", "password", "notes", "generate", "length"],
+        "rm" | "search" => &[],
+        "export" => &["output", "group", "fields", "favorites"],
+        "import" => &["overwrite", "dry-run"],
+        "agent" => &[],
+        _ => return Err(CliError::new(EXIT_USAGE, format!("未知命令: {}，使用 --help 查看用法", command))),
+    };
+    args.check_known(allowed).map_err(|e| CliError::new(EXIT_USAGE, e))?;
+
+    if command == "agent" {
+        #[cfg(unix)]
+        return agent::run(args);
+        #[cfg(not(unix))]
+        return Err(CliError::new(EXIT_ERROR, "此平台不支持解锁代理"));
+    }
+
+    // Reads are answered by a running unlock agent without asking for the password
+    if matches!(command, "list" | "get" | "search") {
+        let vault = Reader::open(args)?;
+        return match command {
+            "list" => list(&vault, args),
+            "get" => get(&vault, args),
+            _ => search(&vault, args),
+        };
+    }
+
+    let db = open_vault(args)?;
+    match command {
+        "add" => add(&db, args),
+        "edit" => edit(&db, args),
+        "rm" => remove(&db, args),
+        "export" => export(&db, args),
+        "import" => import(&db, args),
+        _ => unreachable!(),
+    }
+}
+
+/// Open the vault selected with `--vault` (or the active one) and check the master password
+fn open_vault(args: &Args) -> CliResult<Database> {
+    let path = vault_path()?;
+    if !path.exists() {
+        return Err(CliError::new(EXIT_ERROR, format!("保险库不存在: {}", path.display())));
+    }
+    let password = read_master_password(args)?;
+    unlock_vault(&path, &password)
+}
+
+fn unlock_vault(path: &Path, password: &str) -> CliResult<Database> {
+    let database = Database::new(path).map_err(|e| e.to_string())?;
+
+    let hash: String = {
+        let conn = database.conn.lock().map_err(|e| e.to_string())?;
+        conn.query_row(
+            "SELECT value FROM settings WHERE key = 'master_password_hash'",
+            [],
+            |row| row.get(0),
+        )
+        .map_err(|_| "保险库尚未设置主密码，请先在应用中完成设置".to_string())?
+    };
+
+    if !bcrypt::verify(password, &hash).map_err(|e| e.to_string())? {
+        return Err(CliError::new(EXIT_AUTH, "主密码不正确"));
+    }
+
+    Ok(database)
+}
+
+/// Location of the vault selected with `--vault`, or the active one
+fn vault_path() -> CliResult<PathBuf> {
+    Ok(db::vaults::startup_path(std::env::args().skip(1))?)
+}
+
+fn read_master_password(args: &Args) -> CliResult<String> {
+    let stdin = io::stdin();
+    if args.flag("password-stdin") || !stdin.is_terminal() {
+        let mut line = String::new();
+        stdin.lock().read_line(&mut line).map_err(|e| e.to_string())?;
+        return Ok(line.trim_end_matches(['\r', '\n']).to_string());
+    }
+
+    rpassword::prompt_password("主密码: ").map_err(|e| CliError::from(e.to_string()))
+}
+
+fn parse_entry_type(value: &str) -> Result<EntryType, CliError> {
+    EntryType::ALL
+        .into_iter()
+        .find(|t| t.as_str() == value)
+        .ok_or_else(|| CliError::new(EXIT_USAGE, format!("未知条目类型: {}", value)))
+}
+
+fn list(vault: &Reader, args: &Args) -> CliResult {
+    let groups = vault.groups()?;
+
+    let filter = EntryFilter {
+        group_ids: args.value("group").map(|g| find_group(&groups, g).map(|g| vec![g.id.clone()])).transpose()?,
+        favorites_only: args.flag("favorites").then_some(true),
+        entry_types: args.value("type").map(|t| parse_entry_type(t).map(|t| vec![t])).transpose()?,
+        ..Default::default()
+    };
+    print_summaries(&vault.entries(&filter)?, &groups, args)
+}
+
+fn search(vault: &Reader, args: &Args) -> CliResult {
+    let keyword = positional(args, "关键词")?;
+    let groups = vault.groups()?;
+
+    let filter = EntryFilter {
+        search: Some(keyword.to_string()),
+        ..Default::default()
+    };
+    print_summaries(&vault.entries(&filter)?, &groups, args)
+}
+
+fn get(vault: &Reader, args: &Args) -> CliResult {
+    let entry = find_entry(vault.entries(&EntryFilter::default())?, positional(args, "标题或ID")?)?;
+    let entry = vault.with_secrets(entry)?;
+
+    let Some(field) = args.value("field") else {
+        if args.flag("json") {
+            return print_json(&entry);
+        }
+        let groups = vault.groups()?;
+        let group = entry
+            .group_id
+            .as_ref()
+            .and_then(|id| groups.iter().find(|g| &g.id == id))
+            .map(|g| g.name.as_str())
+            .unwrap_or_default();
+        println!("标题:   {}", entry.title);
+        println!("分组:   {}", group);
+        println!("网址:   {}", entry.url);
+        println!("用户名: {}", entry.username);
+        println!("密码:   {}", entry.password);
+        println!("备注:   {}", entry.notes);
+        for field in &entry.fields {
+            println!("{}: {}", field.name, field.value);
+        }
+        return Ok(());
+    };
+
+    let value = match field {
+        "title" => &entry.title,
+        "url" => &entry.url,
+        "username" => &entry.username,
+        "password" => &entry.password,
+        "notes" => &entry.notes,
+        "id" => &entry.id,
+        // Typed and custom fields, e.g. `number` of a credit card
+        _ => match entry.fields.iter().find(|f| f.name == field) {
+            Some(f) => &f.value,
+            None => return Err(CliError::new(EXIT_USAGE, format!("未知字段: {}", field))),
+        },
+    };
+
+    if args.flag("json") {
+        print_json(&serde_json::json!({ field: value }))
+    } else {
+        // No trailing newline when piped, so `$(op-cli get ...)` and `| pbcopy` get the exact value
+        let mut stdout = io::stdout();
+        if stdout.is_terminal() {
+            writeln!(stdout, "{}", value)
+        } else {
+            write!(stdout, "{}", value)
+        }
+        .map_err(|e| CliError::from(e.to_string()))
+    }
+}
+
+fn add(db: &Database, args: &Args) -> CliResult {
+    let title = positional(args, "标题")?.to_string();
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+
+    let group_id = match args.value("group") {
+        Some(name) => Some(find_group(&query_groups(&conn)?, name)?.id.clone()),
+        None => None,
+    };
+    let template_id = match args.value("template") {
+        Some(name) => Some(find_template(&query_templates(&conn)?, name)?.id.clone()),
+        None => None,
+    };
+
+    let entry = insert_entry(
+        &conn,
+        CreateEntryDto {
+            entry_type: None,
+            group_id,
+            title,
+            url: args.value("url").map(str::to_string),
+            username: args.value("username").map(str::to_string),
+            password: new_password(args)?,
+            notes: args.value("notes").map(str::to_string),
+            url_match: None,
+            extra_urls: None,
+            fields: None,
+            template_id,
+            autotype_sequence: None,
+        },
+    )?;
+
+    print_changed("已添加", &entry, args)
+}
+
+fn edit(db: &Database, args: &Args) -> CliResult {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let entry = find_entry(query_entries(&conn, &EntryFilter::default())?, positional(args, "标题或ID")?)?;
+
+    // An empty --group moves the entry out of its group
+    let group_id = match args.value("group") {
+        Some("") => Some(None),
+        Some(name) => Some(Some(find_group(&query_groups(&conn)?, name)?.id.clone())),
+        None => None,
+    };
+
+    let update = UpdateEntryDto {
+        entry_type: None,
+        group_id,
+        title: args.value("title").map(str::to_string),
+        url: args.value("url").map(str::to_string),
+        username: args.value("username").map(str::to_string),
+        password: new_password(args)?,
+        notes: args.value("notes").map(str::to_string),
+        url_match: None,
+        extra_urls: None,
+        fields: None,
+        is_favorite: None,
+        sort_order: None,
+        autotype_sequence: None,
+    };
+    let entry = apply_entry_update(&conn, &entry.id, &update)?;
+
+    print_changed("已更新", &entry, args)
+}
+
+fn remove(db: &Database, args: &Args) -> CliResult {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let entry = find_entry(query_entries(&conn, &EntryFilter::default())?, positional(args, "标题或ID")?)?;
+
+    conn.execute("DELETE FROM entries WHERE id = ?1", [&entry.id])
+        .map_err(|e| e.to_string())?;
+
+    print_changed("已删除", &entry, args)
+}
+
+fn generate(args: &Args) -> CliResult {
+    args.check_known(&["length", "no-lowercase", "no-uppercase", "no-digits", "no-symbols"])
+        .map_err(|e| CliError::new(EXIT_USAGE, e))?;
+
+    let options = PasswordOptions {
+        length: length_option(args)?,
+        lowercase: !args.flag("no-lowercase"),
+        uppercase: !args.flag("no-uppercase"),
+        digits: !args.flag("no-digits"),
+        symbols: !args.flag("no-symbols"),
+    };
+    let password = generate_password(&options).map_err(|e| CliError::new(EXIT_USAGE, e))?;
+
+    if args.flag("json") {
+        print_json(&serde_json::json!({ "password": password }))
+    } else {
+        println!("{}", password);
+        Ok(())
+    }
+}
+
+fn export(db: &Database, args: &Args) -> CliResult {
+    let group_ids = match args.value("group") {
+        Some(name) => {
+            let conn = db.conn.lock().map_err(|e| e.to_string())?;
+            Some(vec![find_group(&query_groups(&conn)?, name)?.id.clone()])
+        }
+        None => None,
+    };
+
+    let fields = args
+        .value("fields")
+        .map(|list| {
+            list.split(',')
+                .map(|name| {
+                    serde_json::from_value::<ExportField>(serde_json::Value::String(name.trim().to_string()))
+                        .map_err(|_| CliError::new(EXIT_USAGE, format!("未知字段: {}", name.trim())))
+                })
+                .collect::<CliResult<Vec<_>>>()
+        })
+        .transpose()?;
+
+    let options = ExportOptions {
+        filter: EntryFilter {
+            group_ids,
+            favorites_only: args.flag("favorites").then_some(true),
+            ..Default::default()
+        },
+        fields,
+    };
+    let json = export_json(db, &options)?;
+
+    match args.value("output") {
+        Some(path) => fs::write(path, json).map_err(|e| CliError::from(format!("无法写入文件: {}", e))),
+        None => {
+            println!("{}", json);
+            Ok(())
+        }
+    }
+}
+
+fn import(db: &Database, args: &Args) -> CliResult {
+    let path = positional(args, "文件")?;
+    let content = fs::read_to_string(path).map_err(|e| format!("无法读取文件: {}", e))?;
+
+    let result = import_json(db, &content, !args.flag("overwrite"), args.flag("dry-run"))?;
+    if args.flag("json") {
+        return print_json(&result);
+    }
+
+    let prefix = if result.dry_run { "预览: " } else { "" };
+    println!(
+        "{}导入分组 {} 个 (跳过 {}), 条目 {} 个 (跳过 {})",
+        prefix, result.groups_imported, result.groups_skipped, result.entries_imported, result.entries_skipped
+    );
+    if result.groups_removed > 0 || result.entries_removed > 0 {
+        println!(
+            "{}移除原有分组 {} 个, 条目 {} 个",
+            prefix, result.groups_removed, result.entries_removed
+        );
+    }
+    Ok(())
+}
+
+/// Helper: the entry with this id, or the only entry with this title (case-insensitive)
+fn find_entry(entries: Vec<Entry>, query: &str) -> CliResult<Entry> {
+    if let Some(entry) = entries.iter().find(|e| e.id == query) {
+        return Ok(entry.clone());
+    }
+
+    let mut matches: Vec<Entry> = entries
+        .into_iter()
+        .filter(|e| e.title.to_lowercase() == query.to_lowercase())
+        .collect();
+    match matches.len() {
+        0 => Err(CliError::new(EXIT_NOT_FOUND, format!("未找到条目: {}", query))),
+        1 => Ok(matches.remove(0)),
+        n => {
+            let ids: Vec<&str> = matches.iter().map(|e| e.id.as_str()).collect();
+            Err(CliError::new(
+                EXIT_AMBIGUOUS,
+                format!("有 {} 个条目名为 \"{}\"，请改用ID: {}", n, query, ids.join(", ")),
+            ))
+        }
+    }
+}
+
+fn find_group<'a>(groups: &'a [Group], name: &str) -> CliResult<&'a Group> {
+    groups
+        .iter()
+        .find(|g| g.id == name || g.name == name)
+        .ok_or_else(|| CliError::new(EXIT_NOT_FOUND, format!("未找到分组: {}", name)))
+}
+
+fn find_template<'a>(templates: &'a [EntryTemplate], name: &str) -> CliResult<&'a EntryTemplate> {
+    templates
+        .iter()
+        .find(|t| t.id == name || t.name == name)
+        .ok_or_else(|| CliError::new(EXIT_NOT_FOUND, format!("未找到模板: {}", name)))
+}
+
+fn positional<'a>(args: &'a Args, name: &str) -> CliResult<&'a str> {
+    match args.positional.as_slice() {
+        [value] => Ok(value),
+        [] => Err(CliError::new(EXIT_USAGE, format!("缺少参数: <{}>", name))),
+        _ => Err(CliError::new(EXIT_USAGE, "参数过多，包含空格的值请加引号")),
+    }
+}
+
+/// Helper: the entry password from `--password` or `--generate`
+fn new_password(args: &Args) -> CliResult<Option<String>> {
+    match (args.value("password"), args.flag("generate")) {
+        (Some(_), true) => Err(CliError::new(EXIT_USAGE, "--password 与 --generate 不能同时使用")),
+        (Some(password), false) => Ok(Some(password.to_string())),
+        (None, true) => {
+            let options = PasswordOptions {
+                length: length_option(args)?,
+                ..Default::default()
+            };
+            Ok(Some(generate_password(&options).map_err(|e| CliError::new(EXIT_USAGE, e))?))
+        }
+        (None, false) => Ok(None),
+    }
+}
+
+fn length_option(args: &Args) -> CliResult<usize> {
+    match args.value("length") {
+        Some(value) => value
+            .parse()
+            .map_err(|_| CliError::new(EXIT_USAGE, format!("无效的长度: {}", value))),
+        None => Ok(PasswordOptions::default().length),
+    }
+}
+
+fn print_summaries(entries: &[Entry], groups: &[Group], args: &Args) -> CliResult {
+    let summaries: Vec<EntrySummary> = entries
+        .iter()
+        .map(|entry| EntrySummary {
+            id: &entry.id,
+            title: &entry.title,
+            username: &entry.username,
+            url: &entry.url,
+            group: entry
+                .group_id
+                .as_ref()
+                .and_then(|id| groups.iter().find(|g| &g.id == id))
+                .map(|g| g.name.as_str()),
+            is_favorite: entry.is_favorite,
+        })
+        .collect();
+
+    if args.flag("json") {
+        return print_json(&summaries);
+    }
+
+    // Tab separated so the output works with cut and awk
+    for summary in &summaries {
+        println!(
+            "{}\t{}\t{}\t{}\t{}",
+            summary.id,
+            summary.title,
+            summary.username,
+            summary.url,
+            summary.group.unwrap_or_default()
+        );
+    }
+    Ok(())
+}
+
+fn print_changed(action: &str, entry: &Entry, args: &Args) -> CliResult {
+    if args.flag("json") {
+        print_json(&serde_json::json!({ "id": entry.id, "title": entry.title }))
+    } else {
+        println!("{} {} ({})", action, entry.title, entry.id);
+        Ok(())
+    }
+}
+
+fn print_json<T: Serialize + ?Sized>(value: &T) -> CliResult {
+    let json = serde_json::to_string_pretty(value).map_err(|e| e.to_string())?;
+    println!("{}", json);
+    Ok(())
+}
@@ -0,0 +1,36 @@
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+use crate::db::Database;
+use crate::settings;
+
+/// Copy a secret and clear it after `clear_clipboard_seconds` unless something else was copied meanwhile
+pub fn copy_secret(app: &AppHandle, text: &str) -> Result<(), String> {
+    app.clipboard()
+        .write_text(text.to_string())
+        .map_err(|e| format!("复制失败: {}", e))?;
+
+    let seconds = clear_delay(app);
+    if seconds == 0 {
+        return Ok(());
+    }
+
+    let app = app.clone();
+    let text = text.to_string();
+    thread::spawn(move || {
+        thread::sleep(Duration::from_secs(seconds));
+        if app.clipboard().read_text().is_ok_and(|current| current == text) {
+            let _ = app.clipboard().write_text(String::new());
+        }
+    });
+    Ok(())
+}
+
+fn clear_delay(app: &AppHandle) -> u64 {
+    let db = app.state::<Database>();
+    let Ok(conn) = db.conn.lock() else {
+        return 30;
+    };
+    settings::get_int(&conn, "clear_clipboard_seconds").max(0) as u64
+}
@@ -1,5 +1,10 @@
-use tauri::State;
+use rusqlite::Connection;
+use tauri::{AppHandle, Emitter, Manager, State};
+use crate::backup::keys;
+use crate::crypto::{self, Session, VaultKey, SALT_LEN};
 use crate::db::Database;
+use crate::sync::scheduler as sync_scheduler;
+use crate::sync::webdav;
 
 #[tauri::command]
 pub fn check_initialized(db: State<Database>) -> Result<bool, String> {
@@ -15,10 +20,11 @@ pub fn check_initialized(db: State<Database>) -> Result<bool, String> {
 }
 
 #[tauri::command]
-pub fn setup_password(db: State<Database>, password: String) -> Result<(), String> {
+pub fn setup_password(app: AppHandle, db: State<Database>, session: State<Session>, password: String) -> Result<(), String> {
     if password.len() < 4 {
         return Err("密码长度不能少于4位".to_string());
     }
+    crate::policy::current().check_master_password(&password)?;
 
     let hash = bcrypt::hash(&password, 10).map_err(|e| e.to_string())?;
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
@@ -29,11 +35,19 @@ pub fn setup_password(db: State<Database>, password: String) -> Result<(), Strin
     )
     .map_err(|e| e.to_string())?;
 
+    let key = derive_vault_key(&conn, &password)?;
+    keys::ensure(&conn, &key)?;
+    session.set_key(key);
+    drop(conn);
+    if session.unlock() {
+        notify_lock_state(&app, true);
+    }
+
     Ok(())
 }
 
 #[tauri::command]
-pub fn verify_password(db: State<Database>, password: String) -> Result<bool, String> {
+pub fn verify_password(app: AppHandle, db: State<Database>, session: State<Session>, password: String) -> Result<bool, String> {
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
     let hash: String = conn
         .query_row(
@@ -43,17 +57,54 @@ pub fn verify_password(db: State<Database>, password: String) -> Result<bool, St
         )
         .map_err(|_| "未设置主密码".to_string())?;
 
-    bcrypt::verify(&password, &hash).map_err(|e| e.to_string())
+    let is_valid = bcrypt::verify(&password, &hash).map_err(|e| e.to_string())?;
+    if is_valid {
+        let key = derive_vault_key(&conn, &password)?;
+        keys::ensure(&conn, &key)?;
+        session.set_key(key);
+        drop(conn);
+        if session.unlock() {
+            notify_lock_state(&app, true);
+        }
+    }
+
+    Ok(is_valid)
+}
+
+#[tauri::command]
+pub fn get_lock_state(session: State<Session>) -> bool {
+    session.is_unlocked()
+}
+
+/// Mark the vault as locked, e.g. after auto-lock
+#[tauri::command]
+pub fn lock_session(app: AppHandle) {
+    lock_app(&app);
+}
+
+/// Lock the vault from anywhere (tray, shortcut, CLI, D-Bus) and tell every window
+pub(crate) fn lock_app(app: &AppHandle) {
+    if app.state::<Session>().lock() {
+        notify_lock_state(app, false);
+    }
+}
+
+/// Windows follow the backend's lock state through `lock-state-changed`
+fn notify_lock_state(app: &AppHandle, unlocked: bool) {
+    let _ = app.emit("lock-state-changed", unlocked);
+    crate::refresh_tray(app);
 }
 
 #[tauri::command]
 pub fn change_password(
+    app: AppHandle,
     db: State<Database>,
+    session: State<Session>,
     old_password: String,
     new_password: String,
 ) -> Result<(), String> {
     // Verify old password first
-    let is_valid = verify_password(db.clone(), old_password)?;
+    let is_valid = verify_password(app, db.clone(), session.clone(), old_password)?;
     if !is_valid {
         return Err("旧密码不正确".to_string());
     }
@@ -61,15 +112,58 @@ pub fn change_password(
     if new_password.len() < 4 {
         return Err("新密码长度不能少于4位".to_string());
     }
+    crate::policy::current().check_master_password(&new_password)?;
 
     let hash = bcrypt::hash(&new_password, 10).map_err(|e| e.to_string())?;
-    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let old_key = session.key().ok_or("请先解锁")?;
+
+    // No sync may run with the old key while it changes; paused before locking the database
+    let _sync = sync_scheduler::pause()?;
+    let new_key = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        derive_vault_key(&conn, &new_password)?
+    };
 
+    // Other devices need the new password to read this device's log, so the sync target
+    // moves to the new key first; a failure leaves the password unchanged
+    sync_scheduler::rekey(&db, &old_key, &new_key)?;
+
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
     conn.execute(
         "INSERT OR REPLACE INTO settings (key, value) VALUES ('master_password_hash', ?1)",
         [&hash],
     )
     .map_err(|e| e.to_string())?;
 
+    webdav::reencrypt_credentials(&conn, &old_key, &new_key)?;
+    keys::ensure(&conn, &new_key)?;
+    session.set_key(new_key);
+
     Ok(())
 }
+
+/// Derive the vault encryption key, creating the salt on first use
+pub(crate) fn derive_vault_key(conn: &Connection, password: &str) -> Result<VaultKey, String> {
+    let stored: Option<String> = conn
+        .query_row(
+            "SELECT value FROM settings WHERE key = 'vault_salt'",
+            [],
+            |row| row.get(0),
+        )
+        .ok();
+
+    let salt: [u8; SALT_LEN] = match stored.as_deref().and_then(crypto::salt_from_hex) {
+        Some(salt) => salt,
+        None => {
+            let salt = crypto::random_salt();
+            conn.execute(
+                "INSERT OR REPLACE INTO settings (key, value) VALUES ('vault_salt', ?1)",
+                [crypto::to_hex(&salt)],
+            )
+            .map_err(|e| e.to_string())?;
+            salt
+        }
+    };
+
+    crypto::derive_key(password, &salt)
+}
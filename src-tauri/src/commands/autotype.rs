@@ -0,0 +1,58 @@
+use serde_json::{Map, Value};
+use tauri::{AppHandle, State};
+use crate::autotype;
+use crate::commands::entries::{entry_from_row, record_use, ENTRY_COLUMNS};
+use crate::commands::settings::change_settings;
+use crate::crypto::Session;
+use crate::db::Database;
+
+/// Type an entry into the window that was focused before Quick Access opened
+#[tauri::command]
+pub fn autotype_entry(app: AppHandle, db: State<Database>, session: State<Session>, id: String) -> Result<(), String> {
+    if !session.is_unlocked() {
+        return Err("密码库已锁定".to_string());
+    }
+    session.touch();
+
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let entry = conn
+        .query_row(
+            &format!("SELECT {} FROM entries WHERE id = ?1", ENTRY_COLUMNS),
+            [&id],
+            entry_from_row,
+        )
+        .map_err(|_| "条目不存在".to_string())?;
+    let sequence = autotype::resolve_sequence(&conn, &entry)?;
+
+    autotype::perform(&app, &entry, &sequence)?;
+
+    record_use(&conn, &id)
+}
+
+/// Sequence auto-type would use for the entry, after group and default fallbacks
+#[tauri::command]
+pub fn get_autotype_sequence(db: State<Database>, id: String) -> Result<String, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let entry = conn
+        .query_row(
+            &format!("SELECT {} FROM entries WHERE id = ?1", ENTRY_COLUMNS),
+            [&id],
+            entry_from_row,
+        )
+        .map_err(|_| "条目不存在".to_string())?;
+    autotype::resolve_sequence(&conn, &entry)
+}
+
+#[tauri::command]
+pub fn get_default_autotype_sequence(db: State<Database>) -> Result<String, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    Ok(autotype::default_sequence(&conn))
+}
+
+/// Sequence for entries and groups without their own; empty restores the built-in default
+#[tauri::command]
+pub fn set_default_autotype_sequence(app: AppHandle, sequence: String) -> Result<(), String> {
+    let mut changes = Map::new();
+    changes.insert("autotypeDefaultSequence".to_string(), Value::from(sequence));
+    change_settings(&app, &changes)
+}
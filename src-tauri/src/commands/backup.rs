@@ -0,0 +1,69 @@
+use std::path::PathBuf;
+use tauri::State;
+use crate::backup::{archive, keys, scheduler, BackupInfo};
+use crate::commands::settings::read_backup_settings;
+use crate::crypto::Session;
+use crate::db::Database;
+
+/// Write a backup right away, regardless of the schedule
+#[tauri::command]
+pub fn backup_now(db: State<Database>) -> Result<BackupInfo, String> {
+    let (settings, backup_key) = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        (read_backup_settings(&conn), keys::load(&conn)?)
+    };
+    let backup_key = backup_key.ok_or("请先解锁后再备份")?;
+
+    let dir = archive::backup_dir(&db, &settings);
+    let info = archive::create_backup(&db, &backup_key, &dir)?;
+    scheduler::prune(&dir, &settings)?;
+
+    Ok(info)
+}
+
+/// List backups in the configured directory, newest first
+#[tauri::command]
+pub fn list_backups(db: State<Database>) -> Result<Vec<BackupInfo>, String> {
+    let settings = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        read_backup_settings(&conn)
+    };
+
+    archive::list_backups(&archive::backup_dir(&db, &settings))
+}
+
+/// Restore the database from a backup
+/// password: the master password at the time the backup was written
+#[tauri::command]
+pub fn restore_backup(
+    db: State<Database>,
+    session: State<Session>,
+    file_name: String,
+    password: String,
+) -> Result<(), String> {
+    let (settings, backup_key) = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        (read_backup_settings(&conn), keys::load(&conn)?)
+    };
+    let dir = archive::backup_dir(&db, &settings);
+
+    // Only accept names of backups inside the backup directory
+    if !archive::list_backups(&dir)?.iter().any(|b| b.file_name == file_name) {
+        return Err("备份文件不存在".to_string());
+    }
+    let path: PathBuf = dir.join(&file_name);
+
+    // Keep the current state so the restore itself can be undone
+    if let Some(backup_key) = backup_key {
+        archive::create_backup(&db, &backup_key, &dir)?;
+    }
+
+    let key = archive::restore_backup(&db, &path, &password)?;
+    {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        keys::ensure(&conn, &key)?;
+    }
+    session.set_key(key);
+
+    Ok(())
+}
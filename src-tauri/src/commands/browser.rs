@@ -0,0 +1,42 @@
+use tauri::State;
+use crate::db::Database;
+use crate::models::BrowserPairing;
+
+/// Browser extensions paired with this device
+#[tauri::command]
+pub fn list_browser_pairings(db: State<Database>) -> Result<Vec<BrowserPairing>, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT extension_id, browser, created_at, last_used_at
+             FROM browser_pairings ORDER BY created_at",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let pairings = stmt
+        .query_map([], |row| {
+            Ok(BrowserPairing {
+                extension_id: row.get(0)?,
+                browser: row.get(1)?,
+                created_at: row.get(2)?,
+                last_used_at: row.get(3)?,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok(pairings)
+}
+
+/// Revoke an extension; it has to pair again before requesting credentials
+#[tauri::command]
+pub fn remove_browser_pairing(db: State<Database>, extension_id: String) -> Result<(), String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    conn.execute(
+        "DELETE FROM browser_pairings WHERE extension_id = ?1",
+        [&extension_id],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
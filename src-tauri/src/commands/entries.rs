@@ -1,62 +1,90 @@
+use rusqlite::Connection;
 use tauri::State;
+use crate::autotype;
+use crate::commands::templates;
 use crate::db::Database;
-use crate::models::{Entry, CreateEntryDto, UpdateEntryDto};
+use crate::entry_types;
+use crate::models::{
+    Entry, CreateEntryDto, EntryField, EntryFilter, EntryMatch, EntrySort, EntryType, EntryTypeSchema, EntryUrl,
+    EntryUsage, SearchHit, UpdateEntryDto, UrlMatch,
+};
+use crate::search::SearchIndex;
+use crate::settings;
+use crate::urlmatch;
 
-#[tauri::command]
-pub fn get_entries(
-    db: State<Database>,
-    group_id: Option<String>,
-    search: Option<String>,
-    favorites_only: Option<bool>,
-) -> Result<Vec<Entry>, String> {
-    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+pub(crate) const ENTRY_COLUMNS: &str =
+    "id, group_id, title, url, username, password, notes, is_favorite, sort_order, created_at, updated_at, url_match, extra_urls, entry_type, fields, autotype_sequence";
 
-    let mut sql = String::from(
-        "SELECT id, group_id, title, url, username, password, notes, is_favorite, sort_order, created_at, updated_at
-         FROM entries WHERE 1=1"
-    );
+/// Map a row selected with `ENTRY_COLUMNS` to an `Entry`
+pub(crate) fn entry_from_row(row: &rusqlite::Row) -> rusqlite::Result<Entry> {
+    Ok(Entry {
+        id: row.get(0)?,
+        entry_type: EntryType::parse(&row.get::<_, String>(13)?),
+        group_id: row.get(1)?,
+        title: row.get(2)?,
+        url: row.get(3)?,
+        username: row.get(4)?,
+        password: row.get(5)?,
+        notes: row.get(6)?,
+        url_match: UrlMatch::parse(&row.get::<_, String>(11)?),
+        extra_urls: serde_json::from_str(&row.get::<_, String>(12)?).unwrap_or_default(),
+        fields: serde_json::from_str(&row.get::<_, String>(14)?).unwrap_or_default(),
+        autotype_sequence: row.get(15)?,
+        is_favorite: row.get::<_, i32>(7)? != 0,
+        sort_order: row.get(8)?,
+        created_at: row.get(9)?,
+        updated_at: row.get(10)?,
+    })
+}
+
+/// Query entries matching the given filter
+pub(crate) fn query_entries(conn: &Connection, filter: &EntryFilter) -> Result<Vec<Entry>, String> {
+    let mut sql = format!("SELECT {} FROM entries WHERE 1=1", ENTRY_COLUMNS);
     let mut params: Vec<Box<dyn rusqlite::types::ToSql>> = Vec::new();
 
-    if let Some(ref gid) = group_id {
-        sql.push_str(" AND group_id = ?");
-        params.push(Box::new(gid.clone()));
+    if let Some(ref group_ids) = filter.group_ids {
+        sql.push_str(&format!(" AND group_id IN ({})", placeholders(group_ids.len())));
+        for gid in group_ids {
+            params.push(Box::new(gid.clone()));
+        }
+    }
+
+    if let Some(ref entry_types) = filter.entry_types {
+        sql.push_str(&format!(" AND entry_type IN ({})", placeholders(entry_types.len())));
+        for entry_type in entry_types {
+            params.push(Box::new(entry_type.as_str()));
+        }
     }
 
-    if let Some(true) = favorites_only {
+    if let Some(ref entry_ids) = filter.entry_ids {
+        sql.push_str(&format!(" AND id IN ({})", placeholders(entry_ids.len())));
+        for id in entry_ids {
+            params.push(Box::new(id.clone()));
+        }
+    }
+
+    if let Some(true) = filter.favorites_only {
         sql.push_str(" AND is_favorite = 1");
     }
 
-    if let Some(ref keyword) = search {
+    if let Some(ref keyword) = filter.search {
         if !keyword.is_empty() {
-            sql.push_str(" AND (title LIKE ? OR url LIKE ? OR username LIKE ?)");
+            sql.push_str(" AND (title LIKE ? OR url LIKE ? OR username LIKE ? OR extra_urls LIKE ?)");
             let pattern = format!("%{}%", keyword);
             params.push(Box::new(pattern.clone()));
             params.push(Box::new(pattern.clone()));
+            params.push(Box::new(pattern.clone()));
             params.push(Box::new(pattern));
         }
     }
 
-    sql.push_str(" ORDER BY sort_order ASC, updated_at DESC");
+    sql.push_str(&format!(" ORDER BY {}", filter.sort.order_by()));
 
     let params_refs: Vec<&dyn rusqlite::types::ToSql> = params.iter().map(|p| p.as_ref()).collect();
 
     let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
     let entries = stmt
-        .query_map(params_refs.as_slice(), |row| {
-            Ok(Entry {
-                id: row.get(0)?,
-                group_id: row.get(1)?,
-                title: row.get(2)?,
-                url: row.get(3)?,
-                username: row.get(4)?,
-                password: row.get(5)?,
-                notes: row.get(6)?,
-                is_favorite: row.get::<_, i32>(7)? != 0,
-                sort_order: row.get(8)?,
-                created_at: row.get(9)?,
-                updated_at: row.get(10)?,
-            })
-        })
+        .query_map(params_refs.as_slice(), entry_from_row)
         .map_err(|e| e.to_string())?
         .collect::<Result<Vec<_>, _>>()
         .map_err(|e| e.to_string())?;
@@ -64,131 +92,264 @@ pub fn get_entries(
     Ok(entries)
 }
 
-#[tauri::command]
-pub fn create_entry(db: State<Database>, entry: CreateEntryDto) -> Result<Entry, String> {
-    let conn = db.conn.lock().map_err(|e| e.to_string())?;
-    let id = uuid::Uuid::new_v4().to_string();
-    let now = chrono::Utc::now().timestamp();
-
-    let url = entry.url.unwrap_or_default();
-    let username = entry.username.unwrap_or_default();
-    let password = entry.password.unwrap_or_default();
-    let notes = entry.notes.unwrap_or_default();
-
+/// Insert an entry or replace the stored copy with the same id
+pub(crate) fn upsert_entry(conn: &Connection, entry: &Entry) -> Result<(), String> {
     conn.execute(
-        "INSERT INTO entries (id, group_id, title, url, username, password, notes, created_at, updated_at)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        "INSERT INTO entries (id, group_id, title, url, username, password, notes, is_favorite, sort_order, created_at, updated_at, url_match, extra_urls, entry_type, fields, autotype_sequence)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)
+         ON CONFLICT(id) DO UPDATE SET
+             group_id = excluded.group_id,
+             title = excluded.title,
+             url = excluded.url,
+             username = excluded.username,
+             password = excluded.password,
+             notes = excluded.notes,
+             is_favorite = excluded.is_favorite,
+             sort_order = excluded.sort_order,
+             created_at = excluded.created_at,
+             updated_at = excluded.updated_at,
+             url_match = excluded.url_match,
+             extra_urls = excluded.extra_urls,
+             entry_type = excluded.entry_type,
+             fields = excluded.fields,
+             autotype_sequence = excluded.autotype_sequence",
         rusqlite::params![
-            id,
+            entry.id,
             entry.group_id,
             entry.title,
-            url,
-            username,
-            password,
-            notes,
-            now,
-            now,
+            entry.url,
+            entry.username,
+            entry.password,
+            entry.notes,
+            entry.is_favorite as i32,
+            entry.sort_order,
+            entry.created_at,
+            entry.updated_at,
+            entry.url_match.as_str(),
+            extra_urls_json(&entry.extra_urls)?,
+            entry.entry_type.as_str(),
+            fields_json(&entry.fields)?,
+            entry.autotype_sequence,
         ],
     )
     .map_err(|e| e.to_string())?;
 
-    Ok(Entry {
-        id,
+    Ok(())
+}
+
+/// Insert a new entry
+pub(crate) fn insert_entry(conn: &Connection, mut entry: CreateEntryDto) -> Result<Entry, String> {
+    if let Some(template_id) = entry.template_id.take() {
+        let template = templates::find_template(conn, &template_id)?;
+        templates::apply_template(&template, &mut entry)?;
+    }
+
+    let now = chrono::Utc::now().timestamp();
+    let entry = Entry {
+        id: uuid::Uuid::new_v4().to_string(),
+        entry_type: entry.entry_type.unwrap_or_default(),
         group_id: entry.group_id,
         title: entry.title,
-        url,
-        username,
-        password,
-        notes,
+        url: entry.url.unwrap_or_default(),
+        username: entry.username.unwrap_or_default(),
+        password: entry.password.unwrap_or_default(),
+        notes: entry.notes.unwrap_or_default(),
+        url_match: entry.url_match.unwrap_or_default(),
+        extra_urls: entry.extra_urls.unwrap_or_default(),
+        fields: entry.fields.unwrap_or_default(),
+        autotype_sequence: entry.autotype_sequence.filter(|s| !s.trim().is_empty()),
         is_favorite: false,
         sort_order: 0,
         created_at: now,
         updated_at: now,
-    })
+    };
+    validate_entry(&entry)?;
+
+    conn.execute(
+        "INSERT INTO entries (id, entry_type, group_id, title, url, username, password, notes, url_match, extra_urls, fields, autotype_sequence, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+        rusqlite::params![
+            entry.id,
+            entry.entry_type.as_str(),
+            entry.group_id,
+            entry.title,
+            entry.url,
+            entry.username,
+            entry.password,
+            entry.notes,
+            entry.url_match.as_str(),
+            extra_urls_json(&entry.extra_urls)?,
+            fields_json(&entry.fields)?,
+            entry.autotype_sequence,
+            now,
+            now,
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(entry)
 }
 
-#[tauri::command]
-pub fn update_entry(
-    db: State<Database>,
-    id: String,
-    entry: UpdateEntryDto,
-) -> Result<Entry, String> {
-    let conn = db.conn.lock().map_err(|e| e.to_string())?;
-    let now = chrono::Utc::now().timestamp();
+/// Apply the set fields of an update and return the updated entry
+pub(crate) fn apply_entry_update(conn: &Connection, id: &str, entry: &UpdateEntryDto) -> Result<Entry, String> {
+    let current = conn
+        .query_row(
+            &format!("SELECT {} FROM entries WHERE id = ?1", ENTRY_COLUMNS),
+            [id],
+            entry_from_row,
+        )
+        .map_err(|e| e.to_string())?;
 
-    // Build dynamic UPDATE query
-    let mut sets: Vec<String> = Vec::new();
-    let mut params: Vec<Box<dyn rusqlite::types::ToSql>> = Vec::new();
+    // Validation sees the entry as it will be stored
+    let updated = Entry {
+        id: current.id,
+        entry_type: entry.entry_type.unwrap_or(current.entry_type),
+        group_id: entry.group_id.clone().unwrap_or(current.group_id),
+        title: entry.title.clone().unwrap_or(current.title),
+        url: entry.url.clone().unwrap_or(current.url),
+        username: entry.username.clone().unwrap_or(current.username),
+        password: entry.password.clone().unwrap_or(current.password),
+        notes: entry.notes.clone().unwrap_or(current.notes),
+        url_match: entry.url_match.unwrap_or(current.url_match),
+        extra_urls: entry.extra_urls.clone().unwrap_or(current.extra_urls),
+        fields: entry.fields.clone().unwrap_or(current.fields),
+        autotype_sequence: entry
+            .autotype_sequence
+            .clone()
+            .map(|s| s.filter(|s| !s.trim().is_empty()))
+            .unwrap_or(current.autotype_sequence),
+        is_favorite: entry.is_favorite.unwrap_or(current.is_favorite),
+        sort_order: entry.sort_order.unwrap_or(current.sort_order),
+        created_at: current.created_at,
+        updated_at: chrono::Utc::now().timestamp(),
+    };
+    validate_entry(&updated)?;
 
-    if let Some(ref group_id) = entry.group_id {
-        sets.push("group_id = ?".to_string());
-        params.push(Box::new(group_id.clone()));
-    }
-    if let Some(ref title) = entry.title {
-        sets.push("title = ?".to_string());
-        params.push(Box::new(title.clone()));
-    }
-    if let Some(ref url) = entry.url {
-        sets.push("url = ?".to_string());
-        params.push(Box::new(url.clone()));
-    }
-    if let Some(ref username) = entry.username {
-        sets.push("username = ?".to_string());
-        params.push(Box::new(username.clone()));
-    }
-    if let Some(ref password) = entry.password {
-        sets.push("password = ?".to_string());
-        params.push(Box::new(password.clone()));
-    }
-    if let Some(ref notes) = entry.notes {
-        sets.push("notes = ?".to_string());
-        params.push(Box::new(notes.clone()));
+    conn.execute(
+        "UPDATE entries SET entry_type = ?1, group_id = ?2, title = ?3, url = ?4, username = ?5, password = ?6,
+             notes = ?7, url_match = ?8, extra_urls = ?9, fields = ?10, autotype_sequence = ?11, is_favorite = ?12,
+             sort_order = ?13, updated_at = ?14
+         WHERE id = ?15",
+        rusqlite::params![
+            updated.entry_type.as_str(),
+            updated.group_id,
+            updated.title,
+            updated.url,
+            updated.username,
+            updated.password,
+            updated.notes,
+            updated.url_match.as_str(),
+            extra_urls_json(&updated.extra_urls)?,
+            fields_json(&updated.fields)?,
+            updated.autotype_sequence,
+            updated.is_favorite as i32,
+            updated.sort_order,
+            updated.updated_at,
+            id,
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(updated)
+}
+
+/// Check the type's fields and reject regex match rules that don't compile, so they don't silently never match
+pub(crate) fn validate_entry(entry: &Entry) -> Result<(), String> {
+    entry_types::validate(entry)?;
+    if let Some(sequence) = &entry.autotype_sequence {
+        autotype::sequence::parse(sequence)?;
     }
-    if let Some(is_favorite) = entry.is_favorite {
-        sets.push("is_favorite = ?".to_string());
-        params.push(Box::new(is_favorite as i32));
+
+    let rules = std::iter::once((entry.url.as_str(), entry.url_match)).chain(
+        entry
+            .extra_urls
+            .iter()
+            .map(|u| (u.url.as_str(), u.match_mode.unwrap_or(entry.url_match))),
+    );
+    for (url, mode) in rules {
+        if mode == UrlMatch::Regex && !url.is_empty() {
+            urlmatch::compile_regex(url).map_err(|e| format!("无效的网址正则表达式 \"{}\": {}", url, e))?;
+        }
     }
-    if let Some(sort_order) = entry.sort_order {
-        sets.push("sort_order = ?".to_string());
-        params.push(Box::new(sort_order));
+    Ok(())
+}
+
+fn extra_urls_json(extra_urls: &[EntryUrl]) -> Result<String, String> {
+    serde_json::to_string(extra_urls).map_err(|e| e.to_string())
+}
+
+fn fields_json(fields: &[EntryField]) -> Result<String, String> {
+    serde_json::to_string(fields).map_err(|e| e.to_string())
+}
+
+/// Build `?, ?, ?` for an IN clause; an empty list matches nothing
+fn placeholders(count: usize) -> String {
+    if count == 0 {
+        "NULL".to_string()
+    } else {
+        vec!["?"; count].join(", ")
     }
+}
 
-    sets.push("updated_at = ?".to_string());
-    params.push(Box::new(now));
+#[tauri::command]
+pub fn get_entries(
+    db: State<Database>,
+    group_id: Option<String>,
+    search: Option<String>,
+    favorites_only: Option<bool>,
+    entry_type: Option<EntryType>,
+    sort: Option<EntrySort>,
+) -> Result<Vec<Entry>, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
 
-    params.push(Box::new(id.clone()));
+    let filter = EntryFilter {
+        group_ids: group_id.map(|gid| vec![gid]),
+        entry_types: entry_type.map(|t| vec![t]),
+        entry_ids: None,
+        search,
+        favorites_only,
+        sort: sort.unwrap_or_default(),
+    };
 
-    let sql = format!("UPDATE entries SET {} WHERE id = ?", sets.join(", "));
-    let params_refs: Vec<&dyn rusqlite::types::ToSql> = params.iter().map(|p| p.as_ref()).collect();
+    query_entries(&conn, &filter)
+}
 
-    conn.execute(&sql, params_refs.as_slice())
-        .map_err(|e| e.to_string())?;
+#[tauri::command]
+pub fn get_entry(db: State<Database>, id: String) -> Result<Entry, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    conn.query_row(
+        &format!("SELECT {} FROM entries WHERE id = ?1", ENTRY_COLUMNS),
+        [&id],
+        entry_from_row,
+    )
+    .map_err(|_| "条目不存在".to_string())
+}
 
-    // Return updated entry
-    let entry = conn
-        .query_row(
-            "SELECT id, group_id, title, url, username, password, notes, is_favorite, sort_order, created_at, updated_at
-             FROM entries WHERE id = ?1",
-            [&id],
-            |row| {
-                Ok(Entry {
-                    id: row.get(0)?,
-                    group_id: row.get(1)?,
-                    title: row.get(2)?,
-                    url: row.get(3)?,
-                    username: row.get(4)?,
-                    password: row.get(5)?,
-                    notes: row.get(6)?,
-                    is_favorite: row.get::<_, i32>(7)? != 0,
-                    sort_order: row.get(8)?,
-                    created_at: row.get(9)?,
-                    updated_at: row.get(10)?,
-                })
-            },
-        )
-        .map_err(|e| e.to_string())?;
+/// Fuzzy search for Quick Access, answered from the in-memory index without secret fields
+#[tauri::command]
+pub fn quick_search(
+    db: State<Database>,
+    index: State<SearchIndex>,
+    query: String,
+    limit: Option<usize>,
+) -> Result<Vec<SearchHit>, String> {
+    index.search(&db, &query, limit)
+}
 
-    Ok(entry)
+#[tauri::command]
+pub fn create_entry(db: State<Database>, entry: CreateEntryDto) -> Result<Entry, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    insert_entry(&conn, entry)
+}
+
+#[tauri::command]
+pub fn update_entry(
+    db: State<Database>,
+    id: String,
+    entry: UpdateEntryDto,
+) -> Result<Entry, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    apply_entry_update(&conn, &id, &entry)
 }
 
 #[tauri::command]
@@ -220,3 +381,101 @@ pub fn toggle_favorite(db: State<Database>, id: String) -> Result<bool, String>
 
     Ok(new_val != 0)
 }
+
+/// Record that an entry's secret was copied, revealed or typed
+/// Only when usage tracking is on: the time, the count, and the entry for the copy-last-password shortcut.
+pub(crate) fn record_use(conn: &Connection, id: &str) -> Result<(), String> {
+    if !settings::get_bool(conn, "track_usage") {
+        return Ok(());
+    }
+
+    conn.execute(
+        "INSERT OR REPLACE INTO settings (key, value) VALUES ('last_used_entry_id', ?1)",
+        [id],
+    )
+    .map_err(|e| e.to_string())?;
+    // Usage is not an edit, so updated_at stays and sync doesn't pick the entry up
+    conn.execute(
+        "UPDATE entries SET last_used_at = ?1, use_count = use_count + 1 WHERE id = ?2",
+        rusqlite::params![chrono::Utc::now().timestamp(), id],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Entries used most recently, newest first
+pub(crate) fn query_recent_entries(conn: &Connection, limit: usize) -> Result<Vec<Entry>, String> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM entries WHERE last_used_at IS NOT NULL ORDER BY last_used_at DESC LIMIT ?1",
+            ENTRY_COLUMNS
+        ))
+        .map_err(|e| e.to_string())?;
+    let entries = stmt
+        .query_map([limit as i64], entry_from_row)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    Ok(entries)
+}
+
+#[tauri::command]
+pub fn mark_entry_used(db: State<Database>, id: String) -> Result<(), String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    record_use(&conn, &id)
+}
+
+#[tauri::command]
+pub fn get_recent_entries(db: State<Database>, limit: Option<usize>) -> Result<Vec<Entry>, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    query_recent_entries(&conn, limit.unwrap_or(10).min(100))
+}
+
+#[tauri::command]
+pub fn get_entry_usage(db: State<Database>, id: String) -> Result<EntryUsage, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    conn.query_row(
+        "SELECT id, last_used_at, use_count FROM entries WHERE id = ?1",
+        [&id],
+        |row| {
+            Ok(EntryUsage {
+                entry_id: row.get(0)?,
+                last_used_at: row.get(1)?,
+                use_count: row.get(2)?,
+            })
+        },
+    )
+    .map_err(|_| "条目不存在".to_string())
+}
+
+/// Forget when and how often every entry was used
+pub(crate) fn clear_usage(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        "UPDATE entries SET last_used_at = NULL, use_count = 0 WHERE last_used_at IS NOT NULL OR use_count != 0",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+    conn.execute("DELETE FROM settings WHERE key = 'last_used_entry_id'", [])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn clear_usage_history(db: State<Database>) -> Result<(), String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    clear_usage(&conn)
+}
+
+/// Field schemas of all entry types, for forms and type-specific display
+#[tauri::command]
+pub fn get_entry_types() -> Vec<EntryTypeSchema> {
+    EntryType::ALL.into_iter().map(entry_types::schema).collect()
+}
+
+/// Entries whose URLs match a page URL or `androidapp://` id, best match first
+#[tauri::command]
+pub fn find_entries_for_url(db: State<Database>, url: String) -> Result<Vec<EntryMatch>, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let entries = query_entries(&conn, &EntryFilter::default())?;
+    Ok(urlmatch::rank_entries(entries, &url))
+}
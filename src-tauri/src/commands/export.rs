@@ -1,10 +1,16 @@
+use crate::commands::entries::{query_entries, validate_entry};
+use crate::commands::groups::query_groups;
 use crate::db::Database;
-use crate::models::{Entry, Group};
-use rusqlite::params;
-use rust_xlsxwriter::{Format, Workbook};
+use crate::entry_types;
+use crate::models::{Entry, EntryFilter, FieldKind, Group};
+use crate::policy;
+use crate::sync::engine as sync_engine;
+use rusqlite::{params, Connection};
+use rust_xlsxwriter::{Format, ProtectionOptions, Workbook, Worksheet};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs;
+use std::path::PathBuf;
 use tauri::{AppHandle, State};
 use tauri_plugin_dialog::DialogExt;
 
@@ -13,6 +19,9 @@ use tauri_plugin_dialog::DialogExt;
 pub struct ExportData {
     version: String,
     export_date: String,
+    /// Fields the export was limited to; the others are blank. Complete when not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    fields: Option<Vec<ExportField>>,
     groups: Vec<Group>,
     entries: Vec<Entry>,
 }
@@ -20,18 +29,177 @@ pub struct ExportData {
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ImportResult {
-    groups_imported: usize,
-    entries_imported: usize,
+    pub(crate) groups_imported: usize,
+    pub(crate) entries_imported: usize,
+    pub(crate) groups_skipped: usize,
+    pub(crate) entries_skipped: usize,
+    pub(crate) groups_removed: usize,
+    pub(crate) entries_removed: usize,
+    pub(crate) dry_run: bool,
 }
 
-/// Export all groups and entries as JSON string
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportSnapshot {
+    created_at: i64,
+}
+
+/// Which entries to export and which fields to include
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportOptions {
+    #[serde(flatten)]
+    pub(crate) filter: EntryFilter,
+    /// Fields to include; all fields when not set
+    pub(crate) fields: Option<Vec<ExportField>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ExportField {
+    Title,
+    Url,
+    Username,
+    Password,
+    Notes,
+    /// Typed and custom fields that hold no secret, e.g. a card's holder
+    Fields,
+    /// Typed and custom fields that hold secrets, e.g. card number, CVV, PIN or IBAN
+    ConcealedFields,
+    Group,
+    Favorite,
+    CreatedAt,
+    UpdatedAt,
+}
+
+impl ExportField {
+    const ALL: [ExportField; 11] = [
+        ExportField::Title,
+        ExportField::Url,
+        ExportField::Username,
+        ExportField::Password,
+        ExportField::Notes,
+        ExportField::Fields,
+        ExportField::ConcealedFields,
+        ExportField::Group,
+        ExportField::Favorite,
+        ExportField::CreatedAt,
+        ExportField::UpdatedAt,
+    ];
+
+    fn header(self) -> &'static str {
+        match self {
+            ExportField::Title => "标题",
+            ExportField::Url => "网址",
+            ExportField::Username => "用户名",
+            ExportField::Password => "密码",
+            ExportField::Notes => "备注",
+            ExportField::Fields => "其他字段",
+            ExportField::ConcealedFields => "隐藏字段",
+            ExportField::Group => "分组",
+            ExportField::Favorite => "收藏",
+            ExportField::CreatedAt => "创建时间",
+            ExportField::UpdatedAt => "更新时间",
+        }
+    }
+
+    fn width(self) -> f64 {
+        match self {
+            ExportField::Url | ExportField::Notes | ExportField::Fields | ExportField::ConcealedFields => 30.0,
+            ExportField::Group => 15.0,
+            ExportField::Favorite => 6.0,
+            _ => 20.0,
+        }
+    }
+}
+
+impl ExportOptions {
+    /// Selected fields in column order
+    fn fields(&self) -> Vec<ExportField> {
+        match self.fields {
+            Some(ref fields) => ExportField::ALL
+                .into_iter()
+                .filter(|f| fields.contains(f))
+                .collect(),
+            None => ExportField::ALL.to_vec(),
+        }
+    }
+}
+
+/// Excel export options on top of the common export options
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExcelOptions {
+    #[serde(flatten)]
+    export: ExportOptions,
+    /// How the password column is written; plain when not set
+    password_mode: Option<PasswordMode>,
+    /// Protect every worksheet against editing with this password
+    /// Note: sheet protection only prevents edits, it does not encrypt the file
+    sheet_password: Option<String>,
+    /// One worksheet per group instead of a single entries sheet
+    sheet_per_group: Option<bool>,
+    /// Add autofilters to header rows (default: true)
+    autofilter: Option<bool>,
+    /// Keep header rows visible while scrolling (default: true)
+    freeze_header: Option<bool>,
+    /// Write the URL column as clickable hyperlinks (default: true)
+    hyperlinks: Option<bool>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PasswordMode {
+    Plain,
+    Masked,
+    Omitted,
+}
+
+const MASKED_PASSWORD: &str = "********";
+const MAX_SHEET_NAME_LEN: usize = 31;
+
+const SUPPORTED_VERSIONS: [&str; 1] = ["1.0"];
+const IMPORT_SNAPSHOT_EXTENSION: &str = "import-undo.db";
+const IMPORT_SNAPSHOT_TEMP_EXTENSION: &str = "import-undo.db.tmp";
+
+/// Export groups and entries as JSON string
+/// options: optional entry filter and field selection; everything when not set
 #[tauri::command]
-pub fn export_data(db: State<Database>) -> Result<String, String> {
-    let (groups, entries) = query_all_data(&db)?;
+pub fn export_data(db: State<Database>, options: Option<ExportOptions>) -> Result<String, String> {
+    export_json(&db, &options.unwrap_or_default())
+}
+
+/// Build the JSON export for the given options
+pub(crate) fn export_json(db: &Database, options: &ExportOptions) -> Result<String, String> {
+    let fields = options.fields();
+    policy::current().check_export(fields.contains(&ExportField::Password) || fields.contains(&ExportField::ConcealedFields))?;
+    let (groups, mut entries) = query_export_data(db, &options.filter)?;
+
+    // Blank out omitted fields so the file stays importable
+    for entry in &mut entries {
+        if !fields.contains(&ExportField::Url) {
+            entry.url.clear();
+        }
+        if !fields.contains(&ExportField::Username) {
+            entry.username.clear();
+        }
+        if !fields.contains(&ExportField::Password) {
+            entry.password.clear();
+        }
+        if !fields.contains(&ExportField::Notes) {
+            entry.notes.clear();
+        }
+        let entry_type = entry.entry_type;
+        entry.fields.retain(|field| {
+            let (kind, _) = entry_types::field_info(entry_type, field);
+            fields.contains(&field_group(kind))
+        });
+    }
 
     let export_data = ExportData {
         version: "1.0".to_string(),
         export_date: chrono::Utc::now().to_rfc3339(),
+        fields: (fields.len() < ExportField::ALL.len()).then_some(fields),
         groups,
         entries,
     };
@@ -80,136 +248,322 @@ pub async fn load_import_file(app: AppHandle) -> Result<String, String> {
 
 /// Import data from JSON string
 /// merge_mode: true = merge (skip duplicates), false = overwrite (clear existing data)
+/// dry_run: true = report what would change without writing anything
 #[tauri::command]
-pub fn import_data(db: State<Database>, json_data: String, merge_mode: bool) -> Result<ImportResult, String> {
+pub fn import_data(
+    db: State<Database>,
+    json_data: String,
+    merge_mode: bool,
+    dry_run: Option<bool>,
+) -> Result<ImportResult, String> {
+    import_json(&db, &json_data, merge_mode, dry_run.unwrap_or(false))
+}
+
+/// Import a JSON export, see `import_data`
+pub(crate) fn import_json(db: &Database, json_data: &str, merge_mode: bool, dry_run: bool) -> Result<ImportResult, String> {
     // Parse JSON
     let import_data: ExportData =
-        serde_json::from_str(&json_data).map_err(|e| format!("无效的备份文件格式: {}", e))?;
+        serde_json::from_str(json_data).map_err(|e| format!("无效的备份文件格式: {}", e))?;
 
-    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    // Overwriting would replace the vault with entries missing the fields left out of the export
+    if !merge_mode && import_data.fields.is_some() {
+        return Err("该文件只导出了部分字段，只能以合并模式导入".to_string());
+    }
 
-    // Start transaction
-    conn.execute("BEGIN TRANSACTION", [])
-        .map_err(|e| e.to_string())?;
+    let mut conn = db.conn.lock().map_err(|e| e.to_string())?;
+
+    // Validate the whole file before touching any data
+    let existing_group_ids: HashSet<String> = if merge_mode {
+        let mut stmt = conn
+            .prepare("SELECT id FROM groups")
+            .map_err(|e| e.to_string())?;
+        let ids = stmt
+            .query_map([], |row| row.get(0))
+            .map_err(|e| e.to_string())?
+            .collect::<Result<HashSet<_>, _>>()
+            .map_err(|e| e.to_string())?;
+        ids
+    } else {
+        HashSet::new()
+    };
+    validate_import(&import_data, &existing_group_ids)?;
+
+    // Keep a copy of the current data so the import can be undone; it replaces the
+    // previous snapshot only once the import is committed
+    let snapshot = if dry_run {
+        None
+    } else {
+        Some(create_import_snapshot(&conn, db)?)
+    };
+
+    let result = write_import(&mut conn, db, &import_data, merge_mode, dry_run);
+    if let Some(snapshot) = snapshot {
+        if result.is_ok() {
+            fs::rename(&snapshot, import_snapshot_path(db)).map_err(|e| format!("保存导入前快照失败: {}", e))?;
+        } else {
+            let _ = fs::remove_file(&snapshot);
+        }
+    }
+    result
+}
+
+/// Helper: write the validated import in one transaction, rolled back for dry runs
+fn write_import(
+    conn: &mut Connection,
+    db: &Database,
+    import_data: &ExportData,
+    merge_mode: bool,
+    dry_run: bool,
+) -> Result<ImportResult, String> {
+    // Dropping the transaction without committing rolls it back, so every
+    // early return below leaves the database untouched
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let mut groups_removed = 0;
+    let mut entries_removed = 0;
 
     // Overwrite mode: clear existing data
     if !merge_mode {
-        conn.execute("DELETE FROM entries", [])
+        entries_removed = tx
+            .execute("DELETE FROM entries", [])
             .map_err(|e| e.to_string())?;
-        conn.execute("DELETE FROM groups", [])
+        groups_removed = tx
+            .execute("DELETE FROM groups", [])
             .map_err(|e| e.to_string())?;
+        // Clearing a whole table skips the per-row change hook
+        db.invalidate_entries();
     }
 
     let mut groups_imported = 0;
     let mut entries_imported = 0;
 
     // Import groups
-    for group in import_data.groups {
-        let result = conn.execute(
-            "INSERT OR IGNORE INTO groups (id, name, icon, sort_order, created_at, updated_at)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
-            params![
-                group.id,
-                group.name,
-                group.icon,
-                group.sort_order,
-                group.created_at,
-                group.updated_at,
-            ],
-        );
-
-        if let Ok(rows) = result {
-            if rows > 0 {
-                groups_imported += 1;
-            }
-        }
+    for group in &import_data.groups {
+        let rows = tx
+            .execute(
+                "INSERT OR IGNORE INTO groups (id, name, icon, sort_order, created_at, updated_at, autotype_sequence)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![
+                    group.id,
+                    group.name,
+                    group.icon,
+                    group.sort_order,
+                    group.created_at,
+                    group.updated_at,
+                    group.autotype_sequence,
+                ],
+            )
+            .map_err(|e| format!("导入分组 \"{}\" 失败: {}", group.name, e))?;
+        groups_imported += rows;
     }
 
     // Import entries
-    for entry in import_data.entries {
-        let result = conn.execute(
-            "INSERT OR IGNORE INTO entries (id, group_id, title, url, username, password, notes, is_favorite, sort_order, created_at, updated_at)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
-            params![
-                entry.id,
-                entry.group_id,
-                entry.title,
-                entry.url,
-                entry.username,
-                entry.password,
-                entry.notes,
-                entry.is_favorite as i32,
-                entry.sort_order,
-                entry.created_at,
-                entry.updated_at,
-            ],
-        );
-
-        if let Ok(rows) = result {
-            if rows > 0 {
-                entries_imported += 1;
-            }
-        }
+    for entry in &import_data.entries {
+        let rows = tx
+            .execute(
+                "INSERT OR IGNORE INTO entries (id, group_id, title, url, username, password, notes, is_favorite, sort_order, created_at, updated_at, url_match, extra_urls, entry_type, fields, autotype_sequence)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)",
+                params![
+                    entry.id,
+                    entry.group_id,
+                    entry.title,
+                    entry.url,
+                    entry.username,
+                    entry.password,
+                    entry.notes,
+                    entry.is_favorite as i32,
+                    entry.sort_order,
+                    entry.created_at,
+                    entry.updated_at,
+                    entry.url_match.as_str(),
+                    serde_json::to_string(&entry.extra_urls).map_err(|e| e.to_string())?,
+                    entry.entry_type.as_str(),
+                    serde_json::to_string(&entry.fields).map_err(|e| e.to_string())?,
+                    entry.autotype_sequence,
+                ],
+            )
+            .map_err(|e| format!("导入条目 \"{}\" 失败: {}", entry.title, e))?;
+        entries_imported += rows;
     }
 
-    // Commit transaction
-    conn.execute("COMMIT", []).map_err(|e| e.to_string())?;
+    if !dry_run {
+        tx.commit().map_err(|e| e.to_string())?;
+    }
 
     Ok(ImportResult {
         groups_imported,
         entries_imported,
+        groups_skipped: import_data.groups.len() - groups_imported,
+        entries_skipped: import_data.entries.len() - entries_imported,
+        groups_removed,
+        entries_removed,
+        dry_run,
     })
 }
 
-/// Helper: query all groups and entries from database
-fn query_all_data(db: &State<Database>) -> Result<(Vec<Group>, Vec<Entry>), String> {
-    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+/// Get information about the snapshot taken before the last import, if any
+#[tauri::command]
+pub fn get_import_snapshot(db: State<Database>) -> Result<Option<ImportSnapshot>, String> {
+    let path = import_snapshot_path(&db);
+    if !path.exists() {
+        return Ok(None);
+    }
 
-    let mut stmt = conn
-        .prepare("SELECT id, name, icon, sort_order, created_at, updated_at FROM groups ORDER BY sort_order")
+    let modified = fs::metadata(&path)
+        .and_then(|m| m.modified())
         .map_err(|e| e.to_string())?;
+    let created_at = chrono::DateTime::<chrono::Utc>::from(modified).timestamp();
 
-    let groups = stmt
-        .query_map([], |row| {
-            Ok(Group {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                icon: row.get(2)?,
-                sort_order: row.get(3)?,
-                created_at: row.get(4)?,
-                updated_at: row.get(5)?,
-            })
-        })
-        .map_err(|e| e.to_string())?
-        .collect::<Result<Vec<_>, _>>()
-        .map_err(|e| e.to_string())?;
+    Ok(Some(ImportSnapshot { created_at }))
+}
 
-    let mut stmt = conn
-        .prepare(
-            "SELECT id, group_id, title, url, username, password, notes, is_favorite, sort_order, created_at, updated_at
-             FROM entries ORDER BY sort_order"
-        )
-        .map_err(|e| e.to_string())?;
+/// Restore groups and entries from the snapshot taken before the last import
+#[tauri::command]
+pub fn undo_last_import(db: State<Database>) -> Result<(), String> {
+    let path = import_snapshot_path(&db);
+    if !path.exists() {
+        return Err("没有可撤销的导入".to_string());
+    }
 
-    let entries = stmt
-        .query_map([], |row| {
-            Ok(Entry {
-                id: row.get(0)?,
-                group_id: row.get(1)?,
-                title: row.get(2)?,
-                url: row.get(3)?,
-                username: row.get(4)?,
-                password: row.get(5)?,
-                notes: row.get(6)?,
-                is_favorite: row.get::<_, i32>(7)? != 0,
-                sort_order: row.get(8)?,
-                created_at: row.get(9)?,
-                updated_at: row.get(10)?,
-            })
-        })
-        .map_err(|e| e.to_string())?
-        .collect::<Result<Vec<_>, _>>()
+    let mut conn = db.conn.lock().map_err(|e| e.to_string())?;
+
+    conn.execute("ATTACH DATABASE ?1 AS snapshot", [path.to_string_lossy()])
+        .map_err(|e| format!("无法打开导入前快照: {}", e))?;
+
+    let result = (|| -> Result<(), String> {
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+        // Snapshots taken before a migration lack its new columns, which keep their defaults
+        let group_columns = shared_columns(&tx, "groups")?;
+        let columns = shared_columns(&tx, "entries")?;
+        tx.execute_batch(&format!(
+            "DELETE FROM entries;
+             DELETE FROM groups;
+             INSERT INTO groups ({group_columns}) SELECT {group_columns} FROM snapshot.groups;
+             INSERT INTO entries ({columns}) SELECT {columns} FROM snapshot.entries;",
+        ))
+        .map_err(|e| format!("恢复导入前数据失败: {}", e))?;
+        // Record the undo as a local edit, so it supersedes the imported versions
+        // other devices may already have instead of being overwritten by them
+        if sync_engine::has_state(&tx)? {
+            sync_engine::record_local_changes(&tx, &sync_engine::device_id()?)?;
+        }
+        tx.commit().map_err(|e| e.to_string())
+    })();
+    db.invalidate_entries();
+
+    conn.execute("DETACH DATABASE snapshot", [])
         .map_err(|e| e.to_string())?;
+    result?;
+
+    fs::remove_file(&path).map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// Helper: check the whole import file for problems before any data is written
+fn validate_import(data: &ExportData, existing_group_ids: &HashSet<String>) -> Result<(), String> {
+    if !SUPPORTED_VERSIONS.contains(&data.version.as_str()) {
+        return Err(format!("不支持的备份文件版本: {}", data.version));
+    }
+
+    let mut errors: Vec<String> = Vec::new();
+    let mut group_ids: HashSet<&str> = HashSet::new();
+
+    for (i, group) in data.groups.iter().enumerate() {
+        if group.id.trim().is_empty() {
+            errors.push(format!("第 {} 个分组缺少 id", i + 1));
+        } else if !group_ids.insert(group.id.as_str()) {
+            errors.push(format!("分组 id 重复: {}", group.id));
+        }
+        if group.name.trim().is_empty() {
+            errors.push(format!("第 {} 个分组名称为空", i + 1));
+        }
+    }
+
+    let mut entry_ids: HashSet<&str> = HashSet::new();
+
+    for (i, entry) in data.entries.iter().enumerate() {
+        if entry.id.trim().is_empty() {
+            errors.push(format!("第 {} 个条目缺少 id", i + 1));
+        } else if !entry_ids.insert(entry.id.as_str()) {
+            errors.push(format!("条目 id 重复: {}", entry.id));
+        }
+        if entry.title.trim().is_empty() {
+            errors.push(format!("第 {} 个条目标题为空", i + 1));
+        } else if let Err(e) = validate_entry(entry) {
+            errors.push(format!("条目 \"{}\": {}", entry.title, e));
+        }
+        if let Some(ref gid) = entry.group_id {
+            if !group_ids.contains(gid.as_str()) && !existing_group_ids.contains(gid) {
+                errors.push(format!("条目 \"{}\" 引用了不存在的分组: {}", entry.title, gid));
+            }
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("备份文件校验失败:\n{}", errors.join("\n")))
+    }
+}
+
+/// Helper: snapshot location next to the vault file, e.g. data.import-undo.db
+fn import_snapshot_path(db: &Database) -> PathBuf {
+    db.path().with_extension(IMPORT_SNAPSHOT_EXTENSION)
+}
+
+/// Helper: copy the current database (including WAL contents) to a temporary file next to data.db
+/// It becomes the undo snapshot only once the import succeeded.
+fn create_import_snapshot(conn: &Connection, db: &Database) -> Result<PathBuf, String> {
+    let path = db.path().with_extension(IMPORT_SNAPSHOT_TEMP_EXTENSION);
+    // VACUUM INTO refuses to write over an existing file
+    if path.exists() {
+        fs::remove_file(&path).map_err(|e| e.to_string())?;
+    }
+
+    conn.execute("VACUUM INTO ?1", [path.to_string_lossy()])
+        .map_err(|e| format!("创建导入前快照失败: {}", e))?;
+
+    Ok(path)
+}
+
+/// Helper: columns of a table present both in the vault and in the attached snapshot
+fn shared_columns(conn: &Connection, table: &str) -> Result<String, String> {
+    let columns = |schema: &str| -> Result<Vec<String>, String> {
+        let mut stmt = conn
+            .prepare(&format!("PRAGMA {}.table_info({})", schema, table))
+            .map_err(|e| e.to_string())?;
+        let names = stmt
+            .query_map([], |row| row.get::<_, String>(1))
+            .map_err(|e| e.to_string())?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string());
+        names
+    };
+    let snapshot = columns("snapshot")?;
+    Ok(columns("main")?
+        .into_iter()
+        .filter(|c| snapshot.contains(c))
+        .collect::<Vec<_>>()
+        .join(", "))
+}
+
+/// Helper: query the groups and entries selected by the filter
+/// An empty filter exports every group, otherwise only groups the selected entries belong to
+fn query_export_data(db: &Database, filter: &EntryFilter) -> Result<(Vec<Group>, Vec<Entry>), String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+
+    let entries = query_entries(&conn, filter)?;
+
+    let mut groups = query_groups(&conn)?;
+
+    if !filter.is_empty() {
+        let used: HashSet<&str> = entries
+            .iter()
+            .filter_map(|e| e.group_id.as_deref())
+            .collect();
+        groups.retain(|g| used.contains(g.id.as_str()));
+    }
 
     Ok((groups, entries))
 }
@@ -221,9 +575,19 @@ fn format_timestamp(ts: i64) -> String {
 }
 
 /// Export data as Excel (.xlsx) binary
+/// options: entry filter, column selection and sheet layout; everything in one sheet when not set
 #[tauri::command]
-pub fn export_excel(db: State<Database>) -> Result<Vec<u8>, String> {
-    let (groups, entries) = query_all_data(&db)?;
+pub fn export_excel(db: State<Database>, options: Option<ExcelOptions>) -> Result<Vec<u8>, String> {
+    let options = options.unwrap_or_default();
+    let mut fields = options.export.fields();
+    if options.password_mode == Some(PasswordMode::Omitted) {
+        fields.retain(|f| *f != ExportField::Password && *f != ExportField::ConcealedFields);
+    }
+    let plain_passwords = (fields.contains(&ExportField::Password) || fields.contains(&ExportField::ConcealedFields))
+        && !matches!(options.password_mode, Some(PasswordMode::Masked));
+    policy::current().check_export(plain_passwords)?;
+
+    let (groups, entries) = query_export_data(&db, &options.export.filter)?;
 
     // Build group id -> name map
     let group_map: HashMap<String, String> = groups
@@ -236,41 +600,46 @@ pub fn export_excel(db: State<Database>) -> Result<Vec<u8>, String> {
     // Header format
     let header_fmt = Format::new().set_bold();
 
-    // --- Sheet 1: Entries ---
-    let sheet = workbook.add_worksheet();
-    sheet.set_name("密码条目").map_err(|e| e.to_string())?;
-
-    let headers = ["标题", "网址", "用户名", "密码", "备注", "分组", "收藏", "创建时间", "更新时间"];
-    for (col, h) in headers.iter().enumerate() {
-        sheet.write_string_with_format(0, col as u16, *h, &header_fmt)
-            .map_err(|e| e.to_string())?;
-    }
-
-    // Set column widths
-    let widths = [20.0, 30.0, 20.0, 20.0, 30.0, 15.0, 6.0, 20.0, 20.0];
-    for (col, w) in widths.iter().enumerate() {
-        sheet.set_column_width(col as u16, *w).map_err(|e| e.to_string())?;
-    }
-
-    for (i, entry) in entries.iter().enumerate() {
-        let row = (i + 1) as u32;
-        let group_name = entry.group_id.as_ref()
-            .and_then(|gid| group_map.get(gid))
-            .map(|s| s.as_str())
-            .unwrap_or("未分组");
+    // --- Entry sheets ---
+    if options.sheet_per_group.unwrap_or(false) {
+        let mut used_names: HashSet<String> = HashSet::new();
+        used_names.insert("分组".to_lowercase());
+        // Reserved by Excel
+        used_names.insert("history".to_string());
+
+        let mut sheets: Vec<(&str, Vec<&Entry>)> = groups
+            .iter()
+            .map(|g| {
+                let group_entries = entries
+                    .iter()
+                    .filter(|e| e.group_id.as_deref() == Some(g.id.as_str()))
+                    .collect();
+                (g.name.as_str(), group_entries)
+            })
+            .collect();
+
+        let ungrouped: Vec<&Entry> = entries
+            .iter()
+            .filter(|e| e.group_id.as_ref().is_none_or(|gid| !group_map.contains_key(gid)))
+            .collect();
+        if !ungrouped.is_empty() {
+            sheets.push(("未分组", ungrouped));
+        }
 
-        sheet.write_string(row, 0, &entry.title).map_err(|e| e.to_string())?;
-        sheet.write_string(row, 1, &entry.url).map_err(|e| e.to_string())?;
-        sheet.write_string(row, 2, &entry.username).map_err(|e| e.to_string())?;
-        sheet.write_string(row, 3, &entry.password).map_err(|e| e.to_string())?;
-        sheet.write_string(row, 4, &entry.notes).map_err(|e| e.to_string())?;
-        sheet.write_string(row, 5, group_name).map_err(|e| e.to_string())?;
-        sheet.write_string(row, 6, if entry.is_favorite { "是" } else { "否" }).map_err(|e| e.to_string())?;
-        sheet.write_string(row, 7, &format_timestamp(entry.created_at)).map_err(|e| e.to_string())?;
-        sheet.write_string(row, 8, &format_timestamp(entry.updated_at)).map_err(|e| e.to_string())?;
+        for (name, group_entries) in sheets {
+            let sheet = workbook.add_worksheet();
+            sheet.set_name(unique_sheet_name(name, &mut used_names))
+                .map_err(|e| e.to_string())?;
+            write_entries_sheet(sheet, &group_entries, &fields, &group_map, &options, &header_fmt)?;
+        }
+    } else {
+        let sheet = workbook.add_worksheet();
+        sheet.set_name("密码条目").map_err(|e| e.to_string())?;
+        let all_entries: Vec<&Entry> = entries.iter().collect();
+        write_entries_sheet(sheet, &all_entries, &fields, &group_map, &options, &header_fmt)?;
     }
 
-    // --- Sheet 2: Groups ---
+    // --- Groups sheet ---
     let sheet2 = workbook.add_worksheet();
     sheet2.set_name("分组").map_err(|e| e.to_string())?;
 
@@ -289,15 +658,162 @@ pub fn export_excel(db: State<Database>) -> Result<Vec<u8>, String> {
         let row = (i + 1) as u32;
         sheet2.write_string(row, 0, &group.name).map_err(|e| e.to_string())?;
         sheet2.write_string(row, 1, &group.icon).map_err(|e| e.to_string())?;
-        sheet2.write_string(row, 2, &format_timestamp(group.created_at)).map_err(|e| e.to_string())?;
-        sheet2.write_string(row, 3, &format_timestamp(group.updated_at)).map_err(|e| e.to_string())?;
+        sheet2.write_string(row, 2, format_timestamp(group.created_at)).map_err(|e| e.to_string())?;
+        sheet2.write_string(row, 3, format_timestamp(group.updated_at)).map_err(|e| e.to_string())?;
     }
 
+    apply_sheet_options(sheet2, groups.len(), headers2.len(), &options)?;
+
     // Save to buffer
     let buf = workbook.save_to_buffer().map_err(|e| e.to_string())?;
     Ok(buf)
 }
 
+/// Helper: write a header row and one row per entry to the worksheet
+fn write_entries_sheet(
+    sheet: &mut Worksheet,
+    entries: &[&Entry],
+    fields: &[ExportField],
+    group_map: &HashMap<String, String>,
+    options: &ExcelOptions,
+    header_fmt: &Format,
+) -> Result<(), String> {
+    for (col, field) in fields.iter().enumerate() {
+        sheet.write_string_with_format(0, col as u16, field.header(), header_fmt)
+            .map_err(|e| e.to_string())?;
+        sheet.set_column_width(col as u16, field.width()).map_err(|e| e.to_string())?;
+    }
+
+    let hyperlinks = options.hyperlinks.unwrap_or(true);
+
+    for (i, entry) in entries.iter().enumerate() {
+        let row = (i + 1) as u32;
+        let group_name = entry.group_id.as_ref()
+            .and_then(|gid| group_map.get(gid))
+            .map(|s| s.as_str())
+            .unwrap_or("未分组");
+
+        for (col, field) in fields.iter().enumerate() {
+            let col = col as u16;
+
+            // Only web links become hyperlinks; anything Excel rejects stays plain text
+            if *field == ExportField::Url
+                && hyperlinks
+                && (entry.url.starts_with("http://") || entry.url.starts_with("https://"))
+                && sheet.write_url(row, col, entry.url.as_str()).is_ok()
+            {
+                continue;
+            }
+
+            let value = match field {
+                ExportField::Title => entry.title.clone(),
+                ExportField::Url => entry.url.clone(),
+                ExportField::Username => entry.username.clone(),
+                ExportField::Password => match options.password_mode {
+                    Some(PasswordMode::Masked) if !entry.password.is_empty() => MASKED_PASSWORD.to_string(),
+                    _ => entry.password.clone(),
+                },
+                ExportField::Notes => entry.notes.clone(),
+                ExportField::Fields => field_lines(entry, false, false),
+                ExportField::ConcealedFields => {
+                    field_lines(entry, true, options.password_mode == Some(PasswordMode::Masked))
+                }
+                ExportField::Group => group_name.to_string(),
+                ExportField::Favorite => if entry.is_favorite { "是" } else { "否" }.to_string(),
+                ExportField::CreatedAt => format_timestamp(entry.created_at),
+                ExportField::UpdatedAt => format_timestamp(entry.updated_at),
+            };
+            sheet.write_string(row, col, value).map_err(|e| e.to_string())?;
+        }
+    }
+
+    apply_sheet_options(sheet, entries.len(), fields.len(), options)
+}
+
+/// Helper: export field selection covering a typed or custom field
+fn field_group(kind: FieldKind) -> ExportField {
+    if kind.is_secret() {
+        ExportField::ConcealedFields
+    } else {
+        ExportField::Fields
+    }
+}
+
+/// Helper: the entry's secret or other fields as `label: value` lines for one cell
+fn field_lines(entry: &Entry, secret: bool, masked: bool) -> String {
+    entry
+        .fields
+        .iter()
+        .filter(|field| !field.value.is_empty())
+        .filter_map(|field| {
+            let (kind, label) = entry_types::field_info(entry.entry_type, field);
+            (kind.is_secret() == secret).then(|| {
+                let value = if masked { MASKED_PASSWORD } else { field.value.as_str() };
+                format!("{}: {}", label, value)
+            })
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Helper: apply autofilter, frozen header and protection to a finished sheet
+fn apply_sheet_options(
+    sheet: &mut Worksheet,
+    rows: usize,
+    cols: usize,
+    options: &ExcelOptions,
+) -> Result<(), String> {
+    if cols > 0 && options.autofilter.unwrap_or(true) {
+        sheet.autofilter(0, 0, rows as u32, (cols - 1) as u16)
+            .map_err(|e| e.to_string())?;
+    }
+
+    if options.freeze_header.unwrap_or(true) {
+        sheet.set_freeze_panes(1, 0).map_err(|e| e.to_string())?;
+    }
+
+    if let Some(ref password) = options.sheet_password {
+        if !password.is_empty() {
+            // Keep sorting and filtering usable on protected sheets
+            let protection = ProtectionOptions {
+                sort: true,
+                use_autofilter: true,
+                ..ProtectionOptions::default()
+            };
+            sheet.protect_with_password(password);
+            sheet.protect_with_options(&protection);
+        }
+    }
+
+    Ok(())
+}
+
+/// Helper: turn a group name into a valid, unused worksheet name
+fn unique_sheet_name(name: &str, used: &mut HashSet<String>) -> String {
+    let cleaned: String = name
+        .chars()
+        .map(|c| match c {
+            '[' | ']' | ':' | '*' | '?' | '/' | '\\' => '_',
+            c => c,
+        })
+        .collect();
+    let cleaned = cleaned.trim().trim_matches('\'');
+    let base = if cleaned.is_empty() { "分组" } else { cleaned };
+
+    let mut candidate: String = base.chars().take(MAX_SHEET_NAME_LEN).collect();
+    let mut n = 2;
+    // Excel compares sheet names case-insensitively
+    while used.contains(&candidate.to_lowercase()) {
+        let suffix = format!(" ({})", n);
+        let keep = MAX_SHEET_NAME_LEN - suffix.chars().count();
+        candidate = base.chars().take(keep).collect::<String>() + &suffix;
+        n += 1;
+    }
+
+    used.insert(candidate.to_lowercase());
+    candidate
+}
+
 /// Open save dialog and write Excel data to file
 #[tauri::command]
 pub async fn save_export_excel_file(app: AppHandle, content: Vec<u8>) -> Result<(), String> {
@@ -1,29 +1,31 @@
+use rusqlite::Connection;
 use tauri::State;
 use crate::db::Database;
 use crate::models::Group;
 
-#[tauri::command]
-pub fn get_groups(db: State<Database>) -> Result<Vec<Group>, String> {
-    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+pub(crate) const GROUP_COLUMNS: &str = "id, name, icon, sort_order, created_at, updated_at, autotype_sequence";
+
+/// Map a row selected with `GROUP_COLUMNS` to a `Group`
+pub(crate) fn group_from_row(row: &rusqlite::Row) -> rusqlite::Result<Group> {
+    Ok(Group {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        icon: row.get(2)?,
+        sort_order: row.get(3)?,
+        autotype_sequence: row.get(6)?,
+        created_at: row.get(4)?,
+        updated_at: row.get(5)?,
+    })
+}
 
+/// Query all groups in display order
+pub(crate) fn query_groups(conn: &Connection) -> Result<Vec<Group>, String> {
     let mut stmt = conn
-        .prepare(
-            "SELECT id, name, icon, sort_order, created_at, updated_at
-             FROM groups ORDER BY sort_order ASC"
-        )
+        .prepare(&format!("SELECT {} FROM groups ORDER BY sort_order ASC", GROUP_COLUMNS))
         .map_err(|e| e.to_string())?;
 
     let groups = stmt
-        .query_map([], |row| {
-            Ok(Group {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                icon: row.get(2)?,
-                sort_order: row.get(3)?,
-                created_at: row.get(4)?,
-                updated_at: row.get(5)?,
-            })
-        })
+        .query_map([], group_from_row)
         .map_err(|e| e.to_string())?
         .collect::<Result<Vec<_>, _>>()
         .map_err(|e| e.to_string())?;
@@ -31,6 +33,39 @@ pub fn get_groups(db: State<Database>) -> Result<Vec<Group>, String> {
     Ok(groups)
 }
 
+/// Insert a group or replace the stored copy with the same id
+pub(crate) fn upsert_group(conn: &Connection, group: &Group) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO groups (id, name, icon, sort_order, created_at, updated_at, autotype_sequence)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+         ON CONFLICT(id) DO UPDATE SET
+             name = excluded.name,
+             icon = excluded.icon,
+             sort_order = excluded.sort_order,
+             created_at = excluded.created_at,
+             updated_at = excluded.updated_at,
+             autotype_sequence = excluded.autotype_sequence",
+        rusqlite::params![
+            group.id,
+            group.name,
+            group.icon,
+            group.sort_order,
+            group.created_at,
+            group.updated_at,
+            group.autotype_sequence,
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_groups(db: State<Database>) -> Result<Vec<Group>, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    query_groups(&conn)
+}
+
 #[tauri::command]
 pub fn create_group(
     db: State<Database>,
@@ -61,17 +96,20 @@ pub fn create_group(
         name,
         icon,
         sort_order: max_order + 1,
+        autotype_sequence: None,
         created_at: now,
         updated_at: now,
     })
 }
 
+/// autotype_sequence: an empty string clears the group's sequence
 #[tauri::command]
 pub fn update_group(
     db: State<Database>,
     id: String,
     name: Option<String>,
     icon: Option<String>,
+    autotype_sequence: Option<String>,
 ) -> Result<Group, String> {
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
     let now = chrono::Utc::now().timestamp();
@@ -87,6 +125,14 @@ pub fn update_group(
         sets.push("icon = ?".to_string());
         params.push(Box::new(icon.clone()));
     }
+    if let Some(sequence) = autotype_sequence {
+        let sequence = Some(sequence).filter(|s| !s.trim().is_empty());
+        if let Some(ref sequence) = sequence {
+            crate::autotype::sequence::parse(sequence)?;
+        }
+        sets.push("autotype_sequence = ?".to_string());
+        params.push(Box::new(sequence));
+    }
 
     sets.push("updated_at = ?".to_string());
     params.push(Box::new(now));
@@ -100,18 +146,9 @@ pub fn update_group(
 
     let group = conn
         .query_row(
-            "SELECT id, name, icon, sort_order, created_at, updated_at FROM groups WHERE id = ?1",
+            &format!("SELECT {} FROM groups WHERE id = ?1", GROUP_COLUMNS),
             [&id],
-            |row| {
-                Ok(Group {
-                    id: row.get(0)?,
-                    name: row.get(1)?,
-                    icon: row.get(2)?,
-                    sort_order: row.get(3)?,
-                    created_at: row.get(4)?,
-                    updated_at: row.get(5)?,
-                })
-            },
+            group_from_row,
         )
         .map_err(|e| e.to_string())?;
 
@@ -0,0 +1,43 @@
+use tauri::{AppHandle, Manager, State};
+use crate::commands::entries::query_entries;
+use crate::crypto::Session;
+use crate::db::Database;
+use crate::health::{self, HealthState};
+use crate::models::{EntryFilter, HealthReport};
+use crate::settings;
+
+#[tauri::command]
+pub fn get_health_report(db: State<Database>, session: State<Session>, health: State<HealthState>) -> Result<HealthReport, String> {
+    if !session.is_unlocked() {
+        return Err("密码库已锁定".to_string());
+    }
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    health::report(&conn, &health)
+}
+
+/// Check passwords against known breaches online, then report as `get_health_report` does
+/// Nothing is sent unless the user turned on the breach check setting.
+#[tauri::command]
+pub async fn check_breached_passwords(app: AppHandle) -> Result<HealthReport, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        if !app.state::<Session>().is_unlocked() {
+            return Err("密码库已锁定".to_string());
+        }
+        let entries = {
+            let db = app.state::<Database>();
+            let conn = db.conn.lock().map_err(|e| e.to_string())?;
+            if !settings::get_bool(&conn, "breach_check") {
+                return Err("请先在设置中开启在线泄露检查".to_string());
+            }
+            query_entries(&conn, &EntryFilter::default())?
+        };
+        health::check_breaches(&entries, &app.state::<HealthState>())?;
+        crate::refresh_tray(&app);
+
+        let db = app.state::<Database>();
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        health::report(&conn, &app.state::<HealthState>())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
@@ -1,6 +1,15 @@
 pub mod auth;
+pub mod autotype;
+pub mod backup;
+pub mod browser;
 pub mod entries;
 pub mod export;
 pub mod groups;
+pub mod health;
 pub mod settings;
+pub mod shortcuts;
+pub mod ssh;
+pub mod sync;
+pub mod templates;
+pub mod vaults;
 pub mod window;
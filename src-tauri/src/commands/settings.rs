@@ -1,52 +1,261 @@
-use tauri::State;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::fs;
+use tauri::{AppHandle, Emitter, Manager, State};
+use tauri_plugin_dialog::DialogExt;
+use crate::crypto::Session;
 use crate::db::Database;
-use crate::models::Settings;
+use crate::models::{BackupSettings, Settings, SyncBackend, SyncSettings};
+use crate::policy::{self, Policy};
+use crate::settings::{self, Change, SettingSpec};
+
+/// Settings shared as a file, to use the same ones on several machines
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SettingsExport {
+    version: String,
+    export_date: String,
+    settings: Map<String, Value>,
+}
+
+fn current_settings(conn: &Connection) -> Settings {
+    Settings {
+        values: settings::read_all(conn),
+        locked: settings::locks(),
+    }
+}
 
 #[tauri::command]
 pub fn get_settings(db: State<Database>) -> Result<Settings, String> {
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    Ok(current_settings(&conn))
+}
+
+/// Key, type, default and range of every setting, for building forms
+#[tauri::command]
+pub fn get_setting_specs() -> Vec<&'static SettingSpec> {
+    settings::SETTINGS.iter().collect()
+}
+
+/// Change some settings by name; other settings keep their values
+#[tauri::command]
+pub fn update_settings(app: AppHandle, db: State<Database>, changes: Map<String, Value>) -> Result<Settings, String> {
+    change_settings(&app, &changes)?;
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    Ok(current_settings(&conn))
+}
+
+/// Restore defaults for the named settings, or for all of them when `names` is omitted
+#[tauri::command]
+pub fn reset_settings(
+    app: AppHandle,
+    db: State<Database>,
+    session: State<Session>,
+    names: Option<Vec<String>>,
+) -> Result<Settings, String> {
+    let saved = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        settings::reset(&conn, names.as_deref(), session.is_unlocked())?
+    };
+    finish_changes(&app, saved)?;
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    Ok(current_settings(&conn))
+}
+
+/// Save settings by name, then run their side effects and tell every window
+pub(crate) fn change_settings(app: &AppHandle, changes: &Map<String, Value>) -> Result<(), String> {
+    let saved = {
+        let db = app.state::<Database>();
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        settings::update(&conn, changes, app.state::<Session>().is_unlocked())?
+    };
+    finish_changes(app, saved)
+}
+
+/// Helper: side effects run without the database lock, since some of them take it
+pub(crate) fn finish_changes(app: &AppHandle, saved: Vec<Change>) -> Result<(), String> {
+    let (changed, result) = settings::apply(app, saved);
+    notify_changed(app, &changed);
+    result
+}
 
-    let get_setting = |key: &str, default: &str| -> String {
-        conn.query_row(
-            "SELECT value FROM settings WHERE key = ?1",
-            [key],
-            |row| row.get(0),
-        )
-        .unwrap_or_else(|_| default.to_string())
+/// Export the registered settings as JSON string; none of them hold secrets
+/// Settings tied to this vault's sync target are left out.
+#[tauri::command]
+pub fn export_settings(db: State<Database>) -> Result<String, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let mut values = settings::read_all(&conn);
+    values.retain(|name, _| settings::SETTINGS.iter().any(|spec| spec.name == name && !spec.managed));
+    let export = SettingsExport {
+        version: "1.0".to_string(),
+        export_date: chrono::Utc::now().to_rfc3339(),
+        settings: values,
     };
+    serde_json::to_string_pretty(&export).map_err(|e| e.to_string())
+}
+
+/// Apply settings from an exported JSON string
+/// Settings this version doesn't know, the admin policy fixes or sync manages are skipped.
+#[tauri::command]
+pub fn import_settings(app: AppHandle, db: State<Database>, json_data: String) -> Result<Settings, String> {
+    let import: SettingsExport =
+        serde_json::from_str(&json_data).map_err(|e| format!("无效的设置文件格式: {}", e))?;
+    if import.version != "1.0" {
+        return Err(format!("不支持的设置文件版本: {}", import.version));
+    }
+
+    let locked = settings::locks();
+    let changes = import
+        .settings
+        .into_iter()
+        .filter(|(name, _)| {
+            settings::SETTINGS.iter().any(|spec| spec.name == name && !spec.managed)
+                && !locked.get(name).is_some_and(|lock| lock.fixed)
+        })
+        .collect();
 
-    Ok(Settings {
-        auto_lock_minutes: get_setting("auto_lock_minutes", "5")
-            .parse()
-            .unwrap_or(5),
-        clear_clipboard_seconds: get_setting("clear_clipboard_seconds", "30")
-            .parse()
-            .unwrap_or(30),
-        theme: get_setting("theme", "system"),
-    })
+    change_settings(&app, &changes)?;
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    Ok(current_settings(&conn))
+}
+
+/// Open save dialog and write a settings file
+#[tauri::command]
+pub async fn save_settings_file(app: AppHandle, content: String) -> Result<(), String> {
+    let file_path = app
+        .dialog()
+        .file()
+        .set_title("保存设置文件")
+        .add_filter("JSON", &["json"])
+        .set_file_name("one-password-settings.json")
+        .blocking_save_file();
+
+    match file_path {
+        Some(path) => {
+            let path = path.into_path().map_err(|e| e.to_string())?;
+            fs::write(path, content).map_err(|e| format!("无法写入文件: {}", e))?;
+            Ok(())
+        }
+        None => Err("用户取消保存".to_string()),
+    }
+}
+
+/// Open file dialog and read a settings file
+#[tauri::command]
+pub async fn load_settings_file(app: AppHandle) -> Result<String, String> {
+    let file_path = app
+        .dialog()
+        .file()
+        .set_title("选择设置文件")
+        .add_filter("JSON", &["json"])
+        .blocking_pick_file();
+
+    match file_path {
+        Some(path) => {
+            let path = path.into_path().map_err(|e| e.to_string())?;
+            fs::read_to_string(path).map_err(|e| format!("无法读取文件: {}", e))
+        }
+        None => Err("用户取消导入".to_string()),
+    }
+}
+
+/// Admin policy of this machine, so the frontend can explain what it enforces
+#[tauri::command]
+pub fn get_policy() -> Policy {
+    policy::current().clone()
+}
+
+/// Every window gets the changed values with `settings-changed`
+fn notify_changed(app: &AppHandle, changed: &Map<String, Value>) {
+    if !changed.is_empty() {
+        let _ = app.emit("settings-changed", changed);
+    }
 }
 
 #[tauri::command]
-pub fn update_settings(db: State<Database>, settings: Settings) -> Result<(), String> {
+pub fn get_backup_settings(db: State<Database>) -> Result<BackupSettings, String> {
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    Ok(read_backup_settings(&conn))
+}
+
+#[tauri::command]
+pub fn update_backup_settings(app: AppHandle, settings: BackupSettings) -> Result<(), String> {
+    let changes = [
+        ("backupEnabled", Value::from(settings.enabled)),
+        ("backupDirectory", Value::from(settings.directory)),
+        ("backupIntervalHours", Value::from(settings.interval_hours)),
+        ("backupKeepDaily", Value::from(settings.keep_daily)),
+        ("backupKeepWeekly", Value::from(settings.keep_weekly)),
+        ("backupKeepMonthly", Value::from(settings.keep_monthly)),
+    ];
+    change_settings(&app, &changes.into_iter().map(|(name, value)| (name.to_string(), value)).collect())
+}
+
+pub(crate) fn read_backup_settings(conn: &Connection) -> BackupSettings {
+    BackupSettings {
+        enabled: settings::get_bool(conn, "backup_enabled"),
+        directory: settings::get_text(conn, "backup_directory"),
+        interval_hours: settings::get_int(conn, "backup_interval_hours") as i32,
+        keep_daily: settings::get_int(conn, "backup_keep_daily") as i32,
+        keep_weekly: settings::get_int(conn, "backup_keep_weekly") as i32,
+        keep_monthly: settings::get_int(conn, "backup_keep_monthly") as i32,
+    }
+}
+
+pub(crate) fn read_sync_settings(conn: &Connection) -> SyncSettings {
+    SyncSettings {
+        enabled: settings::get_bool(conn, "sync_enabled"),
+        backend: match settings::get_text(conn, "sync_backend").as_str() {
+            "webdav" => SyncBackend::Webdav,
+            _ => SyncBackend::Folder,
+        },
+        directory: settings::get_text(conn, "sync_directory"),
+        webdav_url: settings::get_text(conn, "sync_webdav_url"),
+        interval_minutes: settings::get_int(conn, "sync_interval_minutes") as i32,
+    }
+}
+
+/// Whether this app serves as the system keyring (org.freedesktop.secrets)
+#[tauri::command]
+pub fn get_secret_service_enabled(db: State<Database>) -> Result<bool, String> {
+    #[cfg(target_os = "linux")]
+    {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        Ok(crate::secret_service::is_enabled(&conn))
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = db;
+        Ok(false)
+    }
+}
+
+#[tauri::command]
+pub fn set_secret_service_enabled(app: AppHandle, enabled: bool) -> Result<(), String> {
+    let mut changes = Map::new();
+    changes.insert("secretServiceEnabled".to_string(), Value::Bool(enabled));
+    change_settings(&app, &changes)
+}
 
-    conn.execute(
-        "INSERT OR REPLACE INTO settings (key, value) VALUES ('auto_lock_minutes', ?1)",
-        [&settings.auto_lock_minutes.to_string()],
-    )
-    .map_err(|e| e.to_string())?;
-
-    conn.execute(
-        "INSERT OR REPLACE INTO settings (key, value) VALUES ('clear_clipboard_seconds', ?1)",
-        [&settings.clear_clipboard_seconds.to_string()],
-    )
-    .map_err(|e| e.to_string())?;
-
-    conn.execute(
-        "INSERT OR REPLACE INTO settings (key, value) VALUES ('theme', ?1)",
-        [&settings.theme],
-    )
-    .map_err(|e| e.to_string())?;
-
-    Ok(())
+/// Take or release the bus name after the setting changed
+pub(crate) fn apply_secret_service_enabled(app: &AppHandle, enabled: bool) -> Result<(), String> {
+    #[cfg(target_os = "linux")]
+    {
+        if enabled {
+            crate::secret_service::start(app)?;
+        } else {
+            crate::secret_service::stop(app);
+        }
+        Ok(())
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = app;
+        if enabled {
+            Err("系统密钥环仅支持 Linux".to_string())
+        } else {
+            Ok(())
+        }
+    }
 }
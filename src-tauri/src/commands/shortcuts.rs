@@ -0,0 +1,66 @@
+use serde_json::{Map, Value};
+use tauri::{AppHandle, State};
+use crate::commands::settings::{change_settings, finish_changes};
+use crate::crypto::Session;
+use crate::db::Database;
+use crate::models::{ShortcutAction, ShortcutStatus};
+use crate::settings;
+use crate::shortcuts::ShortcutRegistry;
+
+/// Configured shortcuts and whether each could be registered
+#[tauri::command]
+pub fn get_shortcuts(registry: State<ShortcutRegistry>) -> Vec<ShortcutStatus> {
+    registry.statuses()
+}
+
+/// Shortcuts that are configured but could not be registered, e.g. because another app holds them
+#[tauri::command]
+pub fn get_shortcut_failures(registry: State<ShortcutRegistry>) -> Vec<ShortcutStatus> {
+    registry
+        .statuses()
+        .into_iter()
+        .filter(|s| !s.accelerator.is_empty() && !s.registered)
+        .collect()
+}
+
+/// Bind an action to a key combination such as `ctrl+alt+l`; an empty accelerator unbinds it
+/// Fails without changing anything when the combination can't be registered.
+#[tauri::command]
+pub fn set_shortcut(
+    app: AppHandle,
+    registry: State<ShortcutRegistry>,
+    action: ShortcutAction,
+    accelerator: String,
+) -> Result<ShortcutStatus, String> {
+    let mut changes = Map::new();
+    changes.insert(setting_name(action), Value::from(accelerator));
+    change_settings(&app, &changes)?;
+    registry
+        .statuses()
+        .into_iter()
+        .find(|status| status.action == action)
+        .ok_or_else(|| "快捷键不存在".to_string())
+}
+
+/// Restore the default combination of every action
+#[tauri::command]
+pub fn reset_shortcuts(
+    app: AppHandle,
+    db: State<Database>,
+    session: State<Session>,
+    registry: State<ShortcutRegistry>,
+) -> Result<Vec<ShortcutStatus>, String> {
+    let names: Vec<String> = ShortcutAction::ALL.into_iter().map(setting_name).collect();
+    let saved = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        settings::reset(&conn, Some(&names), session.is_unlocked())?
+    };
+    finish_changes(&app, saved)?;
+    Ok(registry.statuses())
+}
+
+fn setting_name(action: ShortcutAction) -> String {
+    settings::spec(action.setting_key())
+        .map(|spec| spec.name.to_string())
+        .unwrap_or_default()
+}
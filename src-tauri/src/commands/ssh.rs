@@ -0,0 +1,104 @@
+use tauri::{AppHandle, State};
+use serde_json::{Map, Value};
+use crate::commands::entries::{insert_entry, ENTRY_COLUMNS, entry_from_row};
+use crate::commands::settings::change_settings;
+use crate::db::Database;
+use crate::models::{CreateEntryDto, Entry, EntryType, SshAgentStatus, SshKeyAlgorithm, SshKeyInfo};
+use crate::ssh;
+
+/// Generate a key pair and store it as a new SSH key entry
+#[tauri::command]
+pub fn generate_ssh_key(
+    db: State<Database>,
+    group_id: Option<String>,
+    title: String,
+    algorithm: SshKeyAlgorithm,
+    comment: Option<String>,
+) -> Result<Entry, String> {
+    let comment = comment.unwrap_or_default();
+    let private_key = ssh::generate(algorithm, &comment)?;
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    insert_entry(&conn, ssh_key_entry(group_id, title, comment, private_key))
+}
+
+/// Store an existing OpenSSH private key; a passphrase-protected key is decrypted first
+#[tauri::command]
+pub fn import_ssh_key(
+    db: State<Database>,
+    group_id: Option<String>,
+    title: String,
+    private_key: String,
+    passphrase: Option<String>,
+) -> Result<Entry, String> {
+    let private_key = ssh::import(&private_key, passphrase.as_deref().filter(|p| !p.is_empty()))?;
+    let comment = ssh::key_info(&private_key)?.comment;
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    insert_entry(&conn, ssh_key_entry(group_id, title, comment, private_key))
+}
+
+#[tauri::command]
+pub fn get_ssh_key_info(db: State<Database>, id: String) -> Result<SshKeyInfo, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let entry = conn
+        .query_row(
+            &format!("SELECT {} FROM entries WHERE id = ?1", ENTRY_COLUMNS),
+            [&id],
+            entry_from_row,
+        )
+        .map_err(|_| "条目不存在".to_string())?;
+    if entry.entry_type != EntryType::SshKey {
+        return Err("该条目不是 SSH 密钥".to_string());
+    }
+    ssh::key_info(&entry.password)
+}
+
+#[tauri::command]
+pub fn get_ssh_agent_status(app: AppHandle, db: State<Database>) -> Result<SshAgentStatus, String> {
+    #[cfg(unix)]
+    {
+        let enabled = {
+            let conn = db.conn.lock().map_err(|e| e.to_string())?;
+            ssh::agent::is_enabled(&conn)
+        };
+        Ok(SshAgentStatus {
+            enabled,
+            running: ssh::agent::is_running(&app),
+            socket_path: ssh::agent::socket_path(&db.path()).display().to_string(),
+        })
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = (app, db);
+        Ok(SshAgentStatus {
+            enabled: false,
+            running: false,
+            socket_path: String::new(),
+        })
+    }
+}
+
+/// Turn the built-in ssh-agent on or off for the open vault
+#[tauri::command]
+pub fn set_ssh_agent_enabled(app: AppHandle, db: State<Database>, enabled: bool) -> Result<SshAgentStatus, String> {
+    let mut changes = Map::new();
+    changes.insert("sshAgentEnabled".to_string(), Value::Bool(enabled));
+    change_settings(&app, &changes)?;
+    get_ssh_agent_status(app, db)
+}
+
+fn ssh_key_entry(group_id: Option<String>, title: String, comment: String, private_key: String) -> CreateEntryDto {
+    CreateEntryDto {
+        entry_type: Some(EntryType::SshKey),
+        group_id,
+        title,
+        url: None,
+        username: Some(comment),
+        password: Some(private_key),
+        notes: None,
+        url_match: None,
+        extra_urls: None,
+        fields: None,
+        template_id: None,
+        autotype_sequence: None,
+    }
+}
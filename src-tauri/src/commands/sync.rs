@@ -0,0 +1,218 @@
+use rusqlite::OptionalExtension;
+use serde_json::Value;
+use std::path::Path;
+use tauri::{AppHandle, Manager, State};
+use crate::backup::keys;
+use crate::commands::auth::derive_vault_key;
+use crate::commands::settings::read_sync_settings;
+use crate::crypto::{self, Session};
+use crate::db::Database;
+use crate::settings;
+use crate::models::{SyncBackend, SyncConflict, SyncReport, SyncSettings};
+use crate::sync::engine::{self, RecordKind};
+use crate::sync::folder::FolderTransport;
+use crate::sync::webdav::{self, WebDavCredentials, WebDavTransport};
+use crate::sync::{scheduler, transport};
+
+#[tauri::command]
+pub fn get_sync_settings(db: State<Database>) -> Result<SyncSettings, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    Ok(read_sync_settings(&conn))
+}
+
+/// Start syncing and run the first sync
+/// password: the master password, which must be the same on every device
+/// webdav_username/webdav_password: the WebDAV login, kept from before when omitted
+#[tauri::command]
+pub async fn enable_sync(
+    app: AppHandle,
+    settings: SyncSettings,
+    password: String,
+    webdav_username: Option<String>,
+    webdav_password: Option<String>,
+) -> Result<SyncReport, String> {
+    let credentials = match (webdav_username, webdav_password) {
+        (Some(username), Some(password)) => Some(WebDavCredentials { username, password }),
+        _ => None,
+    };
+
+    // Joining may talk to a server, keep it off the main thread
+    tauri::async_runtime::spawn_blocking(move || join_sync(&app, settings, &password, credentials))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+pub fn disable_sync(db: State<Database>) -> Result<(), String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    settings::set(&conn, "sync_enabled", &Value::Bool(false))
+}
+
+/// Sync right away, regardless of the interval
+#[tauri::command]
+pub async fn sync_now(app: AppHandle) -> Result<SyncReport, String> {
+    tauri::async_runtime::spawn_blocking(move || scheduler::run_now(&app))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Conflicts resolved automatically during sync, newest first
+#[tauri::command]
+pub fn get_sync_conflicts(db: State<Database>) -> Result<Vec<SyncConflict>, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, record_type, record_id, title, discarded_device, discarded_data, detected_at
+             FROM sync_conflicts ORDER BY detected_at DESC",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let conflicts = stmt
+        .query_map([], |row| {
+            let data: Option<String> = row.get(5)?;
+            Ok(SyncConflict {
+                id: row.get(0)?,
+                record_type: row.get(1)?,
+                record_id: row.get(2)?,
+                title: row.get(3)?,
+                discarded_device: row.get(4)?,
+                discarded_data: data.and_then(|d| serde_json::from_str(&d).ok()),
+                detected_at: row.get(6)?,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok(conflicts)
+}
+
+/// Dismiss a conflict, or bring back the discarded version as a new edit
+#[tauri::command]
+pub fn resolve_sync_conflict(db: State<Database>, id: String, restore: bool) -> Result<(), String> {
+    let mut conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let conflict: Option<(String, String, Option<String>)> = tx
+        .query_row(
+            "SELECT record_type, record_id, discarded_data FROM sync_conflicts WHERE id = ?1",
+            [&id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    let (record_type, record_id, data) = conflict.ok_or("冲突记录不存在")?;
+
+    if restore {
+        let kind = RecordKind::parse(&record_type).ok_or("冲突记录格式错误")?;
+        let data = match data {
+            Some(data) => {
+                let mut value: serde_json::Value =
+                    serde_json::from_str(&data).map_err(|e| e.to_string())?;
+                // Restoring counts as a new edit so it wins on the next sync
+                value["updatedAt"] = chrono::Utc::now().timestamp().into();
+                Some(value)
+            }
+            None => None,
+        };
+        engine::apply_version(&tx, kind, &record_id, data.as_ref())?;
+    }
+
+    tx.execute("DELETE FROM sync_conflicts WHERE id = ?1", [&id])
+        .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// Helper: check the sync target, adopt its key and save the settings
+fn join_sync(
+    app: &AppHandle,
+    settings: SyncSettings,
+    password: &str,
+    credentials: Option<WebDavCredentials>,
+) -> Result<SyncReport, String> {
+    if settings.interval_minutes < 1 {
+        return Err("同步间隔不能小于1分钟".to_string());
+    }
+    let directory = settings.directory.trim().to_string();
+    let webdav_url = settings.webdav_url.trim().to_string();
+
+    let db = app.state::<Database>();
+    let session = app.state::<Session>();
+
+    let current = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        let hash: String = conn
+            .query_row(
+                "SELECT value FROM settings WHERE key = 'master_password_hash'",
+                [],
+                |row| row.get(0),
+            )
+            .map_err(|_| "未设置主密码".to_string())?;
+        if !bcrypt::verify(password, &hash).map_err(|e| e.to_string())? {
+            return Err("主密码不正确".to_string());
+        }
+        derive_vault_key(&conn, password)?
+    };
+
+    // Every device must derive the same key, so adopt the target's salt
+    let (key, credentials) = match settings.backend {
+        SyncBackend::Folder => {
+            if directory.is_empty() || !Path::new(&directory).is_absolute() {
+                return Err("同步目录必须是绝对路径".to_string());
+            }
+            let key = transport::join(&FolderTransport::new(&directory), password, &current)?;
+            (key, None)
+        }
+        SyncBackend::Webdav => {
+            let credentials = match credentials {
+                Some(credentials) => credentials,
+                None => {
+                    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+                    webdav::load_credentials(&conn, &current)?.ok_or("请填写 WebDAV 用户名和密码")?
+                }
+            };
+            let remote = WebDavTransport::new(&webdav_url, credentials.clone(), None)?;
+            let key = transport::join(&remote, password, &current)?;
+            (key, Some(credentials))
+        }
+    };
+
+    {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        webdav::reencrypt_credentials(&conn, &current, &key)?;
+        keys::ensure(&conn, &key)?;
+        if let Some(ref credentials) = credentials {
+            webdav::save_credentials(&conn, &key, credentials)?;
+        }
+
+        let backend = match settings.backend {
+            SyncBackend::Folder => "folder",
+            SyncBackend::Webdav => "webdav",
+        };
+        conn.execute(
+            "INSERT OR REPLACE INTO settings (key, value) VALUES ('vault_salt', ?1)",
+            [crypto::to_hex(&key.salt)],
+        )
+        .map_err(|e| e.to_string())?;
+        let values = [
+            ("sync_enabled", Value::Bool(true)),
+            ("sync_backend", Value::from(backend)),
+            ("sync_directory", Value::from(directory)),
+            ("sync_webdav_url", Value::from(webdav_url)),
+            ("sync_interval_minutes", Value::from(settings.interval_minutes)),
+        ];
+        for (key, value) in &values {
+            crate::settings::set(&conn, key, value)?;
+        }
+        conn.execute(
+            "DELETE FROM settings WHERE key IN ('sync_log_hash', 'webdav_log_etag')",
+            [],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+
+    session.set_key(key);
+    scheduler::run_now(app)
+}
@@ -0,0 +1,316 @@
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use tauri::{AppHandle, State};
+use tauri_plugin_dialog::DialogExt;
+use crate::db::Database;
+use crate::entry_types::validation;
+use crate::models::{
+    CreateEntryDto, CreateTemplateDto, EntryField, EntryTemplate, EntryType, TemplateField, TemplateImportResult,
+    UpdateTemplateDto,
+};
+
+const TEMPLATE_COLUMNS: &str = "id, name, icon, base_type, fields, created_at, updated_at";
+
+/// Templates shared as a file of their own
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TemplateExport {
+    version: String,
+    export_date: String,
+    templates: Vec<EntryTemplate>,
+}
+
+/// Map a row selected with `TEMPLATE_COLUMNS` to an `EntryTemplate`
+fn template_from_row(row: &rusqlite::Row) -> rusqlite::Result<EntryTemplate> {
+    Ok(EntryTemplate {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        icon: row.get(2)?,
+        base_type: EntryType::parse(&row.get::<_, String>(3)?),
+        fields: serde_json::from_str(&row.get::<_, String>(4)?).unwrap_or_default(),
+        created_at: row.get(5)?,
+        updated_at: row.get(6)?,
+    })
+}
+
+/// Query all templates by name
+pub(crate) fn query_templates(conn: &Connection) -> Result<Vec<EntryTemplate>, String> {
+    let mut stmt = conn
+        .prepare(&format!("SELECT {} FROM entry_templates ORDER BY name ASC", TEMPLATE_COLUMNS))
+        .map_err(|e| e.to_string())?;
+
+    let templates = stmt
+        .query_map([], template_from_row)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok(templates)
+}
+
+pub(crate) fn find_template(conn: &Connection, id: &str) -> Result<EntryTemplate, String> {
+    conn.query_row(
+        &format!("SELECT {} FROM entry_templates WHERE id = ?1", TEMPLATE_COLUMNS),
+        [id],
+        template_from_row,
+    )
+    .map_err(|e| match e {
+        rusqlite::Error::QueryReturnedNoRows => "模板不存在".to_string(),
+        e => e.to_string(),
+    })
+}
+
+/// Insert a template or replace the stored copy with the same id
+fn upsert_template(conn: &Connection, template: &EntryTemplate) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO entry_templates (id, name, icon, base_type, fields, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+         ON CONFLICT(id) DO UPDATE SET
+             name = excluded.name,
+             icon = excluded.icon,
+             base_type = excluded.base_type,
+             fields = excluded.fields,
+             created_at = excluded.created_at,
+             updated_at = excluded.updated_at",
+        rusqlite::params![
+            template.id,
+            template.name,
+            template.icon,
+            template.base_type.as_str(),
+            serde_json::to_string(&template.fields).map_err(|e| e.to_string())?,
+            template.created_at,
+            template.updated_at,
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// Reject unnamed templates, unnamed or repeated fields and defaults that don't fit their kind
+fn validate_template(template: &EntryTemplate) -> Result<(), String> {
+    if template.name.trim().is_empty() {
+        return Err("模板名称不能为空".to_string());
+    }
+
+    let mut names = HashSet::new();
+    for field in &template.fields {
+        let name = field.name.trim();
+        if name.is_empty() {
+            return Err("模板字段名称不能为空".to_string());
+        }
+        if !names.insert(name) {
+            return Err(format!("模板字段重复: {}", name));
+        }
+        if !field.default_value.trim().is_empty() {
+            validation::check(field.kind, &field.default_value).map_err(|e| format!("{}: {}", name, e))?;
+        }
+    }
+    Ok(())
+}
+
+/// Fill an entry being created with the template's type and field defaults; values already set are kept
+pub(crate) fn apply_template(template: &EntryTemplate, entry: &mut CreateEntryDto) -> Result<(), String> {
+    entry.entry_type.get_or_insert(template.base_type);
+    let fields = entry.fields.get_or_insert_with(Vec::new);
+
+    for spec in &template.fields {
+        let column = match spec.name.as_str() {
+            "url" => Some(&mut entry.url),
+            "username" => Some(&mut entry.username),
+            "password" => Some(&mut entry.password),
+            "notes" => Some(&mut entry.notes),
+            _ => None,
+        };
+
+        let value = match column {
+            Some(column) => {
+                if column.as_deref().is_none_or(str::is_empty) {
+                    *column = Some(spec.default_value.clone());
+                }
+                column.clone().unwrap_or_default()
+            }
+            None => match fields.iter_mut().find(|f| f.name == spec.name) {
+                Some(field) => {
+                    field.kind = spec.kind;
+                    field.value.clone()
+                }
+                None => {
+                    fields.push(EntryField {
+                        name: spec.name.clone(),
+                        value: spec.default_value.clone(),
+                        kind: spec.kind,
+                    });
+                    spec.default_value.clone()
+                }
+            },
+        };
+
+        if spec.required && value.trim().is_empty() {
+            return Err(format!("{}不能为空", spec.name));
+        }
+    }
+    Ok(())
+}
+
+fn trimmed_fields(fields: Vec<TemplateField>) -> Vec<TemplateField> {
+    fields
+        .into_iter()
+        .map(|f| TemplateField { name: f.name.trim().to_string(), ..f })
+        .collect()
+}
+
+#[tauri::command]
+pub fn get_templates(db: State<Database>) -> Result<Vec<EntryTemplate>, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    query_templates(&conn)
+}
+
+#[tauri::command]
+pub fn create_template(db: State<Database>, template: CreateTemplateDto) -> Result<EntryTemplate, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let now = chrono::Utc::now().timestamp();
+
+    let template = EntryTemplate {
+        id: uuid::Uuid::new_v4().to_string(),
+        name: template.name.trim().to_string(),
+        icon: template.icon.unwrap_or_else(|| "📋".to_string()),
+        base_type: template.base_type.unwrap_or_default(),
+        fields: trimmed_fields(template.fields),
+        created_at: now,
+        updated_at: now,
+    };
+    validate_template(&template)?;
+    upsert_template(&conn, &template)?;
+
+    Ok(template)
+}
+
+/// Change a template; entries already created from it keep their fields
+#[tauri::command]
+pub fn update_template(db: State<Database>, id: String, template: UpdateTemplateDto) -> Result<EntryTemplate, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let current = find_template(&conn, &id)?;
+
+    let updated = EntryTemplate {
+        id: current.id,
+        name: template.name.map(|n| n.trim().to_string()).unwrap_or(current.name),
+        icon: template.icon.unwrap_or(current.icon),
+        base_type: template.base_type.unwrap_or(current.base_type),
+        fields: template.fields.map(trimmed_fields).unwrap_or(current.fields),
+        created_at: current.created_at,
+        updated_at: chrono::Utc::now().timestamp(),
+    };
+    validate_template(&updated)?;
+    upsert_template(&conn, &updated)?;
+
+    Ok(updated)
+}
+
+#[tauri::command]
+pub fn delete_template(db: State<Database>, id: String) -> Result<(), String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    conn.execute("DELETE FROM entry_templates WHERE id = ?1", [&id])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Export templates as a JSON string
+/// ids: templates to include; all templates when not set
+#[tauri::command]
+pub fn export_templates(db: State<Database>, ids: Option<Vec<String>>) -> Result<String, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let mut templates = query_templates(&conn)?;
+    if let Some(ids) = ids {
+        templates.retain(|t| ids.contains(&t.id));
+    }
+
+    let export = TemplateExport {
+        version: "1.0".to_string(),
+        export_date: chrono::Utc::now().to_rfc3339(),
+        templates,
+    };
+
+    serde_json::to_string_pretty(&export).map_err(|e| e.to_string())
+}
+
+/// Import templates from JSON string; templates with a known id replace the stored copy
+#[tauri::command]
+pub fn import_templates(db: State<Database>, json_data: String) -> Result<TemplateImportResult, String> {
+    let import: TemplateExport =
+        serde_json::from_str(&json_data).map_err(|e| format!("无效的模板文件格式: {}", e))?;
+
+    // Validate the whole file before touching any data
+    let mut ids = HashSet::new();
+    for template in &import.templates {
+        if !ids.insert(template.id.as_str()) {
+            return Err(format!("模板文件中存在重复的ID: {}", template.id));
+        }
+        validate_template(template).map_err(|e| format!("模板 \"{}\": {}", template.name, e))?;
+    }
+
+    let mut conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let mut result = TemplateImportResult { imported: 0, updated: 0 };
+    for template in &import.templates {
+        let exists: bool = tx
+            .query_row(
+                "SELECT EXISTS(SELECT 1 FROM entry_templates WHERE id = ?1)",
+                [&template.id],
+                |row| row.get(0),
+            )
+            .map_err(|e| e.to_string())?;
+        upsert_template(&tx, template)?;
+        if exists {
+            result.updated += 1;
+        } else {
+            result.imported += 1;
+        }
+    }
+
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(result)
+}
+
+/// Open save dialog and write exported templates to file
+#[tauri::command]
+pub async fn save_templates_file(app: AppHandle, content: String) -> Result<(), String> {
+    let file_path = app
+        .dialog()
+        .file()
+        .set_title("保存模板文件")
+        .add_filter("JSON", &["json"])
+        .set_file_name("one-password-templates.json")
+        .blocking_save_file();
+
+    match file_path {
+        Some(path) => {
+            let path = path.into_path().map_err(|e| e.to_string())?;
+            fs::write(path, content).map_err(|e| format!("无法写入文件: {}", e))?;
+            Ok(())
+        }
+        None => Err("用户取消保存".to_string()),
+    }
+}
+
+/// Open file dialog and read a template file
+#[tauri::command]
+pub async fn load_templates_file(app: AppHandle) -> Result<String, String> {
+    let file_path = app
+        .dialog()
+        .file()
+        .set_title("选择模板文件")
+        .add_filter("JSON", &["json"])
+        .blocking_pick_file();
+
+    match file_path {
+        Some(path) => {
+            let path = path.into_path().map_err(|e| e.to_string())?;
+            fs::read_to_string(path).map_err(|e| format!("无法读取文件: {}", e))
+        }
+        None => Err("用户取消导入".to_string()),
+    }
+}
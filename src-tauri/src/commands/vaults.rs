@@ -0,0 +1,127 @@
+use serde::Serialize;
+use std::path::PathBuf;
+use tauri::{AppHandle, Emitter, Manager, State};
+use crate::crypto::Session;
+use crate::db::vaults::{self, Vault, VaultRegistry};
+use crate::db::Database;
+use crate::health::HealthState;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VaultInfo {
+    name: String,
+    path: String,
+    exists: bool,
+    active: bool,
+}
+
+/// List registered vaults; the open vault is marked active
+#[tauri::command]
+pub fn list_vaults(db: State<Database>) -> Result<Vec<VaultInfo>, String> {
+    let registry = VaultRegistry::load()?;
+    let current = db.path();
+
+    Ok(registry
+        .vaults
+        .iter()
+        .map(|v| VaultInfo {
+            name: v.name.clone(),
+            path: v.path.to_string_lossy().to_string(),
+            exists: v.path.exists(),
+            active: v.path == current,
+        })
+        .collect())
+}
+
+/// Register a vault location; the file is created when the vault is first opened
+/// path: a database file, or a directory to hold data.db
+#[tauri::command]
+pub fn add_vault(app: AppHandle, name: String, path: String) -> Result<(), String> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("保险库名称不能为空".to_string());
+    }
+
+    let path = PathBuf::from(path.trim());
+    if !path.is_absolute() {
+        return Err("保险库路径必须是绝对路径".to_string());
+    }
+    let path = vaults::normalize_vault_path(&path);
+
+    let mut registry = VaultRegistry::load()?;
+    if registry.find(&name).is_some() {
+        return Err("保险库名称已存在".to_string());
+    }
+    if registry.vaults.iter().any(|v| v.path == path) {
+        return Err("该位置已注册为保险库".to_string());
+    }
+
+    registry.vaults.push(Vault { name, path });
+    registry.save()?;
+
+    crate::refresh_tray(&app);
+    Ok(())
+}
+
+/// Remove a vault from the list; the database file itself is kept
+#[tauri::command]
+pub fn remove_vault(app: AppHandle, db: State<Database>, name: String) -> Result<(), String> {
+    let mut registry = VaultRegistry::load()?;
+    let vault = registry.find(&name).ok_or("保险库不存在")?;
+    if vault.path == db.path() {
+        return Err("无法移除当前打开的保险库".to_string());
+    }
+
+    registry.vaults.retain(|v| v.name != name);
+    if registry.active.as_deref() == Some(name.as_str()) {
+        registry.active = None;
+    }
+    registry.save()?;
+
+    crate::refresh_tray(&app);
+    Ok(())
+}
+
+/// Close the current vault and open another one; the app is locked afterwards
+#[tauri::command]
+pub fn switch_vault(app: AppHandle, name: String) -> Result<(), String> {
+    switch_to(&app, &name)
+}
+
+/// Switch vaults from commands or the tray menu
+pub(crate) fn switch_to(app: &AppHandle, name: &str) -> Result<(), String> {
+    let mut registry = VaultRegistry::load()?;
+    let vault = registry.find(name).ok_or("保险库不存在")?.clone();
+
+    let db = app.state::<Database>();
+    #[cfg(unix)]
+    let old_path = db.path();
+    db.reopen(&vault.path)
+        .map_err(|e| format!("无法打开保险库: {}", e))?;
+
+    // Keys and breach results belong to the previous vault
+    app.state::<Session>().lock();
+    app.state::<HealthState>().clear();
+    #[cfg(unix)]
+    {
+        crate::agent::restart_for_app(app, &old_path);
+        crate::ssh::agent::stop(app);
+        crate::ssh::agent::start_for_app(app);
+    }
+    #[cfg(target_os = "linux")]
+    {
+        crate::secret_service::stop(app);
+        crate::secret_service::start_for_app(app);
+    }
+
+    // Shortcuts are stored per vault
+    crate::shortcuts::register_all(app);
+
+    registry.active = Some(vault.name.clone());
+    registry.save()?;
+
+    let _ = app.emit("vault-changed", &vault.name);
+    crate::refresh_tray(app);
+
+    Ok(())
+}
@@ -1,4 +1,5 @@
-use tauri::{AppHandle, Manager, WebviewUrl, WebviewWindowBuilder};
+use tauri::{AppHandle, Manager, State, WebviewUrl, WebviewWindowBuilder};
+use crate::deep_link::{DeepLink, PendingDeepLink};
 
 #[tauri::command]
 pub async fn toggle_quick_access(app: AppHandle) -> Result<(), String> {
@@ -8,10 +9,12 @@ pub async fn toggle_quick_access(app: AppHandle) -> Result<(), String> {
         if is_visible {
             window.hide().map_err(|e| e.to_string())?;
         } else {
+            crate::autotype::remember_target(&app);
             window.show().map_err(|e| e.to_string())?;
             window.set_focus().map_err(|e| e.to_string())?;
         }
     } else {
+        crate::autotype::remember_target(&app);
         // Create new window
         let window = WebviewWindowBuilder::new(
             &app,
@@ -39,9 +42,13 @@ pub async fn toggle_quick_access(app: AppHandle) -> Result<(), String> {
 #[tauri::command]
 pub async fn show_quick_access(app: AppHandle) -> Result<(), String> {
     if let Some(window) = app.get_webview_window("quick-access") {
+        if !window.is_visible().unwrap_or(false) {
+            crate::autotype::remember_target(&app);
+        }
         window.show().map_err(|e| e.to_string())?;
         window.set_focus().map_err(|e| e.to_string())?;
     } else {
+        crate::autotype::remember_target(&app);
         // Create new window
         let window = WebviewWindowBuilder::new(
             &app,
@@ -80,3 +87,9 @@ pub async fn close_quick_access(app: AppHandle) -> Result<(), String> {
     }
     Ok(())
 }
+
+/// Link opened since the main window last asked, see `deep_link::PendingDeepLink`
+#[tauri::command]
+pub fn take_deep_link(pending: State<PendingDeepLink>) -> Option<DeepLink> {
+    pending.take()
+}
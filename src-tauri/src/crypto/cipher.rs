@@ -0,0 +1,94 @@
+use argon2::Argon2;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+
+pub const KEY_LEN: usize = 32;
+pub const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+
+/// Symmetric key derived from the master password
+#[derive(Clone)]
+pub struct VaultKey {
+    pub salt: [u8; SALT_LEN],
+    key: [u8; KEY_LEN],
+}
+
+impl Drop for VaultKey {
+    fn drop(&mut self) {
+        self.key.fill(0);
+    }
+}
+
+impl VaultKey {
+    /// Key that is not derived from a password, e.g. a browser pairing key; the salt is unused
+    pub fn from_bytes(key: [u8; KEY_LEN]) -> Self {
+        VaultKey { salt: [0u8; SALT_LEN], key }
+    }
+}
+
+/// Generate a random salt for key derivation
+pub fn random_salt() -> [u8; SALT_LEN] {
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    salt
+}
+
+/// Derive an encryption key from a password with Argon2id
+pub fn derive_key(password: &str, salt: &[u8; SALT_LEN]) -> Result<VaultKey, String> {
+    let mut key = [0u8; KEY_LEN];
+    Argon2::default()
+        .hash_password_into(password.as_bytes(), salt, &mut key)
+        .map_err(|e| format!("密钥派生失败: {}", e))?;
+
+    Ok(VaultKey { salt: *salt, key })
+}
+
+/// Encrypt data with XChaCha20-Poly1305, output is nonce || ciphertext
+pub fn encrypt(key: &VaultKey, plaintext: &[u8]) -> Result<Vec<u8>, String> {
+    let cipher = XChaCha20Poly1305::new(&key.key.into());
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, plaintext)
+        .map_err(|_| "加密失败".to_string())?;
+
+    let mut out = Vec::with_capacity(NONCE_LEN + ciphertext.len());
+    out.extend_from_slice(&nonce);
+    out.extend_from_slice(&ciphertext);
+    Ok(out)
+}
+
+/// Decrypt data produced by `encrypt`
+pub fn decrypt(key: &VaultKey, data: &[u8]) -> Result<Vec<u8>, String> {
+    if data.len() < NONCE_LEN {
+        return Err("加密数据已损坏".to_string());
+    }
+
+    let (nonce, ciphertext) = data.split_at(NONCE_LEN);
+    let cipher = XChaCha20Poly1305::new(&key.key.into());
+    cipher
+        .decrypt(XNonce::from_slice(nonce), ciphertext)
+        .map_err(|_| "密码错误或数据已损坏".to_string())
+}
+
+/// Encode bytes as lowercase hex, e.g. salts stored in the settings table
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Decode a hex string produced by `to_hex`
+pub fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// Decode a hex encoded key salt
+pub fn salt_from_hex(hex: &str) -> Option<[u8; SALT_LEN]> {
+    from_hex(hex)?.try_into().ok()
+}
@@ -0,0 +1,79 @@
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::OsRng;
+
+const LOWERCASE: &str = "abcdefghijkmnopqrstuvwxyz";
+const UPPERCASE: &str = "ABCDEFGHJKLMNPQRSTUVWXYZ";
+const DIGITS: &str = "23456789";
+const SYMBOLS: &str = "!@#$%^&*-_=+?";
+
+pub const MIN_LENGTH: usize = 4;
+pub const MAX_LENGTH: usize = 128;
+
+/// Character classes and length of a generated password
+/// Look-alike characters (l, I, O, 0, 1) are left out.
+#[derive(Debug, Clone)]
+pub struct PasswordOptions {
+    pub length: usize,
+    pub lowercase: bool,
+    pub uppercase: bool,
+    pub digits: bool,
+    pub symbols: bool,
+}
+
+impl Default for PasswordOptions {
+    fn default() -> Self {
+        PasswordOptions {
+            length: 20,
+            lowercase: true,
+            uppercase: true,
+            digits: true,
+            symbols: true,
+        }
+    }
+}
+
+/// Generate a random password containing at least one character of every enabled class
+pub fn generate_password(options: &PasswordOptions) -> Result<String, String> {
+    if !(MIN_LENGTH..=MAX_LENGTH).contains(&options.length) {
+        return Err(format!("密码长度必须在 {} 到 {} 之间", MIN_LENGTH, MAX_LENGTH));
+    }
+
+    let classes: Vec<Vec<char>> = [
+        (options.lowercase, LOWERCASE),
+        (options.uppercase, UPPERCASE),
+        (options.digits, DIGITS),
+        (options.symbols, SYMBOLS),
+    ]
+    .into_iter()
+    .filter(|(enabled, _)| *enabled)
+    .map(|(_, chars)| chars.chars().collect())
+    .collect();
+    if classes.is_empty() {
+        return Err("至少需要选择一种字符类型".to_string());
+    }
+
+    let all: Vec<char> = classes.iter().flatten().copied().collect();
+    let mut password: Vec<char> = classes.iter().map(|class| class[random_index(class.len())]).collect();
+    while password.len() < options.length {
+        password.push(all[random_index(all.len())]);
+    }
+
+    // Fisher-Yates, so the guaranteed characters are not always up front
+    for i in (1..password.len()).rev() {
+        password.swap(i, random_index(i + 1));
+    }
+
+    Ok(password.into_iter().collect())
+}
+
+/// Helper: uniform index below `bound` without modulo bias
+fn random_index(bound: usize) -> usize {
+    let bound = bound as u32;
+    let zone = u32::MAX - u32::MAX % bound;
+    loop {
+        let value = OsRng.next_u32();
+        if value < zone {
+            return (value % bound) as usize;
+        }
+    }
+}
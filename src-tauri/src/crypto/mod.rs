@@ -0,0 +1,7 @@
+pub mod cipher;
+pub mod generator;
+pub mod session;
+
+pub use cipher::{decrypt, derive_key, encrypt, from_hex, random_salt, salt_from_hex, to_hex, VaultKey, SALT_LEN};
+pub use generator::{generate_password, PasswordOptions};
+pub use session::Session;
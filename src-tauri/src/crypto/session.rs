@@ -0,0 +1,59 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use super::cipher::VaultKey;
+
+/// In-memory key material and lock state of the open vault
+/// The key is wiped when the vault locks; scheduled backups only need the public backup key.
+#[derive(Default)]
+pub struct Session {
+    key: Mutex<Option<VaultKey>>,
+    /// Time of the last activity while unlocked, None when locked
+    unlocked: Mutex<Option<Instant>>,
+}
+
+impl Session {
+    pub fn set_key(&self, key: VaultKey) {
+        if let Ok(mut guard) = self.key.lock() {
+            *guard = Some(key);
+        }
+    }
+
+    pub fn key(&self) -> Option<VaultKey> {
+        self.key.lock().ok().and_then(|guard| guard.clone())
+    }
+
+    /// Returns whether the session was locked before
+    pub fn unlock(&self) -> bool {
+        self.unlocked
+            .lock()
+            .map(|mut guard| guard.replace(Instant::now()).is_none())
+            .unwrap_or(false)
+    }
+
+    /// Wipe the key; returns whether the session was unlocked before
+    pub fn lock(&self) -> bool {
+        if let Ok(mut guard) = self.key.lock() {
+            *guard = None;
+        }
+        self.unlocked.lock().map(|mut guard| guard.take().is_some()).unwrap_or(false)
+    }
+
+    pub fn is_unlocked(&self) -> bool {
+        self.unlocked.lock().map(|guard| guard.is_some()).unwrap_or(false)
+    }
+
+    /// Record activity so the idle timer restarts
+    pub fn touch(&self) {
+        if let Ok(mut guard) = self.unlocked.lock() {
+            if guard.is_some() {
+                *guard = Some(Instant::now());
+            }
+        }
+    }
+
+    /// Time since the last activity, None when locked
+    pub fn idle_for(&self) -> Option<Duration> {
+        self.unlocked.lock().ok().and_then(|guard| guard.map(|at| at.elapsed()))
+    }
+}
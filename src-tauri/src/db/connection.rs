@@ -1,30 +1,61 @@
 use rusqlite::Connection;
+use std::collections::HashSet;
 use std::fs;
-use std::path::PathBuf;
-use std::sync::Mutex;
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 use super::migrations;
 
-pub struct Database {
-    pub conn: Mutex<Connection>,
+/// How long a statement waits for another process (e.g. the CLI) to finish writing
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Rows of the `entries` table written since the last `take_entry_changes`
+#[derive(Debug, Default)]
+pub struct EntryChanges {
+    pub rowids: HashSet<i64>,
+    /// The whole table may have changed, e.g. after switching vaults or restoring a backup
+    pub reset: bool,
 }
 
-impl Database {
-    pub fn new() -> Result<Self, Box<dyn std::error::Error>> {
-        let db_path = Self::get_db_path()?;
+/// Shared with the update hook of the connection
+#[derive(Default)]
+struct EntryWatch {
+    changes: Mutex<EntryChanges>,
+    /// Notified after every change, e.g. to rebuild the tray menu
+    listeners: Mutex<Vec<Sender<()>>>,
+}
 
-        // Ensure parent directory exists
-        if let Some(parent) = db_path.parent() {
-            fs::create_dir_all(parent)?;
+impl EntryWatch {
+    fn notify(&self) {
+        if let Ok(mut listeners) = self.listeners.lock() {
+            listeners.retain(|listener| listener.send(()).is_ok());
         }
+    }
+}
 
-        let conn = Connection::open(&db_path)?;
+pub struct Database {
+    pub conn: Mutex<Connection>,
+    path: Mutex<PathBuf>,
+    entry_watch: Arc<EntryWatch>,
+    /// `PRAGMA data_version` last seen, which changes when another connection commits
+    data_version: Mutex<i64>,
+}
 
-        // Enable WAL mode for better performance
-        conn.execute_batch("PRAGMA journal_mode=WAL; PRAGMA foreign_keys=ON;")?;
+impl Database {
+    pub fn new(db_path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let conn = Self::open_connection(db_path)?;
+        let entry_watch = Arc::new(EntryWatch::default());
+        watch_entries(&conn, &entry_watch);
+        let data_version = data_version(&conn)?;
 
         let db = Database {
             conn: Mutex::new(conn),
+            path: Mutex::new(db_path.to_path_buf()),
+            entry_watch,
+            data_version: Mutex::new(data_version),
         };
 
         // Run migrations
@@ -33,10 +64,102 @@ impl Database {
         Ok(db)
     }
 
-    fn get_db_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
-        let data_dir = dirs::data_dir()
-            .ok_or("Failed to get data directory")?;
+    /// Switch to the database file at `db_path`, replacing the current connection
+    pub fn reopen(&self, db_path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        // Open and migrate the new file before touching the current connection
+        let fresh = Database::new(db_path)?;
+        let conn = fresh.conn.into_inner().map_err(|e| format!("Lock error: {}", e))?;
+        watch_entries(&conn, &self.entry_watch);
+        let version = data_version(&conn)?;
+
+        let mut current = self.conn.lock().map_err(|e| format!("Lock error: {}", e))?;
+        let mut path = self.path.lock().map_err(|e| format!("Lock error: {}", e))?;
+        *current = conn;
+        if let Ok(mut last) = self.data_version.lock() {
+            *last = version;
+        }
+        *path = db_path.to_path_buf();
+        self.invalidate_entries();
+
+        Ok(())
+    }
+
+    /// Entry rows changed since the previous call, for caches such as the search index
+    pub fn take_entry_changes(&self) -> EntryChanges {
+        self.entry_watch
+            .changes
+            .lock()
+            .map(|mut changes| mem::take(&mut *changes))
+            .unwrap_or(EntryChanges { reset: true, ..Default::default() })
+    }
+
+    /// Report every entry as changed, for writes that bypass SQL such as restoring a backup
+    pub fn invalidate_entries(&self) {
+        if let Ok(mut changes) = self.entry_watch.changes.lock() {
+            changes.reset = true;
+        }
+        self.entry_watch.notify();
+    }
+
+    /// Report every entry as changed if another process, e.g. the CLI, wrote to the file
+    /// since the last check; the update hook only sees writes made through `conn`.
+    pub fn check_outside_writes(&self, conn: &Connection) {
+        let Ok(version) = data_version(conn) else { return };
+        let changed = match self.data_version.lock() {
+            Ok(mut last) => mem::replace(&mut *last, version) != version,
+            Err(_) => false,
+        };
+        if changed {
+            self.invalidate_entries();
+        }
+    }
+
+    /// Receives a message after every entry change; a burst of writes sends one per row
+    pub fn subscribe_entry_changes(&self) -> Receiver<()> {
+        let (sender, receiver) = mpsc::channel();
+        if let Ok(mut listeners) = self.entry_watch.listeners.lock() {
+            listeners.push(sender);
+        }
+        receiver
+    }
+
+    /// Location of the SQLite file backing this database
+    pub fn path(&self) -> PathBuf {
+        self.path
+            .lock()
+            .map(|p| p.clone())
+            .unwrap_or_default()
+    }
+
+    fn open_connection(db_path: &Path) -> Result<Connection, Box<dyn std::error::Error>> {
+        // Ensure parent directory exists
+        if let Some(parent) = db_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let conn = Connection::open(db_path)?;
+        conn.busy_timeout(BUSY_TIMEOUT)?;
+
+        // Enable WAL mode for better performance
+        conn.execute_batch("PRAGMA journal_mode=WAL; PRAGMA foreign_keys=ON;")?;
 
-        Ok(data_dir.join("one-password").join("data.db"))
+        Ok(conn)
     }
 }
+
+fn data_version(conn: &Connection) -> rusqlite::Result<i64> {
+    conn.query_row("PRAGMA data_version", [], |row| row.get(0))
+}
+
+/// Record the rowid of every entry inserted, updated or deleted through `conn`
+fn watch_entries(conn: &Connection, watch: &Arc<EntryWatch>) {
+    let watch = Arc::clone(watch);
+    conn.update_hook(Some(move |_, _: &str, table: &str, rowid| {
+        if table == "entries" {
+            if let Ok(mut changes) = watch.changes.lock() {
+                changes.rowids.insert(rowid);
+            }
+            watch.notify();
+        }
+    }));
+}
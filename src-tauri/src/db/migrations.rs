@@ -1,3 +1,5 @@
+use rusqlite::Connection;
+
 use super::connection::Database;
 
 pub fn run(db: &Database) -> Result<(), Box<dyn std::error::Error>> {
@@ -41,27 +43,86 @@ pub fn run(db: &Database) -> Result<(), Box<dyn std::error::Error>> {
         );"
     )?;
 
-    // Create indexes
+    // Entry columns added after the first release
+    add_column(&conn, "entries", "url_match", "TEXT NOT NULL DEFAULT 'baseDomain'")?;
+    add_column(&conn, "entries", "extra_urls", "TEXT NOT NULL DEFAULT '[]'")?;
+    add_column(&conn, "entries", "entry_type", "TEXT NOT NULL DEFAULT 'login'")?;
+    add_column(&conn, "entries", "fields", "TEXT NOT NULL DEFAULT '[]'")?;
+    add_column(&conn, "entries", "autotype_sequence", "TEXT")?;
+    add_column(&conn, "groups", "autotype_sequence", "TEXT")?;
+    // Usage is local to this device and stays out of sync and export
+    add_column(&conn, "entries", "last_used_at", "INTEGER")?;
+    add_column(&conn, "entries", "use_count", "INTEGER NOT NULL DEFAULT 0")?;
+    conn.execute_batch("CREATE INDEX IF NOT EXISTS idx_entries_last_used_at ON entries(last_used_at)")?;
+
+    // Sync bookkeeping: per-record vector clocks, tombstones and resolved conflicts
     conn.execute_batch(
-        "CREATE INDEX IF NOT EXISTS idx_entries_group ON entries(group_id);
-         CREATE INDEX IF NOT EXISTS idx_entries_title ON entries(title);
-         CREATE INDEX IF NOT EXISTS idx_entries_favorite ON entries(is_favorite);"
+        "CREATE TABLE IF NOT EXISTS sync_state (
+            record_type TEXT NOT NULL,
+            record_id TEXT NOT NULL,
+            clock TEXT NOT NULL,
+            content_hash TEXT NOT NULL DEFAULT '',
+            deleted INTEGER NOT NULL DEFAULT 0,
+            updated_at INTEGER NOT NULL,
+            PRIMARY KEY (record_type, record_id)
+        );
+        CREATE TABLE IF NOT EXISTS sync_conflicts (
+            id TEXT PRIMARY KEY,
+            record_type TEXT NOT NULL,
+            record_id TEXT NOT NULL,
+            title TEXT NOT NULL DEFAULT '',
+            discarded_device TEXT NOT NULL,
+            discarded_data TEXT,
+            detected_at INTEGER NOT NULL
+        );"
     )?;
 
-    // Insert default settings if not exists
-    conn.execute(
-        "INSERT OR IGNORE INTO settings (key, value) VALUES (?1, ?2)",
-        ("auto_lock_minutes", "5"),
+    // Browser extensions paired with this device, keyed by the extension origin
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS browser_pairings (
+            extension_id TEXT PRIMARY KEY,
+            browser TEXT NOT NULL DEFAULT '',
+            key TEXT NOT NULL,
+            created_at INTEGER NOT NULL,
+            last_used_at INTEGER
+        );"
     )?;
-    conn.execute(
-        "INSERT OR IGNORE INTO settings (key, value) VALUES (?1, ?2)",
-        ("clear_clipboard_seconds", "30"),
+
+    // Lookup attributes of items stored through the Secret Service provider
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS secret_attributes (
+            entry_id TEXT NOT NULL,
+            name TEXT NOT NULL,
+            value TEXT NOT NULL,
+            PRIMARY KEY (entry_id, name),
+            FOREIGN KEY (entry_id) REFERENCES entries(id) ON DELETE CASCADE
+        );"
     )?;
-    conn.execute(
-        "INSERT OR IGNORE INTO settings (key, value) VALUES (?1, ?2)",
-        ("theme", "system"),
+
+    // User-defined entry templates
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS entry_templates (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            icon TEXT NOT NULL DEFAULT '📋',
+            base_type TEXT NOT NULL DEFAULT 'login',
+            fields TEXT NOT NULL DEFAULT '[]',
+            created_at INTEGER NOT NULL,
+            updated_at INTEGER NOT NULL
+        );"
     )?;
 
+    // Create indexes
+    conn.execute_batch(
+        "CREATE INDEX IF NOT EXISTS idx_entries_group ON entries(group_id);
+         CREATE INDEX IF NOT EXISTS idx_entries_title ON entries(title);
+         CREATE INDEX IF NOT EXISTS idx_entries_favorite ON entries(is_favorite);
+         CREATE INDEX IF NOT EXISTS idx_entries_type ON entries(entry_type);"
+    )?;
+
+    // Launch at login is read from the autostart entry, a row left by older versions would only mislead
+    conn.execute("DELETE FROM settings WHERE key = 'launch_at_login'", [])?;
+
     // Insert default groups if no groups exist
     let group_count: i64 = conn.query_row(
         "SELECT COUNT(*) FROM groups",
@@ -90,3 +151,16 @@ pub fn run(db: &Database) -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
+
+/// Add a column to an existing table unless it is already there
+fn add_column(conn: &Connection, table: &str, column: &str, definition: &str) -> rusqlite::Result<()> {
+    let exists: bool = conn.query_row(
+        &format!("SELECT EXISTS(SELECT 1 FROM pragma_table_info('{}') WHERE name = ?1)", table),
+        [column],
+        |row| row.get(0),
+    )?;
+    if !exists {
+        conn.execute_batch(&format!("ALTER TABLE {} ADD COLUMN {} {};", table, column, definition))?;
+    }
+    Ok(())
+}
@@ -1,4 +1,5 @@
 pub mod connection;
 pub mod migrations;
+pub mod vaults;
 
 pub use connection::Database;
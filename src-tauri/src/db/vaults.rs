@@ -0,0 +1,113 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const REGISTRY_FILE: &str = "vaults.json";
+pub const DEFAULT_VAULT_NAME: &str = "默认";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Vault {
+    pub name: String,
+    pub path: PathBuf,
+}
+
+/// Named vault locations, stored outside of any vault
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VaultRegistry {
+    pub active: Option<String>,
+    pub vaults: Vec<Vault>,
+}
+
+impl VaultRegistry {
+    /// Load the registry, always containing at least the default vault
+    pub fn load() -> Result<Self, String> {
+        let path = registry_path()?;
+        let mut registry: VaultRegistry = if path.exists() {
+            let content = fs::read_to_string(&path).map_err(|e| format!("无法读取保险库列表: {}", e))?;
+            serde_json::from_str(&content).map_err(|e| format!("保险库列表格式错误: {}", e))?
+        } else {
+            VaultRegistry::default()
+        };
+
+        if registry.vaults.is_empty() {
+            registry.vaults.push(Vault {
+                name: DEFAULT_VAULT_NAME.to_string(),
+                path: default_vault_path()?,
+            });
+        }
+
+        Ok(registry)
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let path = registry_path()?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        let content = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        fs::write(&path, content).map_err(|e| format!("无法保存保险库列表: {}", e))
+    }
+
+    pub fn find(&self, name: &str) -> Option<&Vault> {
+        self.vaults.iter().find(|v| v.name == name)
+    }
+
+    /// The vault opened on startup when no `--vault` argument is given
+    pub fn active_vault(&self) -> &Vault {
+        self.active
+            .as_deref()
+            .and_then(|name| self.find(name))
+            .unwrap_or(&self.vaults[0])
+    }
+}
+
+/// Resolve the vault to open on startup from `--vault <name|path>` or the registry
+pub fn startup_path(args: impl Iterator<Item = String>) -> Result<PathBuf, String> {
+    let registry = VaultRegistry::load()?;
+
+    match vault_arg(args) {
+        Some(arg) => Ok(match registry.find(&arg) {
+            Some(vault) => vault.path.clone(),
+            None => normalize_vault_path(Path::new(&arg)),
+        }),
+        None => Ok(registry.active_vault().path.clone()),
+    }
+}
+
+/// A directory means the default file name inside it
+pub fn normalize_vault_path(path: &Path) -> PathBuf {
+    if path.is_dir() {
+        path.join("data.db")
+    } else {
+        path.to_path_buf()
+    }
+}
+
+pub fn default_vault_path() -> Result<PathBuf, String> {
+    let data_dir = dirs::data_dir()
+        .ok_or("Failed to get data directory")?;
+
+    Ok(data_dir.join("one-password").join("data.db"))
+}
+
+fn registry_path() -> Result<PathBuf, String> {
+    let config_dir = dirs::config_dir()
+        .ok_or("Failed to get config directory")?;
+
+    Ok(config_dir.join("one-password").join(REGISTRY_FILE))
+}
+
+/// Helper: read `--vault <value>` or `--vault=<value>` from command line arguments
+pub(crate) fn vault_arg(mut args: impl Iterator<Item = String>) -> Option<String> {
+    while let Some(arg) = args.next() {
+        if arg == "--vault" {
+            return args.next();
+        }
+        if let Some(value) = arg.strip_prefix("--vault=") {
+            return Some(value.to_string());
+        }
+    }
+    None
+}
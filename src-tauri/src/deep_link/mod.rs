@@ -0,0 +1,136 @@
+use serde::Serialize;
+use std::path::Path;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_deep_link::DeepLinkExt;
+use url::Url;
+
+use crate::commands::vaults::switch_to;
+use crate::db::vaults::{self, VaultRegistry};
+use crate::db::Database;
+
+/// Scheme registered for the app, also set under `plugins.deep-link` in tauri.conf.json
+pub const SCHEME: &str = "onepassword";
+
+/// Where an `onepassword://` link asks the main window to go
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "action", rename_all = "camelCase")]
+pub enum DeepLink {
+    /// `onepassword://open/<entry-id>`
+    #[serde(rename_all = "camelCase")]
+    Open { entry_id: String },
+    /// `onepassword://search?q=<query>`
+    Search { query: String },
+}
+
+impl DeepLink {
+    pub fn parse(url: &Url) -> Result<Self, String> {
+        if url.scheme() != SCHEME {
+            return Err(format!("不支持的链接: {}", url));
+        }
+        let segments: Vec<&str> = url
+            .path_segments()
+            .map(|segments| segments.filter(|s| !s.is_empty()).collect())
+            .unwrap_or_default();
+
+        match (url.host_str(), segments.as_slice()) {
+            (Some("open"), [entry_id]) => Ok(DeepLink::Open {
+                entry_id: entry_id.to_string(),
+            }),
+            (Some("search"), []) => Ok(DeepLink::Search {
+                query: url
+                    .query_pairs()
+                    .find(|(key, _)| key == "q")
+                    .map(|(_, value)| value.into_owned())
+                    .unwrap_or_default(),
+            }),
+            _ => Err(format!("无法识别的链接: {}", url)),
+        }
+    }
+}
+
+/// Latest link not yet picked up by the main window
+/// Links can arrive before the frontend listens, so it takes them with `take_deep_link`
+/// when notified and once on startup.
+#[derive(Default)]
+pub struct PendingDeepLink(Mutex<Option<DeepLink>>);
+
+impl PendingDeepLink {
+    pub fn take(&self) -> Option<DeepLink> {
+        self.0.lock().ok().and_then(|mut pending| pending.take())
+    }
+}
+
+/// Register the scheme and route links from this launch and later ones to the main window
+pub fn setup(app: &AppHandle) {
+    // Installed bundles declare the scheme themselves; this covers portable and dev builds
+    #[cfg(any(windows, target_os = "linux"))]
+    if let Err(e) = app.deep_link().register_all() {
+        eprintln!("Warning: Failed to register {}:// links: {}", SCHEME, e);
+    }
+
+    let handle = app.clone();
+    app.deep_link().on_open_url(move |event| {
+        for url in event.urls() {
+            open_url(&handle, &url);
+        }
+    });
+
+    if let Ok(Some(urls)) = app.deep_link().get_current() {
+        for url in urls {
+            open_url(app, &url);
+        }
+    }
+}
+
+fn open_url(app: &AppHandle, url: &Url) {
+    let link = match DeepLink::parse(url) {
+        Ok(link) => link,
+        Err(e) => {
+            eprintln!("Ignoring link: {}", e);
+            return;
+        }
+    };
+    if let Ok(mut pending) = app.state::<PendingDeepLink>().0.lock() {
+        *pending = Some(link);
+    }
+    show_main_window(app);
+    let _ = app.emit_to("main", "deep-link", ());
+}
+
+/// Called in the running instance when the app is launched again
+/// Links among the arguments are delivered through the deep link plugin.
+pub fn second_launch(app: &AppHandle, args: Vec<String>) {
+    show_main_window(app);
+
+    let Some(vault) = vaults::vault_arg(args.into_iter().skip(1)) else {
+        return;
+    };
+    let registry = match VaultRegistry::load() {
+        Ok(registry) => registry,
+        Err(e) => {
+            eprintln!("Failed to read vaults: {}", e);
+            return;
+        }
+    };
+    let current = app.state::<Database>().path();
+    let Some(target) = registry.find(&vault).cloned() else {
+        if vaults::normalize_vault_path(Path::new(&vault)) != current {
+            eprintln!("Ignoring --vault {}: only registered vaults can be opened in the running app", vault);
+        }
+        return;
+    };
+    if target.path != current {
+        if let Err(e) = switch_to(app, &target.name) {
+            eprintln!("Failed to switch vault: {}", e);
+        }
+    }
+}
+
+fn show_main_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
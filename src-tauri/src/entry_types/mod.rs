@@ -0,0 +1,178 @@
+pub mod validation;
+
+use crate::models::{Entry, EntryField, EntryType, EntryTypeSchema, FieldKind, FieldSpec};
+
+/// Fields, label and icon of an entry type
+pub fn schema(entry_type: EntryType) -> EntryTypeSchema {
+    use FieldKind::*;
+
+    let (label, icon, summary_field, fields): (_, _, _, &[(&str, &str, FieldKind, bool)]) = match entry_type {
+        EntryType::Login => (
+            "登录",
+            "🔐",
+            Some("username"),
+            &[
+                ("url", "网址", Url, false),
+                ("username", "用户名", Text, false),
+                ("password", "密码", Concealed, false),
+                ("totp", "一次性密码", Totp, false),
+            ],
+        ),
+        EntryType::SecureNote => ("安全笔记", "📝", None, &[]),
+        EntryType::CreditCard => (
+            "信用卡",
+            "💳",
+            Some("number"),
+            &[
+                ("cardholder", "持卡人", Text, false),
+                ("number", "卡号", CardNumber, true),
+                ("expiry", "有效期", MonthYear, false),
+                ("cvv", "安全码", CardCode, false),
+                ("pin", "PIN", Concealed, false),
+                ("issuer", "发卡行", Text, false),
+            ],
+        ),
+        EntryType::BankAccount => (
+            "银行账户",
+            "🏦",
+            Some("bankName"),
+            &[
+                ("bankName", "银行名称", Text, false),
+                ("accountHolder", "开户人", Text, false),
+                ("accountNumber", "账号", Text, false),
+                ("iban", "IBAN", Iban, false),
+                ("bic", "BIC/SWIFT", Bic, false),
+                ("pin", "密码/PIN", Concealed, false),
+            ],
+        ),
+        EntryType::Identity => (
+            "身份信息",
+            "🪪",
+            Some("fullName"),
+            &[
+                ("fullName", "姓名", Text, true),
+                ("email", "邮箱", Email, false),
+                ("phone", "电话", Phone, false),
+                ("address", "地址", Multiline, false),
+                ("birthday", "出生日期", Date, false),
+                ("idNumber", "证件号码", Concealed, false),
+            ],
+        ),
+        EntryType::WiFi => (
+            "Wi-Fi",
+            "📶",
+            Some("ssid"),
+            &[
+                ("ssid", "网络名称 (SSID)", Text, true),
+                ("password", "密码", Concealed, false),
+                ("security", "加密方式", Text, false),
+            ],
+        ),
+        EntryType::Server => (
+            "服务器",
+            "🖥️",
+            Some("url"),
+            &[
+                ("url", "主机", Text, true),
+                ("port", "端口", Number, false),
+                ("username", "用户名", Text, false),
+                ("password", "密码", Concealed, false),
+            ],
+        ),
+        EntryType::SshKey => (
+            "SSH 密钥",
+            "🗝️",
+            Some("username"),
+            &[
+                ("username", "注释", Text, false),
+                ("password", "私钥", SshPrivateKey, true),
+            ],
+        ),
+        EntryType::SoftwareLicense => (
+            "软件许可",
+            "📦",
+            Some("licensedTo"),
+            &[
+                ("password", "许可证密钥", Concealed, true),
+                ("version", "版本", Text, false),
+                ("licensedTo", "授权给", Text, false),
+                ("email", "注册邮箱", Email, false),
+                ("purchaseDate", "购买日期", Date, false),
+            ],
+        ),
+        EntryType::ApiKey => (
+            "API 密钥",
+            "🔑",
+            Some("username"),
+            &[
+                ("url", "服务地址", Url, false),
+                ("username", "Key ID", Text, false),
+                ("password", "密钥", Concealed, true),
+                ("expires", "到期日期", Date, false),
+            ],
+        ),
+    };
+
+    EntryTypeSchema {
+        entry_type,
+        label,
+        icon,
+        fields: fields
+            .iter()
+            .map(|&(name, label, kind, required)| FieldSpec { name, label, kind, required })
+            .collect(),
+        summary_field,
+    }
+}
+
+/// Value of a schema field, looking in the common columns first
+pub fn field_value<'a>(entry: &'a Entry, name: &str) -> &'a str {
+    match name {
+        "url" => &entry.url,
+        "username" => &entry.username,
+        "password" => &entry.password,
+        "notes" => &entry.notes,
+        _ => entry
+            .fields
+            .iter()
+            .find(|f| f.name == name)
+            .map(|f| f.value.as_str())
+            .unwrap_or(""),
+    }
+}
+
+/// Kind and label of a stored field: from the schema for typed fields, its own for custom fields
+pub fn field_info(entry_type: EntryType, field: &EntryField) -> (FieldKind, String) {
+    schema(entry_type)
+        .fields
+        .iter()
+        .find(|spec| spec.name == field.name)
+        .map(|spec| (spec.kind, spec.label.to_string()))
+        .unwrap_or_else(|| (field.kind, field.name.clone()))
+}
+
+/// Check required fields and the format of every filled in field
+pub fn validate(entry: &Entry) -> Result<(), String> {
+    let schema = schema(entry.entry_type);
+
+    for spec in &schema.fields {
+        let value = field_value(entry, spec.name);
+        if value.trim().is_empty() {
+            if spec.required {
+                return Err(format!("{}不能为空", spec.label));
+            }
+            continue;
+        }
+        validation::check(spec.kind, value).map_err(|e| format!("{}: {}", spec.label, e))?;
+    }
+
+    // Custom fields are checked by their own kind
+    for field in &entry.fields {
+        if schema.fields.iter().any(|spec| spec.name == field.name) || field.value.trim().is_empty() {
+            continue;
+        }
+        validation::check(field.kind, &field.value).map_err(|e| format!("{}: {}", field.name, e))?;
+    }
+
+    Ok(())
+}
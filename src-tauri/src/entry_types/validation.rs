@@ -0,0 +1,187 @@
+use chrono::NaiveDate;
+
+use crate::models::FieldKind;
+use crate::ssh;
+use crate::totp::Totp;
+
+/// Check a non-empty value against its field kind
+pub fn check(kind: FieldKind, value: &str) -> Result<(), String> {
+    let value = value.trim();
+    match kind {
+        FieldKind::Text | FieldKind::Multiline | FieldKind::Concealed | FieldKind::Url => Ok(()),
+        FieldKind::Email => {
+            let valid = value
+                .split_once('@')
+                .is_some_and(|(user, domain)| !user.is_empty() && domain.contains('.') && !value.contains(' '));
+            ok_if(valid, "邮箱格式不正确")
+        }
+        FieldKind::Phone => ok_if(
+            value.chars().all(|c| c.is_ascii_digit() || " +-()".contains(c)) && value.chars().any(|c| c.is_ascii_digit()),
+            "电话号码格式不正确",
+        ),
+        FieldKind::Number => ok_if(value.parse::<u64>().is_ok(), "必须是数字"),
+        FieldKind::Date => ok_if(NaiveDate::parse_from_str(value, "%Y-%m-%d").is_ok(), "日期格式应为 YYYY-MM-DD"),
+        FieldKind::MonthYear => ok_if(month_year_is_valid(value), "格式应为 MM/YY 或 MM/YYYY"),
+        FieldKind::CardNumber => {
+            let digits = strip_separators(value);
+            ok_if(
+                (12..=19).contains(&digits.len()) && luhn_is_valid(&digits),
+                "卡号无效",
+            )
+        }
+        FieldKind::CardCode => ok_if(
+            (3..=4).contains(&value.len()) && value.chars().all(|c| c.is_ascii_digit()),
+            "安全码应为3或4位数字",
+        ),
+        FieldKind::Iban => ok_if(iban_is_valid(value), "IBAN 校验失败"),
+        FieldKind::Bic => ok_if(bic_is_valid(value), "BIC 格式不正确"),
+        FieldKind::SshPrivateKey => ssh::parse_private_key(value).map(|_| ()),
+        FieldKind::Totp => Totp::parse(value).map(|_| ()),
+    }
+}
+
+/// Luhn checksum over a string of digits
+fn luhn_is_valid(digits: &str) -> bool {
+    if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return false;
+    }
+
+    let sum: u32 = digits
+        .bytes()
+        .rev()
+        .enumerate()
+        .map(|(i, b)| {
+            let d = u32::from(b - b'0');
+            if i % 2 == 1 {
+                let doubled = d * 2;
+                if doubled > 9 { doubled - 9 } else { doubled }
+            } else {
+                d
+            }
+        })
+        .sum();
+    sum.is_multiple_of(10)
+}
+
+/// ISO 13616 check: move the first four characters to the end, letters become 10..35, mod 97 == 1
+fn iban_is_valid(value: &str) -> bool {
+    let iban = strip_separators(value).to_ascii_uppercase();
+    if !(15..=34).contains(&iban.len()) || !iban.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return false;
+    }
+    let (country, check) = (&iban[..2], &iban[2..4]);
+    if !country.chars().all(|c| c.is_ascii_alphabetic()) || !check.chars().all(|c| c.is_ascii_digit()) {
+        return false;
+    }
+
+    let rearranged = format!("{}{}", &iban[4..], &iban[..4]);
+    let remainder = rearranged.chars().fold(0u32, |acc, c| {
+        let n = c.to_digit(36).unwrap_or(0);
+        if n >= 10 {
+            (acc * 100 + n) % 97
+        } else {
+            (acc * 10 + n) % 97
+        }
+    });
+    remainder == 1
+}
+
+/// 4 letter bank code, 2 letter country, 2 character location, optional 3 character branch
+fn bic_is_valid(value: &str) -> bool {
+    let bic = value.to_ascii_uppercase();
+    bic.is_ascii()
+        && (bic.len() == 8 || bic.len() == 11)
+        && bic[..6].chars().all(|c| c.is_ascii_alphabetic())
+        && bic[6..].chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+fn month_year_is_valid(value: &str) -> bool {
+    let Some((month, year)) = value.split_once('/') else { return false };
+    let month_ok = month.len() == 2 && month.parse::<u32>().is_ok_and(|m| (1..=12).contains(&m));
+    let year_ok = (year.len() == 2 || year.len() == 4) && year.chars().all(|c| c.is_ascii_digit());
+    month_ok && year_ok
+}
+
+/// Helper: drop spaces and dashes people type into card numbers and IBANs
+fn strip_separators(value: &str) -> String {
+    value.chars().filter(|c| !matches!(c, ' ' | '-')).collect()
+}
+
+fn ok_if(valid: bool, message: &str) -> Result<(), String> {
+    if valid {
+        Ok(())
+    } else {
+        Err(message.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn luhn() {
+        assert!(luhn_is_valid("4111111111111111"));
+        assert!(luhn_is_valid("79927398713"));
+        assert!(!luhn_is_valid("4111111111111112"));
+        assert!(!luhn_is_valid(""));
+        assert!(!luhn_is_valid("4111a11111111111"));
+    }
+
+    #[test]
+    fn card_numbers_allow_separators() {
+        assert!(check(FieldKind::CardNumber, "4111 1111-1111 1111").is_ok());
+        assert!(check(FieldKind::CardNumber, "0").is_err());
+        assert!(check(FieldKind::CardNumber, "4111 1111 1111 1112").is_err());
+    }
+
+    #[test]
+    fn iban() {
+        assert!(iban_is_valid("GB82 WEST 1234 5698 7654 32"));
+        assert!(iban_is_valid("de89370400440532013000"));
+        assert!(!iban_is_valid("GB82 WEST 1234 5698 7654 33"));
+        assert!(!iban_is_valid("GB82"));
+        assert!(!iban_is_valid("1282WEST12345698765432"));
+        assert!(!iban_is_valid("GB82 WEST 1234 5698 7654 3!"));
+    }
+
+    #[test]
+    fn bic() {
+        assert!(bic_is_valid("DEUTDEFF"));
+        assert!(bic_is_valid("deutdeff500"));
+        assert!(!bic_is_valid("DEUTDEF"));
+        assert!(!bic_is_valid("DEU1DEFF"));
+        assert!(!bic_is_valid("DEUTDEFF50"));
+        assert!(!bic_is_valid("DEUTDEFé"));
+    }
+
+    #[test]
+    fn month_year() {
+        assert!(check(FieldKind::MonthYear, "09/27").is_ok());
+        assert!(check(FieldKind::MonthYear, "12/2030").is_ok());
+        assert!(check(FieldKind::MonthYear, "13/27").is_err());
+        assert!(check(FieldKind::MonthYear, "9/27").is_err());
+        assert!(check(FieldKind::MonthYear, "09/270").is_err());
+    }
+
+    #[test]
+    fn totp() {
+        assert!(check(FieldKind::Totp, "JBSW Y3DP EHPK 3PXP").is_ok());
+        assert!(check(FieldKind::Totp, "otpauth://totp/Example?secret=JBSWY3DPEHPK3PXP&digits=8").is_ok());
+        assert!(check(FieldKind::Totp, "not base32!").is_err());
+        assert!(check(FieldKind::Totp, "otpauth://hotp/Example?secret=JBSWY3DPEHPK3PXP").is_err());
+        assert!(check(FieldKind::Totp, "otpauth://totp/Example?issuer=x").is_err());
+    }
+
+    #[test]
+    fn simple_kinds() {
+        assert!(check(FieldKind::Email, "a@example.com").is_ok());
+        assert!(check(FieldKind::Email, "a@example").is_err());
+        assert!(check(FieldKind::Phone, "+49 (30) 123-456").is_ok());
+        assert!(check(FieldKind::Phone, "+-").is_err());
+        assert!(check(FieldKind::CardCode, "123").is_ok());
+        assert!(check(FieldKind::CardCode, "12a").is_err());
+        assert!(check(FieldKind::Date, "2026-02-28").is_ok());
+        assert!(check(FieldKind::Date, "2026-02-30").is_err());
+    }
+}
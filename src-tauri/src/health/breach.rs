@@ -0,0 +1,52 @@
+use reqwest::blocking::Client;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::crypto;
+
+const RANGE_URL: &str = "https://api.pwnedpasswords.com/range/";
+const TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Uppercase hex SHA-1, the form the Pwned Passwords API uses
+pub fn sha1_hex(password: &str) -> String {
+    let digest = ring::digest::digest(&ring::digest::SHA1_FOR_LEGACY_USE_ONLY, password.as_bytes());
+    crypto::to_hex(digest.as_ref()).to_uppercase()
+}
+
+/// How often each digest appears in known breaches; digests not listed weren't found
+/// Only the first five hex digits of a digest are sent, and padded responses hide which
+/// prefix had matches.
+pub fn lookup(digests: &[String]) -> Result<HashMap<String, u64>, String> {
+    let client = Client::builder()
+        .timeout(TIMEOUT)
+        .user_agent(concat!("one-password/", env!("CARGO_PKG_VERSION")))
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    let mut by_prefix: HashMap<&str, Vec<&str>> = HashMap::new();
+    for digest in digests {
+        let (prefix, suffix) = digest.split_at(5);
+        by_prefix.entry(prefix).or_default().push(suffix);
+    }
+
+    let mut found = HashMap::new();
+    for (prefix, suffixes) in by_prefix {
+        let body = client
+            .get(format!("{}{}", RANGE_URL, prefix))
+            .header("Add-Padding", "true")
+            .send()
+            .and_then(|response| response.error_for_status())
+            .and_then(|response| response.text())
+            .map_err(|e| format!("泄露检查请求失败: {}", e))?;
+
+        for line in body.lines() {
+            let Some((suffix, count)) = line.trim().split_once(':') else { continue };
+            // Padding lines have a count of zero
+            let count: u64 = count.parse().unwrap_or(0);
+            if count > 0 && suffixes.contains(&suffix) {
+                found.insert(format!("{}{}", prefix, suffix), count);
+            }
+        }
+    }
+    Ok(found)
+}
@@ -0,0 +1,106 @@
+mod breach;
+
+use chrono::{Local, Months, NaiveDate};
+use rusqlite::Connection;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use crate::commands::entries::query_entries;
+use crate::entry_types;
+use crate::models::{Entry, EntryFilter, EntryType, HealthIssue, HealthReport};
+
+/// Outcome of the last breach check
+/// Kept in memory only, so no password digests end up on disk; cleared when the vault changes.
+#[derive(Default)]
+pub struct HealthState(Mutex<BreachResults>);
+
+#[derive(Default)]
+struct BreachResults {
+    /// Entry id to the digest of the password that was found and its breach count
+    found: HashMap<String, (String, u64)>,
+    checked_at: Option<i64>,
+}
+
+impl HealthState {
+    pub fn clear(&self) {
+        if let Ok(mut results) = self.0.lock() {
+            *results = BreachResults::default();
+        }
+    }
+}
+
+/// Entry types whose password is a password someone chose, rather than a key or license
+fn has_user_password(entry: &Entry) -> bool {
+    matches!(entry.entry_type, EntryType::Login | EntryType::WiFi | EntryType::Server) && !entry.password.is_empty()
+}
+
+/// Expired entries, and passwords found by the last breach check that haven't been changed since
+pub fn report(conn: &Connection, state: &HealthState) -> Result<HealthReport, String> {
+    let entries = query_entries(conn, &EntryFilter::default())?;
+    let results = state.0.lock().map_err(|e| e.to_string())?;
+    let today = Local::now().date_naive();
+
+    let mut report = HealthReport {
+        breach_checked_at: results.checked_at,
+        ..Default::default()
+    };
+    for entry in &entries {
+        if let Some((digest, count)) = results.found.get(&entry.id) {
+            if has_user_password(entry) && *digest == breach::sha1_hex(&entry.password) {
+                report.breached.push(issue(entry, format!("密码出现在 {} 次已知数据泄露中", count)));
+            }
+        }
+        if let Some(date) = expiry_date(entry).filter(|date| *date < today) {
+            report.expired.push(issue(entry, format!("已于 {} 到期", date)));
+        }
+    }
+    Ok(report)
+}
+
+/// Look up the passwords in the Pwned Passwords breach data and remember which were found
+/// Runs without the database lock; the caller reads the entries first.
+pub fn check_breaches(entries: &[Entry], state: &HealthState) -> Result<(), String> {
+    let digests: Vec<(&str, String)> = entries
+        .iter()
+        .filter(|entry| has_user_password(entry))
+        .map(|entry| (entry.id.as_str(), breach::sha1_hex(&entry.password)))
+        .collect();
+    let mut unique: Vec<String> = digests.iter().map(|(_, digest)| digest.clone()).collect();
+    unique.sort();
+    unique.dedup();
+    let counts = breach::lookup(&unique)?;
+
+    let mut results = state.0.lock().map_err(|e| e.to_string())?;
+    results.found = digests
+        .into_iter()
+        .filter_map(|(id, digest)| counts.get(&digest).map(|count| (id.to_string(), (digest, *count))))
+        .collect();
+    results.checked_at = Some(chrono::Utc::now().timestamp());
+    Ok(())
+}
+
+/// Last day an entry is valid: API key `expires` dates, or the end of a card's `expiry` month
+fn expiry_date(entry: &Entry) -> Option<NaiveDate> {
+    let expires = entry_types::field_value(entry, "expires");
+    if !expires.is_empty() {
+        return NaiveDate::parse_from_str(expires, "%Y-%m-%d").ok();
+    }
+
+    let (month, year) = entry_types::field_value(entry, "expiry").split_once('/')?;
+    let month: u32 = month.parse().ok()?;
+    let year: i32 = match year.parse().ok()? {
+        year if year < 100 => 2000 + year,
+        year => year,
+    };
+    NaiveDate::from_ymd_opt(year, month, 1)?
+        .checked_add_months(Months::new(1))?
+        .pred_opt()
+}
+
+fn issue(entry: &Entry, detail: String) -> HealthIssue {
+    HealthIssue {
+        entry_id: entry.id.clone(),
+        title: entry.title.clone(),
+        detail,
+    }
+}
@@ -1,74 +1,111 @@
+mod agent;
+mod autotype;
+mod backup;
+mod browser;
+mod clipboard;
+pub mod cli;
 mod commands;
+mod crypto;
 mod db;
+mod deep_link;
+mod entry_types;
+mod health;
 mod models;
+mod policy;
+pub mod native_host;
+mod search;
+#[cfg(target_os = "linux")]
+mod secret_service;
+mod settings;
+mod shortcuts;
+mod ssh;
+mod startup;
+mod sync;
+mod totp;
+#[cfg(desktop)]
+mod tray;
+mod urlmatch;
 
+use crypto::Session;
 use db::Database;
-use tauri::{
-    image::Image,
-    menu::{Menu, MenuItem, PredefinedMenuItem},
-    tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent},
-    Emitter, Manager, WindowEvent,
-};
+use tauri::{AppHandle, Manager, WindowEvent};
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    // Initialize database
-    let database = Database::new().expect("Failed to initialize database");
+    // Vault to open, honoring `--vault <name|path>`; it is only opened in setup
+    let db_path = db::vaults::startup_path(std::env::args().skip(1))
+        .expect("Failed to resolve vault location");
+
+    let builder = tauri::Builder::default();
+    // Plugins start before setup, so a second launch hands over its arguments and exits
+    // before it opens or migrates the vault the first instance is using
+    #[cfg(desktop)]
+    let builder = builder
+        .plugin(tauri_plugin_single_instance::init(|app, args, _cwd| {
+            deep_link::second_launch(app, args);
+        }));
 
-    tauri::Builder::default()
+    builder
+        .plugin(tauri_plugin_deep_link::init())
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_clipboard_manager::init())
         .plugin(tauri_plugin_dialog::init())
-        .setup(|app| {
+        .setup(move |app| {
+            app.manage(Database::new(&db_path)?);
+
             // Create system tray
             #[cfg(desktop)]
             {
-                create_tray(app)?;
+                tray::create(app)?;
             }
+            deep_link::setup(app.handle());
+            startup::apply(app.handle());
 
-            // Register global shortcut plugin with handler
+            // Register global shortcut plugin, then the shortcuts configured in settings
+            app.manage(shortcuts::ShortcutRegistry::default());
             #[cfg(desktop)]
             {
-                use tauri_plugin_global_shortcut::{Code, Modifiers, ShortcutState};
-
-                let handle = app.handle().clone();
                 let shortcut_plugin = tauri_plugin_global_shortcut::Builder::new()
-                    .with_shortcut("ctrl+shift+p")
-                    .map(|builder| {
-                        builder.with_handler(move |_app, shortcut, event| {
-                            if event.state == ShortcutState::Pressed {
-                                if shortcut.matches(Modifiers::CONTROL | Modifiers::SHIFT, Code::KeyP) {
-                                    println!("Quick Access shortcut triggered!");
-                                    let handle = handle.clone();
-                                    tauri::async_runtime::spawn(async move {
-                                        if let Err(e) = commands::window::show_quick_access(handle).await {
-                                            eprintln!("Failed to show quick access: {}", e);
-                                        }
-                                    });
-                                }
-                            }
-                        })
-                        .build()
-                    });
-
-                match shortcut_plugin {
-                    Ok(plugin) => {
-                        if let Err(e) = app.handle().plugin(plugin) {
-                            eprintln!("Warning: Failed to register global shortcut plugin: {}. The shortcut may already be in use.", e);
-                        }
-                    }
-                    Err(e) => {
-                        eprintln!("Warning: Failed to create global shortcut: {}. The shortcut may already be in use.", e);
-                    }
+                    .with_handler(shortcuts::handle)
+                    .build();
+                match app.handle().plugin(shortcut_plugin) {
+                    Ok(()) => shortcuts::register_all(app.handle()),
+                    Err(e) => eprintln!("Warning: Failed to register global shortcut plugin: {}", e),
                 }
             }
 
+            // Start scheduled backups
+            backup::scheduler::start(app.handle().clone());
+            sync::scheduler::start(app.handle().clone());
+            #[cfg(unix)]
+            {
+                agent::start_for_app(app.handle());
+                app.manage(ssh::agent::SshAgentHandle::default());
+                ssh::agent::start_for_app(app.handle());
+            }
+            #[cfg(target_os = "linux")]
+            {
+                app.manage(secret_service::SecretServiceHandle::default());
+                secret_service::start_for_app(app.handle());
+            }
+
             Ok(())
         })
         .on_window_event(|window, event| {
-            // Handle window close event - hide to tray instead of closing
+            // Handle window close event - hide to tray instead of closing, unless set to quit
             if let WindowEvent::CloseRequested { api, .. } = event {
                 if window.label() == "main" {
+                    let quit = window
+                        .state::<Database>()
+                        .conn
+                        .lock()
+                        .ok()
+                        .map(|conn| settings::get_bool(&conn, "quit_on_close"))
+                        .unwrap_or(false);
+                    if quit {
+                        window.app_handle().exit(0);
+                        return;
+                    }
                     // Prevent the window from closing
                     api.prevent_close();
                     // Hide the window instead
@@ -76,38 +113,113 @@ pub fn run() {
                 }
             }
         })
-        .manage(database)
+        .manage(Session::default())
+        .manage(autotype::AutoTypeTarget::default())
+        .manage(search::SearchIndex::default())
+        .manage(health::HealthState::default())
+        .manage(deep_link::PendingDeepLink::default())
         .invoke_handler(tauri::generate_handler![
             // Auth commands
             commands::auth::check_initialized,
             commands::auth::setup_password,
             commands::auth::verify_password,
             commands::auth::change_password,
+            commands::auth::get_lock_state,
+            commands::auth::lock_session,
             // Entry commands
             commands::entries::get_entries,
+            commands::entries::get_entry,
+            commands::entries::quick_search,
             commands::entries::create_entry,
             commands::entries::update_entry,
             commands::entries::delete_entry,
             commands::entries::toggle_favorite,
+            commands::entries::find_entries_for_url,
+            commands::entries::get_entry_types,
+            commands::entries::mark_entry_used,
+            commands::entries::get_recent_entries,
+            commands::entries::get_entry_usage,
+            commands::entries::clear_usage_history,
+            // Template commands
+            commands::templates::get_templates,
+            commands::templates::create_template,
+            commands::templates::update_template,
+            commands::templates::delete_template,
+            commands::templates::export_templates,
+            commands::templates::import_templates,
+            commands::templates::save_templates_file,
+            commands::templates::load_templates_file,
             // Group commands
             commands::groups::get_groups,
             commands::groups::create_group,
             commands::groups::update_group,
             commands::groups::delete_group,
             commands::groups::get_group_entry_counts,
+            // Health commands
+            commands::health::get_health_report,
+            commands::health::check_breached_passwords,
             // Settings commands
             commands::settings::get_settings,
+            commands::settings::get_setting_specs,
             commands::settings::update_settings,
+            commands::settings::reset_settings,
+            commands::settings::export_settings,
+            commands::settings::import_settings,
+            commands::settings::save_settings_file,
+            commands::settings::load_settings_file,
+            commands::settings::get_policy,
+            commands::settings::get_backup_settings,
+            commands::settings::update_backup_settings,
+            commands::settings::get_secret_service_enabled,
+            commands::settings::set_secret_service_enabled,
+            // Auto-type commands
+            commands::autotype::autotype_entry,
+            commands::autotype::get_autotype_sequence,
+            commands::autotype::get_default_autotype_sequence,
+            commands::autotype::set_default_autotype_sequence,
+            // Shortcut commands
+            commands::shortcuts::get_shortcuts,
+            commands::shortcuts::get_shortcut_failures,
+            commands::shortcuts::set_shortcut,
+            commands::shortcuts::reset_shortcuts,
+            // SSH commands
+            commands::ssh::generate_ssh_key,
+            commands::ssh::import_ssh_key,
+            commands::ssh::get_ssh_key_info,
+            commands::ssh::get_ssh_agent_status,
+            commands::ssh::set_ssh_agent_enabled,
+            // Backup commands
+            commands::backup::backup_now,
+            commands::backup::list_backups,
+            commands::backup::restore_backup,
+            // Sync commands
+            commands::sync::get_sync_settings,
+            commands::sync::enable_sync,
+            commands::sync::disable_sync,
+            commands::sync::sync_now,
+            commands::sync::get_sync_conflicts,
+            commands::sync::resolve_sync_conflict,
+            // Browser extension commands
+            commands::browser::list_browser_pairings,
+            commands::browser::remove_browser_pairing,
+            // Vault commands
+            commands::vaults::list_vaults,
+            commands::vaults::add_vault,
+            commands::vaults::remove_vault,
+            commands::vaults::switch_vault,
             // Window commands
             commands::window::toggle_quick_access,
             commands::window::show_quick_access,
             commands::window::hide_quick_access,
             commands::window::close_quick_access,
+            commands::window::take_deep_link,
             // Export/Import commands
             commands::export::export_data,
             commands::export::save_export_file,
             commands::export::load_import_file,
             commands::export::import_data,
+            commands::export::get_import_snapshot,
+            commands::export::undo_last_import,
             commands::export::export_excel,
             commands::export::save_export_excel_file,
         ])
@@ -115,63 +227,10 @@ pub fn run() {
         .expect("error while running tauri application");
 }
 
-#[cfg(desktop)]
-fn create_tray(app: &tauri::App) -> Result<(), Box<dyn std::error::Error>> {
-    // Create tray menu items
-    let show_item = MenuItem::with_id(app, "show", "显示主窗口", true, None::<&str>)?;
-    let lock_item = MenuItem::with_id(app, "lock", "锁定", true, None::<&str>)?;
-    let separator = PredefinedMenuItem::separator(app)?;
-    let quit_item = MenuItem::with_id(app, "quit", "退出", true, None::<&str>)?;
-
-    // Create the menu
-    let menu = Menu::with_items(app, &[&show_item, &lock_item, &separator, &quit_item])?;
-
-    // Load tray icon
-    let icon = Image::from_path("icons/32x32.png")
-        .or_else(|_| Image::from_path("src-tauri/icons/32x32.png"))
-        .unwrap_or_else(|_| Image::from_bytes(include_bytes!("../icons/32x32.png")).expect("Failed to load tray icon"));
-
-    // Create the tray icon
-    let _tray = TrayIconBuilder::new()
-        .icon(icon)
-        .menu(&menu)
-        .tooltip("One-Password")
-        .on_menu_event(|app, event| {
-            match event.id.as_ref() {
-                "show" => {
-                    if let Some(window) = app.get_webview_window("main") {
-                        let _ = window.show();
-                        let _ = window.set_focus();
-                    }
-                }
-                "lock" => {
-                    // Emit lock event to frontend
-                    if let Some(window) = app.get_webview_window("main") {
-                        let _ = window.emit("lock-app", ());
-                    }
-                }
-                "quit" => {
-                    app.exit(0);
-                }
-                _ => {}
-            }
-        })
-        .on_tray_icon_event(|tray, event| {
-            // Handle tray icon click - show window on left click
-            if let TrayIconEvent::Click {
-                button: MouseButton::Left,
-                button_state: MouseButtonState::Up,
-                ..
-            } = event
-            {
-                let app = tray.app_handle();
-                if let Some(window) = app.get_webview_window("main") {
-                    let _ = window.show();
-                    let _ = window.set_focus();
-                }
-            }
-        })
-        .build(app)?;
-
-    Ok(())
+/// Update the tray icon, tooltip and menu after the state they show has changed
+pub(crate) fn refresh_tray(app: &AppHandle) {
+    #[cfg(desktop)]
+    tray::refresh(app);
+    #[cfg(not(desktop))]
+    let _ = app;
 }
@@ -0,0 +1,11 @@
+use serde::{Deserialize, Serialize};
+
+/// A browser extension allowed to request credentials
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BrowserPairing {
+    pub extension_id: String,
+    pub browser: String,
+    pub created_at: i64,
+    pub last_used_at: Option<i64>,
+}
@@ -4,38 +4,334 @@ use serde::{Deserialize, Serialize};
 #[serde(rename_all = "camelCase")]
 pub struct Entry {
     pub id: String,
+    #[serde(default)]
+    pub entry_type: EntryType,
     pub group_id: Option<String>,
     pub title: String,
     pub url: String,
     pub username: String,
     pub password: String,
     pub notes: String,
+    /// How `url` and `extra_urls` without their own mode are matched against pages
+    #[serde(default)]
+    pub url_match: UrlMatch,
+    /// Further sites or Android apps (`androidapp://<package>`) the login belongs to
+    #[serde(default)]
+    pub extra_urls: Vec<EntryUrl>,
+    /// Type specific and custom fields beyond the common columns
+    #[serde(default)]
+    pub fields: Vec<EntryField>,
+    /// Keys typed by auto-type, e.g. `{USERNAME}{TAB}{PASSWORD}{ENTER}`; the group's sequence when not set
+    #[serde(default)]
+    pub autotype_sequence: Option<String>,
     pub is_favorite: bool,
     pub sort_order: i32,
     pub created_at: i64,
     pub updated_at: i64,
 }
 
+/// What an entry holds; see `entry_types::schema` for the fields of each type
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum EntryType {
+    #[default]
+    Login,
+    SecureNote,
+    CreditCard,
+    BankAccount,
+    Identity,
+    #[serde(rename = "wifi")]
+    WiFi,
+    Server,
+    /// Unencrypted OpenSSH private key, served by the built-in ssh-agent
+    SshKey,
+    SoftwareLicense,
+    ApiKey,
+}
+
+impl EntryType {
+    pub const ALL: [EntryType; 10] = [
+        EntryType::Login,
+        EntryType::SecureNote,
+        EntryType::CreditCard,
+        EntryType::BankAccount,
+        EntryType::Identity,
+        EntryType::WiFi,
+        EntryType::Server,
+        EntryType::SshKey,
+        EntryType::SoftwareLicense,
+        EntryType::ApiKey,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            EntryType::Login => "login",
+            EntryType::SecureNote => "secureNote",
+            EntryType::CreditCard => "creditCard",
+            EntryType::BankAccount => "bankAccount",
+            EntryType::Identity => "identity",
+            EntryType::WiFi => "wifi",
+            EntryType::Server => "server",
+            EntryType::SshKey => "sshKey",
+            EntryType::SoftwareLicense => "softwareLicense",
+            EntryType::ApiKey => "apiKey",
+        }
+    }
+
+    pub fn parse(value: &str) -> Self {
+        Self::ALL
+            .into_iter()
+            .find(|t| t.as_str() == value)
+            .unwrap_or_default()
+    }
+}
+
+/// How a field value is entered, shown and validated
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FieldKind {
+    #[default]
+    Text,
+    Multiline,
+    /// Hidden until revealed, like a password
+    Concealed,
+    Url,
+    Email,
+    Phone,
+    Number,
+    /// `YYYY-MM-DD`
+    Date,
+    /// `MM/YY` or `MM/YYYY`, e.g. a card expiry
+    MonthYear,
+    /// Checked with the Luhn algorithm
+    CardNumber,
+    /// 3 or 4 digit card verification code
+    CardCode,
+    /// Checked with the ISO 13616 mod-97 checksum
+    Iban,
+    Bic,
+    SshPrivateKey,
+    /// `otpauth://totp/...` URI or Base32 secret
+    Totp,
+}
+
+impl FieldKind {
+    /// Holds a secret that exports treat like the password
+    pub fn is_secret(self) -> bool {
+        matches!(
+            self,
+            FieldKind::Concealed
+                | FieldKind::CardNumber
+                | FieldKind::CardCode
+                | FieldKind::Iban
+                | FieldKind::SshPrivateKey
+                | FieldKind::Totp
+        )
+    }
+}
+
+/// A typed or custom field stored with an entry
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EntryField {
+    /// Schema field name, or the label of a custom field
+    pub name: String,
+    pub value: String,
+    /// Used for custom fields; schema fields take the kind from the schema
+    #[serde(default)]
+    pub kind: FieldKind,
+}
+
+/// One field of an entry type; `url`, `username` and `password` map to the entry's own columns
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldSpec {
+    pub name: &'static str,
+    pub label: &'static str,
+    pub kind: FieldKind,
+    pub required: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EntryTypeSchema {
+    pub entry_type: EntryType,
+    pub label: &'static str,
+    pub icon: &'static str,
+    pub fields: Vec<FieldSpec>,
+    /// Field shown under the title in lists and Quick Access
+    pub summary_field: Option<&'static str>,
+}
+
+/// How a saved URL is compared with the URL of a page or app
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum UrlMatch {
+    /// Same URL, ignoring the fragment and a trailing slash
+    Exact,
+    /// Same host and port
+    Host,
+    /// Same registrable domain, e.g. `login.example.co.uk` for `example.co.uk`
+    #[default]
+    BaseDomain,
+    /// The page URL starts with the saved URL
+    StartsWith,
+    /// The saved URL is a regular expression matched against the page URL
+    Regex,
+    /// Never offered automatically
+    Never,
+}
+
+impl UrlMatch {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            UrlMatch::Exact => "exact",
+            UrlMatch::Host => "host",
+            UrlMatch::BaseDomain => "baseDomain",
+            UrlMatch::StartsWith => "startsWith",
+            UrlMatch::Regex => "regex",
+            UrlMatch::Never => "never",
+        }
+    }
+
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "exact" => UrlMatch::Exact,
+            "host" => UrlMatch::Host,
+            "startsWith" => UrlMatch::StartsWith,
+            "regex" => UrlMatch::Regex,
+            "never" => UrlMatch::Never,
+            _ => UrlMatch::BaseDomain,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EntryUrl {
+    pub url: String,
+    /// Overrides the entry's `url_match` for this URL
+    #[serde(default, rename = "match")]
+    pub match_mode: Option<UrlMatch>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct CreateEntryDto {
+    #[serde(default)]
+    pub entry_type: Option<EntryType>,
     pub group_id: Option<String>,
     pub title: String,
     pub url: Option<String>,
     pub username: Option<String>,
     pub password: Option<String>,
     pub notes: Option<String>,
+    #[serde(default)]
+    pub url_match: Option<UrlMatch>,
+    #[serde(default)]
+    pub extra_urls: Option<Vec<EntryUrl>>,
+    #[serde(default)]
+    pub fields: Option<Vec<EntryField>>,
+    #[serde(default)]
+    pub autotype_sequence: Option<String>,
+    /// Start from a user-defined template; set values take precedence over its defaults
+    #[serde(default)]
+    pub template_id: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct UpdateEntryDto {
+    #[serde(default)]
+    pub entry_type: Option<EntryType>,
     pub group_id: Option<Option<String>>,
     pub title: Option<String>,
     pub url: Option<String>,
     pub username: Option<String>,
     pub password: Option<String>,
     pub notes: Option<String>,
+    #[serde(default)]
+    pub url_match: Option<UrlMatch>,
+    #[serde(default)]
+    pub extra_urls: Option<Vec<EntryUrl>>,
+    #[serde(default)]
+    pub fields: Option<Vec<EntryField>>,
+    /// An empty sequence clears the entry's own, so the group's or the default applies
+    #[serde(default)]
+    pub autotype_sequence: Option<Option<String>>,
     pub is_favorite: Option<bool>,
     pub sort_order: Option<i32>,
 }
+
+/// Filters shared by entry listing and selective export
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EntryFilter {
+    pub group_ids: Option<Vec<String>>,
+    #[serde(default)]
+    pub entry_types: Option<Vec<EntryType>>,
+    pub entry_ids: Option<Vec<String>>,
+    pub search: Option<String>,
+    pub favorites_only: Option<bool>,
+    /// Order of the result, doesn't narrow it
+    #[serde(default)]
+    pub sort: EntrySort,
+}
+
+/// Orders for entry lists
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum EntrySort {
+    /// User-defined order, most recently changed first within it
+    #[default]
+    Manual,
+    Title,
+    Updated,
+    Created,
+    LastUsed,
+    UseCount,
+}
+
+impl EntrySort {
+    pub fn order_by(self) -> &'static str {
+        match self {
+            EntrySort::Manual => "sort_order ASC, updated_at DESC",
+            EntrySort::Title => "title COLLATE NOCASE ASC",
+            EntrySort::Updated => "updated_at DESC",
+            EntrySort::Created => "created_at DESC",
+            EntrySort::LastUsed => "last_used_at IS NULL, last_used_at DESC, title COLLATE NOCASE ASC",
+            EntrySort::UseCount => "use_count DESC, last_used_at DESC, title COLLATE NOCASE ASC",
+        }
+    }
+}
+
+/// How often and when an entry's secrets were last used on this device
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EntryUsage {
+    pub entry_id: String,
+    pub last_used_at: Option<i64>,
+    pub use_count: i64,
+}
+
+impl EntryFilter {
+    /// True when no filter narrows the result set
+    pub fn is_empty(&self) -> bool {
+        self.group_ids.is_none()
+            && self.entry_types.is_none()
+            && self.entry_ids.is_none()
+            && self.search.as_deref().is_none_or(str::is_empty)
+            && self.favorites_only != Some(true)
+    }
+}
+
+/// An entry matching a page or app, best matches first
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EntryMatch {
+    pub entry: Entry,
+    /// Higher is a closer match
+    pub score: u32,
+    /// The saved URL that matched
+    pub matched_url: String,
+}
@@ -7,6 +7,9 @@ pub struct Group {
     pub name: String,
     pub icon: String,
     pub sort_order: i32,
+    /// Auto-type sequence for entries of this group that don't set their own
+    #[serde(default)]
+    pub autotype_sequence: Option<String>,
     pub created_at: i64,
     pub updated_at: i64,
 }
@@ -0,0 +1,27 @@
+use serde::Serialize;
+
+/// Entry flagged by the health report
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthIssue {
+    pub entry_id: String,
+    pub title: String,
+    pub detail: String,
+}
+
+/// Problems found in the vault; breaches are only known after a breach check
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthReport {
+    pub breached: Vec<HealthIssue>,
+    pub expired: Vec<HealthIssue>,
+    /// When passwords were last looked up in breach data, None if never in this session
+    pub breach_checked_at: Option<i64>,
+}
+
+impl HealthReport {
+    /// Number of entries needing attention, shown on the tray icon
+    pub fn alert_count(&self) -> usize {
+        self.breached.len() + self.expired.len()
+    }
+}
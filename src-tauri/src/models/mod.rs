@@ -1,7 +1,24 @@
+pub mod browser;
 pub mod entry;
 pub mod group;
+pub mod health;
+pub mod search;
 pub mod settings;
+pub mod shortcut;
+pub mod ssh;
+pub mod sync;
+pub mod template;
 
-pub use entry::{Entry, CreateEntryDto, UpdateEntryDto};
+pub use browser::BrowserPairing;
+pub use entry::{
+    Entry, CreateEntryDto, EntryField, EntryFilter, EntryMatch, EntrySort, EntryType, EntryTypeSchema, EntryUrl,
+    EntryUsage, FieldKind, FieldSpec, UpdateEntryDto, UrlMatch,
+};
 pub use group::Group;
-pub use settings::Settings;
+pub use health::{HealthIssue, HealthReport};
+pub use search::SearchHit;
+pub use settings::{BackupSettings, Settings, SyncBackend, SyncSettings};
+pub use shortcut::{ShortcutAction, ShortcutStatus};
+pub use ssh::{SshAgentStatus, SshKeyAlgorithm, SshKeyInfo};
+pub use sync::{SyncConflict, SyncReport};
+pub use template::{CreateTemplateDto, EntryTemplate, TemplateField, TemplateImportResult, UpdateTemplateDto};
@@ -0,0 +1,20 @@
+use serde::Serialize;
+
+use super::EntryType;
+
+/// Quick Access search result, carrying no secret values
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchHit {
+    pub id: String,
+    pub entry_type: EntryType,
+    pub group_id: Option<String>,
+    pub title: String,
+    pub username: String,
+    pub url: String,
+    /// Username, or the type's summary field with card numbers masked
+    pub subtitle: String,
+    pub has_password: bool,
+    pub is_favorite: bool,
+    pub score: u32,
+}
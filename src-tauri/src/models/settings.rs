@@ -1,9 +1,44 @@
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use crate::policy::SettingLock;
+
+/// Values of the registered settings by name, see `settings::SETTINGS`
+#[derive(Debug, Clone, Serialize)]
+pub struct Settings {
+    #[serde(flatten)]
+    pub values: Map<String, Value>,
+    /// Settings restricted by the admin policy
+    pub locked: BTreeMap<String, SettingLock>,
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-pub struct Settings {
-    pub auto_lock_minutes: i32,
-    pub clear_clipboard_seconds: i32,
-    pub theme: String,
+pub struct BackupSettings {
+    pub enabled: bool,
+    /// Target directory; empty means the default backups folder next to data.db
+    pub directory: String,
+    pub interval_hours: i32,
+    pub keep_daily: i32,
+    pub keep_weekly: i32,
+    pub keep_monthly: i32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SyncBackend {
+    Folder,
+    Webdav,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncSettings {
+    pub enabled: bool,
+    pub backend: SyncBackend,
+    /// Shared folder (Syncthing, Nextcloud, NAS mount) holding the change logs
+    pub directory: String,
+    /// WebDAV collection holding the change logs; the login is stored encrypted in the vault
+    pub webdav_url: String,
+    pub interval_minutes: i32,
 }
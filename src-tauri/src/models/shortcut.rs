@@ -0,0 +1,56 @@
+use serde::{Deserialize, Serialize};
+
+/// What a global shortcut does
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ShortcutAction {
+    QuickAccess,
+    ShowMain,
+    Lock,
+    /// Copy the password of the entry used last
+    CopyLastPassword,
+    AutoType,
+}
+
+impl ShortcutAction {
+    pub const ALL: [ShortcutAction; 5] = [
+        ShortcutAction::QuickAccess,
+        ShortcutAction::ShowMain,
+        ShortcutAction::Lock,
+        ShortcutAction::CopyLastPassword,
+        ShortcutAction::AutoType,
+    ];
+
+    /// Key in the `settings` table holding the accelerator
+    pub fn setting_key(self) -> &'static str {
+        match self {
+            ShortcutAction::QuickAccess => "shortcut_quick_access",
+            ShortcutAction::ShowMain => "shortcut_show_main",
+            ShortcutAction::Lock => "shortcut_lock",
+            ShortcutAction::CopyLastPassword => "shortcut_copy_last_password",
+            ShortcutAction::AutoType => "shortcut_auto_type",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ShortcutAction::QuickAccess => "快速访问",
+            ShortcutAction::ShowMain => "显示主窗口",
+            ShortcutAction::Lock => "锁定",
+            ShortcutAction::CopyLastPassword => "复制最近使用的密码",
+            ShortcutAction::AutoType => "自动输入",
+        }
+    }
+}
+
+/// A configured shortcut and whether it could be registered with the system
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShortcutStatus {
+    pub action: ShortcutAction,
+    /// e.g. `ctrl+shift+p`; empty when unbound
+    pub accelerator: String,
+    pub registered: bool,
+    /// Why registration failed, usually because another app holds the combination
+    pub error: Option<String>,
+}
@@ -0,0 +1,33 @@
+use serde::{Deserialize, Serialize};
+
+/// Key types offered when generating a new SSH key
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SshKeyAlgorithm {
+    Ed25519,
+    /// 4096-bit RSA
+    Rsa,
+    /// ECDSA on NIST P-256
+    Ecdsa,
+}
+
+/// Public half of an SSH key entry, for `authorized_keys` and display
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SshKeyInfo {
+    pub algorithm: String,
+    /// `authorized_keys` line
+    pub public_key: String,
+    /// SHA256 fingerprint as printed by `ssh-keygen -l`
+    pub fingerprint: String,
+    pub comment: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SshAgentStatus {
+    pub enabled: bool,
+    pub running: bool,
+    /// Value for `SSH_AUTH_SOCK`
+    pub socket_path: String,
+}
@@ -0,0 +1,29 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncReport {
+    /// Records changed locally by remote versions
+    pub pulled: usize,
+    /// Local changes published to the change log
+    pub pushed: usize,
+    pub conflicts: usize,
+    /// Devices whose log is still encrypted with an old master password; they need to join again
+    pub stale_devices: Vec<String>,
+    pub synced_at: i64,
+}
+
+/// A concurrent edit that was resolved automatically; the losing version is kept here
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncConflict {
+    pub id: String,
+    pub record_type: String,
+    pub record_id: String,
+    pub title: String,
+    /// Device whose version lost; the local device id for discarded local edits
+    pub discarded_device: String,
+    /// Discarded version as JSON, null when the discarded change was a deletion
+    pub discarded_data: Option<serde_json::Value>,
+    pub detected_at: i64,
+}
@@ -0,0 +1,62 @@
+use serde::{Deserialize, Serialize};
+use super::{EntryType, FieldKind};
+
+/// A user-defined entry layout; entries created from it start with its fields
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EntryTemplate {
+    pub id: String,
+    pub name: String,
+    pub icon: String,
+    /// Type of entries created from the template, its schema fields still apply
+    #[serde(default)]
+    pub base_type: EntryType,
+    pub fields: Vec<TemplateField>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+/// A custom field added by a template; `url`, `username` and `password` fill the entry's own columns
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TemplateField {
+    pub name: String,
+    #[serde(default)]
+    pub kind: FieldKind,
+    /// Value new entries start with
+    #[serde(default)]
+    pub default_value: String,
+    #[serde(default)]
+    pub required: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateTemplateDto {
+    pub name: String,
+    pub icon: Option<String>,
+    #[serde(default)]
+    pub base_type: Option<EntryType>,
+    #[serde(default)]
+    pub fields: Vec<TemplateField>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateTemplateDto {
+    pub name: Option<String>,
+    pub icon: Option<String>,
+    #[serde(default)]
+    pub base_type: Option<EntryType>,
+    #[serde(default)]
+    pub fields: Option<Vec<TemplateField>>,
+}
+
+/// Result of importing a template file
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TemplateImportResult {
+    pub imported: usize,
+    /// Templates that replaced a stored one with the same id
+    pub updated: usize,
+}
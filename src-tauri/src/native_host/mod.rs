@@ -0,0 +1,151 @@
+use serde_json::{json, Value};
+use std::io::{self, Read, Write};
+use std::process::ExitCode;
+
+/// Browsers accept at most 1 MB from a host; requests are far smaller
+const MAX_MESSAGE_LEN: u32 = 1024 * 1024;
+
+/// Entry point of the `op-native-host` binary
+/// Speaks the Chrome/Firefox native messaging protocol on stdio: every message is
+/// JSON prefixed with its length as a native-endian u32.
+pub fn run() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let Some(caller) = caller_origin(&args) else {
+        eprintln!("op-native-host must be started by a browser");
+        return ExitCode::FAILURE;
+    };
+
+    let mut stdin = io::stdin().lock();
+    let mut stdout = io::stdout().lock();
+    loop {
+        // The browser closes stdin when the extension disconnects
+        let message = match read_message(&mut stdin) {
+            Ok(Some(message)) => message,
+            Ok(None) => return ExitCode::SUCCESS,
+            Err(e) => {
+                eprintln!("Invalid native message: {}", e);
+                return ExitCode::FAILURE;
+            }
+        };
+
+        let mut reply = handle(&caller, &message);
+        // Let the extension match replies to its requests
+        if let (Some(id), Some(reply)) = (message.get("requestId"), reply.as_object_mut()) {
+            reply.insert("requestId".to_string(), id.clone());
+        }
+        if write_message(&mut stdout, &reply).is_err() {
+            return ExitCode::FAILURE;
+        }
+    }
+}
+
+/// Extension origin as reported by the browser, not by the extension itself
+/// Chrome passes `chrome-extension://<id>/`, Firefox the manifest path and the add-on id.
+fn caller_origin(args: &[String]) -> Option<String> {
+    if let Some(origin) = args.iter().find(|a| a.starts_with("chrome-extension://")) {
+        return Some(origin.trim_end_matches('/').to_string());
+    }
+    match args {
+        [manifest, id, ..] if manifest.ends_with(".json") => Some(format!("moz-extension:{}", id)),
+        _ => None,
+    }
+}
+
+#[cfg(unix)]
+fn handle(caller: &str, message: &Value) -> Value {
+    use crate::agent::{self, client, protocol::Request, protocol::Response};
+    use std::time::Duration;
+
+    // Long enough for the user to answer the approval prompt
+    const PROMPT_TIMEOUT: Duration = Duration::from_secs(120);
+
+    let socket = match crate::db::vaults::startup_path(std::iter::empty()) {
+        Ok(path) => agent::socket_path(&path),
+        Err(e) => return error("failed", &e),
+    };
+
+    let text = |key: &str| message.get(key).and_then(Value::as_str).unwrap_or_default().to_string();
+    let (request, timeout) = match text("type").as_str() {
+        "ping" => (Request::Status, Duration::from_secs(5)),
+        "pair" => {
+            let browser = match message.get("browser").and_then(Value::as_str) {
+                Some(browser) => browser.to_string(),
+                None if caller.starts_with("moz-extension:") => "Firefox".to_string(),
+                None => "Chrome".to_string(),
+            };
+            let request = Request::BrowserPair {
+                extension_id: caller.to_string(),
+                browser,
+                public_key: text("publicKey"),
+            };
+            (request, PROMPT_TIMEOUT)
+        }
+        "getCredentials" => {
+            let request = Request::BrowserCredentials {
+                extension_id: caller.to_string(),
+                payload: text("payload"),
+            };
+            (request, PROMPT_TIMEOUT)
+        }
+        other => return error("badRequest", &format!("未知的消息类型: {}", other)),
+    };
+
+    match client::request_with_timeout(&socket, &request, timeout) {
+        Err(_) => error("appNotRunning", "请先打开桌面应用"),
+        Ok(Response::Locked) => error("locked", "请先在桌面应用中解锁"),
+        Ok(Response::Error { message }) => error("failed", &message),
+        Ok(Response::Ok { data }) => match request {
+            Request::Status => json!({
+                "type": "status",
+                "unlocked": data.get("unlocked").cloned().unwrap_or(Value::Bool(false)),
+            }),
+            Request::BrowserPair { ref public_key, .. } => json!({
+                "type": "paired",
+                "publicKey": data.get("publicKey").cloned().unwrap_or_default(),
+                "code": crate::crypto::from_hex(public_key)
+                    .map(|key| crate::browser::pairing_code(&key))
+                    .unwrap_or_default(),
+            }),
+            _ => json!({
+                "type": "credentials",
+                "payload": data.get("payload").cloned().unwrap_or_default(),
+            }),
+        },
+    }
+}
+
+#[cfg(not(unix))]
+fn handle(_caller: &str, _message: &Value) -> Value {
+    error("unsupported", "此平台暂不支持浏览器扩展")
+}
+
+fn error(code: &str, message: &str) -> Value {
+    json!({ "type": "error", "code": code, "message": message })
+}
+
+fn read_message(input: &mut impl Read) -> io::Result<Option<Value>> {
+    let mut len = [0u8; 4];
+    match input.read_exact(&mut len) {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    }
+
+    let len = u32::from_ne_bytes(len);
+    if len > MAX_MESSAGE_LEN {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "message too large"));
+    }
+
+    let mut body = vec![0u8; len as usize];
+    input.read_exact(&mut body)?;
+    serde_json::from_slice(&body)
+        .map(Some)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+fn write_message(output: &mut impl Write, message: &Value) -> io::Result<()> {
+    let body = serde_json::to_vec(message)?;
+    output.write_all(&(body.len() as u32).to_ne_bytes())?;
+    output.write_all(&body)?;
+    output.flush()
+}
@@ -0,0 +1,179 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::fs;
+use std::sync::OnceLock;
+
+use crate::settings::{SettingKind, SettingSpec};
+
+/// Policy file written by an administrator, readable but not writable by users
+#[cfg(unix)]
+const POLICY_PATH: &str = "/etc/one-password/policy.json";
+/// Overrides the policy file location in debug builds, for testing a policy without root
+#[cfg(debug_assertions)]
+const POLICY_PATH_ENV: &str = "ONE_PASSWORD_POLICY";
+
+/// Rules an administrator enforces on every vault of this machine
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Policy {
+    /// Longest auto-lock delay users may choose; never locking is not allowed either
+    pub max_auto_lock_minutes: Option<i64>,
+    /// Longest time a copied secret may stay on the clipboard; never clearing is not allowed either
+    pub max_clear_clipboard_seconds: Option<i64>,
+    pub master_password: MasterPasswordPolicy,
+    /// Refuse exports that write passwords unencrypted
+    pub disable_plaintext_export: bool,
+    /// Settings fixed to a value, by name as in `get_settings`
+    pub settings: Map<String, Value>,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct MasterPasswordPolicy {
+    pub min_length: Option<usize>,
+    /// How many of lower case, upper case, digits and symbols the password has to mix
+    pub min_character_classes: Option<usize>,
+}
+
+/// Why a setting can't be freely changed, reported by `get_settings`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SettingLock {
+    pub reason: String,
+    /// Fixed to its current value
+    pub fixed: bool,
+    /// Highest value allowed; 0 (never) is not allowed either
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max: Option<i64>,
+}
+
+/// Policy of this machine, read once; no policy file means no restrictions
+pub fn current() -> &'static Policy {
+    static POLICY: OnceLock<Policy> = OnceLock::new();
+    POLICY.get_or_init(load)
+}
+
+fn load() -> Policy {
+    // Release builds only trust the administrator's file, users can't point elsewhere
+    #[cfg(debug_assertions)]
+    let path = std::env::var(POLICY_PATH_ENV).ok();
+    #[cfg(not(debug_assertions))]
+    let path: Option<String> = None;
+    let path = match path {
+        Some(path) => path,
+        #[cfg(unix)]
+        None => POLICY_PATH.to_string(),
+        #[cfg(not(unix))]
+        None => return Policy::default(),
+    };
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Policy::default(),
+        Err(e) => {
+            eprintln!("Failed to read policy {}: {}", path, e);
+            return Policy::default();
+        }
+    };
+    match serde_json::from_str(&content) {
+        Ok(policy) => policy,
+        Err(e) => {
+            eprintln!("Ignoring invalid policy {}: {}", path, e);
+            Policy::default()
+        }
+    }
+}
+
+impl Policy {
+    /// Value the policy fixes a setting to, if it is valid for that setting
+    fn fixed_value(&self, spec: &SettingSpec) -> Option<Value> {
+        let value = self.settings.get(spec.name)?;
+        match spec.validate(value) {
+            Ok(value) => Some(value),
+            Err(e) => {
+                eprintln!("Ignoring policy value for {}: {}", spec.name, e);
+                None
+            }
+        }
+    }
+
+    /// Highest value allowed for a delay setting
+    fn max_value(&self, spec: &SettingSpec) -> Option<i64> {
+        let max = match spec.key {
+            "auto_lock_minutes" => self.max_auto_lock_minutes,
+            "clear_clipboard_seconds" => self.max_clear_clipboard_seconds,
+            _ => None,
+        }?;
+        match spec.kind {
+            SettingKind::Int { min, max: spec_max, .. } => Some(max.clamp(min.max(1), spec_max)),
+            _ => None,
+        }
+    }
+
+    pub fn setting_lock(&self, spec: &SettingSpec) -> Option<SettingLock> {
+        if self.fixed_value(spec).is_some() {
+            return Some(SettingLock {
+                reason: format!("{}由管理员策略固定", spec.label),
+                fixed: true,
+                max: None,
+            });
+        }
+        self.max_value(spec).map(|max| SettingLock {
+            reason: format!("管理员策略要求{}不超过 {}，且不能关闭", spec.label, max),
+            fixed: false,
+            max: Some(max),
+        })
+    }
+
+    /// Value that takes effect for a stored or default value
+    pub fn enforce(&self, spec: &SettingSpec, value: Value) -> Value {
+        if let Some(fixed) = self.fixed_value(spec) {
+            return fixed;
+        }
+        match (self.max_value(spec), value.as_i64()) {
+            (Some(max), Some(number)) if number == 0 || number > max => Value::from(max),
+            _ => value,
+        }
+    }
+
+    /// Reject a new value for a setting that the policy doesn't allow
+    pub fn check_setting(&self, spec: &SettingSpec, value: &Value) -> Result<(), String> {
+        if self.enforce(spec, value.clone()) == *value {
+            return Ok(());
+        }
+        Err(self
+            .setting_lock(spec)
+            .map(|lock| lock.reason)
+            .unwrap_or_else(|| format!("管理员策略不允许修改{}", spec.label)))
+    }
+
+    pub fn check_master_password(&self, password: &str) -> Result<(), String> {
+        let rules = &self.master_password;
+        if let Some(min_length) = rules.min_length {
+            if password.chars().count() < min_length {
+                return Err(format!("管理员策略要求主密码至少 {} 个字符", min_length));
+            }
+        }
+        if let Some(min_classes) = rules.min_character_classes {
+            let classes = [
+                password.chars().any(|c| c.is_lowercase()),
+                password.chars().any(|c| c.is_uppercase()),
+                password.chars().any(|c| c.is_ascii_digit()),
+                password.chars().any(|c| !c.is_alphanumeric()),
+            ];
+            if classes.iter().filter(|present| **present).count() < min_classes {
+                return Err(format!(
+                    "管理员策略要求主密码至少包含小写字母、大写字母、数字、符号中的 {} 种",
+                    min_classes
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn check_export(&self, includes_passwords: bool) -> Result<(), String> {
+        if self.disable_plaintext_export && includes_passwords {
+            return Err("管理员策略禁止导出明文密码".to_string());
+        }
+        Ok(())
+    }
+}
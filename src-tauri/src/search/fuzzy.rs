@@ -0,0 +1,68 @@
+/// Points for every matched character
+const MATCH: i32 = 16;
+/// Extra points when a match directly follows the previous one
+const CONSECUTIVE: i32 = 24;
+/// Extra points for matching the first character of a word
+const WORD_START: i32 = 20;
+/// Lost for every skipped character between two matches
+const GAP: i32 = 2;
+/// Lost for every character before the first match, up to `MAX_LEADING_GAP`
+const LEADING_GAP: i32 = 1;
+const MAX_LEADING_GAP: i32 = 12;
+const PREFIX: i32 = 40;
+const EXACT: i32 = 80;
+/// Start positions tried per text, the rest can't beat an earlier word start by much
+const MAX_STARTS: usize = 8;
+
+/// Score of `query` as a subsequence of `text`, both lowercased; `None` when it doesn't match
+pub fn score(query: &[char], text: &[char]) -> Option<u32> {
+    let first = *query.first()?;
+    if query.len() > text.len() {
+        return None;
+    }
+
+    let best = text
+        .iter()
+        .enumerate()
+        .filter(|(_, c)| **c == first)
+        .take(MAX_STARTS)
+        .filter_map(|(start, _)| score_from(query, text, start))
+        .max()?;
+
+    let bonus = if query == text {
+        EXACT
+    } else if text.starts_with(query) {
+        PREFIX
+    } else {
+        0
+    };
+    Some((best + bonus).max(1) as u32)
+}
+
+/// Greedy match of the query starting at `start`
+fn score_from(query: &[char], text: &[char], start: usize) -> Option<i32> {
+    let mut score = -(start as i32 * LEADING_GAP).min(MAX_LEADING_GAP);
+    let mut pos = start;
+    let mut previous: Option<usize> = None;
+
+    for &q in query {
+        let found = pos + text[pos..].iter().position(|c| *c == q)?;
+        score += MATCH;
+        match previous {
+            Some(p) if p + 1 == found => score += CONSECUTIVE,
+            Some(p) => score -= GAP * (found - p - 1) as i32,
+            None => {}
+        }
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += WORD_START;
+        }
+        previous = Some(found);
+        pos = found + 1;
+    }
+    Some(score)
+}
+
+/// Lowercased characters, the form both arguments of `score` take
+pub fn normalize(text: &str) -> Vec<char> {
+    text.to_lowercase().chars().collect()
+}
@@ -0,0 +1,225 @@
+mod fuzzy;
+
+use rusqlite::Connection;
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+
+use crate::db::Database;
+use crate::entry_types;
+use crate::models::{EntryField, EntryType, FieldKind, SearchHit};
+
+/// Results returned when the caller asks for none or too many
+const DEFAULT_LIMIT: usize = 50;
+const MAX_LIMIT: usize = 500;
+
+/// Columns the index reads; passwords and notes never leave the database
+const INDEX_COLUMNS: &str =
+    "rowid, id, entry_type, group_id, title, username, url, password != '', is_favorite, COALESCE(last_used_at, updated_at), fields, use_count";
+
+/// In-memory index behind Quick Access search, built on first use and then
+/// updated from the rows the database reports as changed
+#[derive(Default)]
+pub struct SearchIndex(Mutex<Option<Index>>);
+
+struct Document {
+    hit: SearchHit,
+    title: Vec<char>,
+    username: Vec<char>,
+    url: Vec<char>,
+    /// Last use, or the last change for entries never used
+    last_active: i64,
+    use_count: i64,
+}
+
+#[derive(Default)]
+struct Index {
+    documents: HashMap<i64, Document>,
+    /// Rowid of each entry id; `INSERT OR REPLACE` moves an entry to a new rowid without reporting the old one
+    rowids: HashMap<String, i64>,
+}
+
+impl SearchIndex {
+    /// Entries matching `query` as a fuzzy subsequence of the title, username or URL
+    /// Favorites come first, then the best matches, with recently and often used entries boosted.
+    /// An empty query lists favorites and recently used entries.
+    pub fn search(&self, db: &Database, query: &str, limit: Option<usize>) -> Result<Vec<SearchHit>, String> {
+        let limit = limit.filter(|l| *l > 0).unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT);
+        let mut index = self.0.lock().map_err(|e| e.to_string())?;
+        {
+            let conn = db.conn.lock().map_err(|e| e.to_string())?;
+            db.check_outside_writes(&conn);
+            let changes = db.take_entry_changes();
+            let refreshed = match index.as_mut() {
+                Some(current) if !changes.reset => current.apply(&conn, &changes.rowids),
+                _ => Index::build(&conn).map(|built| *index = Some(built)),
+            };
+            if let Err(e) = refreshed {
+                // Start over on the next search rather than serve a half-updated index
+                *index = None;
+                return Err(e);
+            }
+        }
+        let Some(index) = index.as_ref() else {
+            return Ok(Vec::new());
+        };
+
+        let query: Vec<char> = fuzzy::normalize(query.trim());
+        let now = chrono::Utc::now().timestamp();
+        let mut hits: Vec<SearchHit> = index
+            .documents
+            .values()
+            .filter_map(|doc| {
+                let relevance = if query.is_empty() { 0 } else { doc.relevance(&query)? };
+                let mut hit = doc.hit.clone();
+                hit.score = relevance + recency_boost(now - doc.last_active) + frequency_boost(doc.use_count);
+                Some(hit)
+            })
+            .collect();
+
+        hits.sort_by(|a, b| {
+            (Reverse(a.is_favorite), Reverse(a.score), &a.title).cmp(&(Reverse(b.is_favorite), Reverse(b.score), &b.title))
+        });
+        hits.truncate(limit);
+        Ok(hits)
+    }
+}
+
+impl Index {
+    fn build(conn: &Connection) -> Result<Self, String> {
+        let mut index = Index::default();
+        for (rowid, doc) in load(conn, None)? {
+            index.insert(rowid, doc);
+        }
+        Ok(index)
+    }
+
+    /// Reload the changed rows; rows that are gone were deleted
+    fn apply(&mut self, conn: &Connection, rowids: &HashSet<i64>) -> Result<(), String> {
+        if rowids.is_empty() {
+            return Ok(());
+        }
+        let ids: Vec<i64> = rowids.iter().copied().collect();
+        for rowid in &ids {
+            self.remove(*rowid);
+        }
+        for (rowid, doc) in load(conn, Some(&ids))? {
+            self.insert(rowid, doc);
+        }
+        Ok(())
+    }
+
+    fn insert(&mut self, rowid: i64, doc: Document) {
+        if let Some(old) = self.rowids.insert(doc.hit.id.clone(), rowid) {
+            if old != rowid {
+                self.documents.remove(&old);
+            }
+        }
+        self.documents.insert(rowid, doc);
+    }
+
+    fn remove(&mut self, rowid: i64) {
+        if let Some(doc) = self.documents.remove(&rowid) {
+            self.rowids.remove(&doc.hit.id);
+        }
+    }
+}
+
+impl Document {
+    /// Best field score, the title weighing most
+    fn relevance(&self, query: &[char]) -> Option<u32> {
+        [
+            fuzzy::score(query, &self.title),
+            fuzzy::score(query, &self.username).map(|s| s * 3 / 4),
+            fuzzy::score(query, &self.url).map(|s| s / 2),
+        ]
+        .into_iter()
+        .flatten()
+        .max()
+    }
+}
+
+/// Recently used entries are more likely to be the ones being looked for
+fn recency_boost(age_seconds: i64) -> u32 {
+    const DAY: i64 = 24 * 60 * 60;
+    match age_seconds {
+        a if a < DAY => 30,
+        a if a < 7 * DAY => 20,
+        a if a < 30 * DAY => 10,
+        _ => 0,
+    }
+}
+
+/// Grows with the logarithm of the use count, so a handful of uses already counts
+fn frequency_boost(use_count: i64) -> u32 {
+    if use_count <= 0 {
+        return 0;
+    }
+    ((use_count as f64 + 1.0).log2() * 6.0).min(30.0) as u32
+}
+
+/// Read entries for the index, all of them or the given rowids
+fn load(conn: &Connection, rowids: Option<&[i64]>) -> Result<Vec<(i64, Document)>, String> {
+    let mut sql = format!("SELECT {} FROM entries", INDEX_COLUMNS);
+    if let Some(rowids) = rowids {
+        let list: Vec<String> = rowids.iter().map(i64::to_string).collect();
+        sql.push_str(&format!(" WHERE rowid IN ({})", list.join(", ")));
+    }
+
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| {
+            let entry_type = EntryType::parse(&row.get::<_, String>(2)?);
+            let username: String = row.get(5)?;
+            let fields: Vec<EntryField> = serde_json::from_str(&row.get::<_, String>(10)?).unwrap_or_default();
+            let hit = SearchHit {
+                id: row.get(1)?,
+                entry_type,
+                group_id: row.get(3)?,
+                title: row.get(4)?,
+                subtitle: subtitle(entry_type, &username, &fields),
+                username,
+                url: row.get(6)?,
+                has_password: row.get(7)?,
+                is_favorite: row.get::<_, i32>(8)? != 0,
+                score: 0,
+            };
+            Ok((
+                row.get::<_, i64>(0)?,
+                Document {
+                    title: fuzzy::normalize(&hit.title),
+                    username: fuzzy::normalize(&hit.username),
+                    url: fuzzy::normalize(&hit.url),
+                    last_active: row.get(9)?,
+                    use_count: row.get(11)?,
+                    hit,
+                },
+            ))
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+/// Line under the title; concealed summary fields are left out and card numbers show the last four digits
+fn subtitle(entry_type: EntryType, username: &str, fields: &[EntryField]) -> String {
+    let schema = entry_types::schema(entry_type);
+    let Some(name) = schema.summary_field else {
+        return String::new();
+    };
+    if name == "username" {
+        return username.to_string();
+    }
+    let value = fields.iter().find(|f| f.name == name).map(|f| f.value.as_str()).unwrap_or("");
+    match schema.fields.iter().find(|f| f.name == name).map(|f| f.kind) {
+        Some(FieldKind::CardNumber) => {
+            let digits: Vec<char> = value.chars().filter(char::is_ascii_digit).collect();
+            if digits.is_empty() {
+                String::new()
+            } else {
+                format!("•••• {}", digits[digits.len().saturating_sub(4)..].iter().collect::<String>())
+            }
+        }
+        Some(FieldKind::Concealed | FieldKind::CardCode | FieldKind::SshPrivateKey | FieldKind::Totp) => String::new(),
+        _ => value.to_string(),
+    }
+}
@@ -0,0 +1,72 @@
+mod service;
+mod store;
+
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+use zbus::blocking::connection::Builder;
+
+use crate::db::Database;
+use service::{Backend, Collection, Service, COLLECTION_PATH, DEFAULT_ALIAS_PATH, SERVICE_PATH};
+
+const BUS_NAME: &str = "org.freedesktop.secrets";
+/// D-Bus address to serve on instead of the session bus, e.g. a private `dbus-daemon` for testing
+const BUS_ADDRESS_ENV: &str = "ONE_PASSWORD_SECRETS_BUS";
+
+/// Bus connection owning `org.freedesktop.secrets` while the provider is enabled
+#[derive(Default)]
+pub struct SecretServiceHandle(Mutex<Option<zbus::blocking::Connection>>);
+
+/// Whether the provider is switched on for the current vault
+pub fn is_enabled(conn: &rusqlite::Connection) -> bool {
+    crate::settings::get_bool(conn, "secret_service_enabled")
+}
+
+/// Start serving the Secret Service API; fails if another keyring owns the name
+pub fn start(app: &AppHandle) -> Result<(), String> {
+    let handle = app.state::<SecretServiceHandle>();
+    let mut current = handle.0.lock().map_err(|e| e.to_string())?;
+    if current.is_some() {
+        return Ok(());
+    }
+
+    let builder = match std::env::var(BUS_ADDRESS_ENV) {
+        Ok(address) => Builder::address(address.as_str()),
+        Err(_) => Builder::session(),
+    }
+    .map_err(|e| format!("无法连接 D-Bus: {}", e))?;
+
+    let backend = Backend::new(app.clone());
+    let connection = builder
+        .serve_at(SERVICE_PATH, Service { backend: backend.clone() })
+        .and_then(|b| b.serve_at(COLLECTION_PATH, Collection { backend: backend.clone() }))
+        .and_then(|b| b.serve_at(DEFAULT_ALIAS_PATH, Collection { backend }))
+        .and_then(|b| b.name(BUS_NAME))
+        .and_then(|b| b.build())
+        .map_err(|e| format!("无法注册系统密钥环服务，可能已有其他密钥环在运行: {}", e))?;
+
+    *current = Some(connection);
+    Ok(())
+}
+
+/// Release the bus name and drop all exported objects
+pub fn stop(app: &AppHandle) {
+    let handle = app.state::<SecretServiceHandle>();
+    if let Ok(mut current) = handle.0.lock() {
+        current.take();
+    };
+}
+
+/// Start the provider at launch when the vault has it enabled
+pub fn start_for_app(app: &AppHandle) {
+    let enabled = {
+        let db = app.state::<Database>();
+        let Ok(conn) = db.conn.lock() else { return };
+        is_enabled(&conn)
+    };
+
+    if enabled {
+        if let Err(e) = start(app) {
+            eprintln!("Failed to start Secret Service provider: {}", e);
+        }
+    }
+}
@@ -0,0 +1,600 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+use zbus::message::Header;
+use zbus::object_server::SignalEmitter;
+use zbus::zvariant::{ObjectPath, OwnedObjectPath, OwnedValue, Type, Value};
+use zbus::{fdo, interface, DBusError, ObjectServer};
+
+use super::store::{self, StoredItem};
+use crate::crypto::Session;
+use crate::db::Database;
+
+pub const SERVICE_PATH: &str = "/org/freedesktop/secrets";
+pub const COLLECTION_PATH: &str = "/org/freedesktop/secrets/collection/login";
+pub const DEFAULT_ALIAS_PATH: &str = "/org/freedesktop/secrets/aliases/default";
+const SESSION_PREFIX: &str = "/org/freedesktop/secrets/session/s";
+const PROMPT_PREFIX: &str = "/org/freedesktop/secrets/prompt/p";
+
+const LABEL_PROPERTY: &str = "org.freedesktop.Secret.Item.Label";
+const ATTRIBUTES_PROPERTY: &str = "org.freedesktop.Secret.Item.Attributes";
+
+/// How long an unlock prompt waits for the user before reporting dismissal
+const PROMPT_TIMEOUT: Duration = Duration::from_secs(300);
+const PROMPT_POLL: Duration = Duration::from_millis(500);
+
+#[derive(Debug, DBusError)]
+#[zbus(prefix = "org.freedesktop.Secret.Error")]
+pub enum SecretError {
+    #[zbus(error)]
+    ZBus(zbus::Error),
+    IsLocked(String),
+    NoSession(String),
+    NoSuchObject(String),
+}
+
+impl From<String> for SecretError {
+    fn from(message: String) -> Self {
+        SecretError::ZBus(zbus::Error::Failure(message))
+    }
+}
+
+fn not_supported(message: &str) -> SecretError {
+    SecretError::ZBus(zbus::Error::FDO(Box::new(fdo::Error::NotSupported(message.to_string()))))
+}
+
+/// Secret as transferred over the bus; only the `plain` algorithm is offered
+#[derive(Debug, Serialize, Deserialize, Type)]
+pub struct Secret {
+    session: OwnedObjectPath,
+    parameters: Vec<u8>,
+    value: Vec<u8>,
+    content_type: String,
+}
+
+/// State shared by all exported objects
+#[derive(Clone)]
+pub struct Backend {
+    app: AppHandle,
+    sessions: Arc<Mutex<HashSet<String>>>,
+    /// Item paths currently exported
+    exported: Arc<Mutex<HashSet<String>>>,
+    next_id: Arc<AtomicU64>,
+}
+
+impl Backend {
+    pub fn new(app: AppHandle) -> Self {
+        Backend {
+            app,
+            sessions: Arc::default(),
+            exported: Arc::default(),
+            next_id: Arc::default(),
+        }
+    }
+
+    fn with_conn<T>(&self, f: impl FnOnce(&rusqlite::Connection) -> Result<T, String>) -> Result<T, String> {
+        let db = self.app.state::<Database>();
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        f(&conn)
+    }
+
+    fn is_unlocked(&self) -> bool {
+        self.app.state::<Session>().is_unlocked()
+    }
+
+    fn ensure_unlocked(&self) -> Result<(), SecretError> {
+        if self.is_unlocked() {
+            Ok(())
+        } else {
+            Err(SecretError::IsLocked("密码库已锁定".to_string()))
+        }
+    }
+
+    /// `ensure_unlocked` for property setters, which can only return standard D-Bus errors
+    fn ensure_unlocked_property(&self) -> fdo::Result<()> {
+        self.ensure_unlocked().map_err(|e| fdo::Error::AccessDenied(format!("{:?}", e)))
+    }
+
+    fn ensure_session(&self, session: &ObjectPath<'_>) -> Result<(), SecretError> {
+        let open = self.sessions.lock().map_err(|e| e.to_string())?.contains(session.as_str());
+        if open {
+            Ok(())
+        } else {
+            Err(SecretError::NoSession(format!("会话不存在: {}", session)))
+        }
+    }
+
+    /// Export objects for items added since the last call and drop deleted ones
+    /// Entries change from the UI and sync without notice, so this runs before paths are handed out.
+    async fn refresh(&self, server: &ObjectServer) -> Result<Vec<StoredItem>, SecretError> {
+        let items = self.with_conn(store::items)?;
+        let current: HashSet<String> = items.iter().map(|item| item_path(&item.entry.id).to_string()).collect();
+
+        let (added, removed) = {
+            let mut exported = self.exported.lock().map_err(|e| e.to_string())?;
+            let added: Vec<String> = current.difference(&exported).cloned().collect();
+            let removed: Vec<String> = exported.difference(&current).cloned().collect();
+            *exported = current;
+            (added, removed)
+        };
+
+        for path in added {
+            let id = item_id(&path).unwrap_or_default();
+            server.at(path.as_str(), Item { backend: self.clone(), id }).await?;
+        }
+        for path in removed {
+            server.remove::<Item, _>(path.as_str()).await?;
+        }
+
+        Ok(items)
+    }
+
+    /// Register a prompt that completes once the vault is unlocked in the main window
+    async fn unlock_prompt(&self, server: &ObjectServer, objects: Vec<OwnedObjectPath>) -> Result<OwnedObjectPath, SecretError> {
+        let path = format!("{}{}", PROMPT_PREFIX, self.next_id.fetch_add(1, Ordering::Relaxed));
+        let prompt = Prompt {
+            backend: self.clone(),
+            objects,
+            dismissed: Arc::new(AtomicBool::new(false)),
+        };
+        server.at(path.as_str(), prompt).await?;
+        Ok(object_path(&path))
+    }
+
+    /// Bring up the main window, whose lock screen is the unlock dialog
+    fn show_unlock_dialog(&self) {
+        if let Some(window) = self.app.get_webview_window("main") {
+            let _ = window.show();
+            let _ = window.set_focus();
+            let _ = window.emit("secret-service-unlock", ());
+        }
+    }
+}
+
+/// Item object path for an entry id; characters not allowed in paths are hex-escaped
+pub fn item_path(id: &str) -> OwnedObjectPath {
+    let mut path = format!("{}/", COLLECTION_PATH);
+    for c in id.chars() {
+        if c.is_ascii_alphanumeric() {
+            path.push(c);
+        } else {
+            for byte in c.to_string().bytes() {
+                path.push_str(&format!("_{:02x}", byte));
+            }
+        }
+    }
+    object_path(&path)
+}
+
+/// Entry id for an item path produced by `item_path`
+fn item_id(path: &str) -> Option<String> {
+    let escaped = path.strip_prefix(COLLECTION_PATH)?.strip_prefix('/')?;
+    let mut bytes = Vec::with_capacity(escaped.len());
+    let mut chars = escaped.bytes();
+    while let Some(b) = chars.next() {
+        if b == b'_' {
+            let hex = [chars.next()?, chars.next()?];
+            bytes.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+        } else {
+            bytes.push(b);
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
+fn object_path(path: &str) -> OwnedObjectPath {
+    ObjectPath::try_from(path.to_string())
+        .map(OwnedObjectPath::from)
+        .unwrap_or_else(|_| no_prompt())
+}
+
+/// The "/" path used where no object (e.g. no prompt) is returned
+fn no_prompt() -> OwnedObjectPath {
+    OwnedObjectPath::from(ObjectPath::from_static_str_unchecked("/"))
+}
+
+fn is_collection(path: &ObjectPath<'_>) -> bool {
+    path.as_str() == COLLECTION_PATH || path.as_str() == DEFAULT_ALIAS_PATH
+}
+
+fn plain_secret(session: OwnedObjectPath, value: &str) -> Secret {
+    Secret {
+        session,
+        parameters: Vec::new(),
+        value: value.as_bytes().to_vec(),
+        content_type: "text/plain; charset=utf8".to_string(),
+    }
+}
+
+fn secret_text(secret: Secret) -> Result<String, SecretError> {
+    String::from_utf8(secret.value).map_err(|_| not_supported("仅支持文本密钥"))
+}
+
+pub struct Service {
+    pub backend: Backend,
+}
+
+#[interface(name = "org.freedesktop.Secret.Service")]
+impl Service {
+    #[zbus(out_args("output", "result"))]
+    async fn open_session(
+        &self,
+        algorithm: &str,
+        _input: OwnedValue,
+        #[zbus(object_server)] server: &ObjectServer,
+    ) -> Result<(OwnedValue, OwnedObjectPath), SecretError> {
+        // Secrets never leave the machine, so transport encryption is not offered
+        if algorithm != "plain" {
+            return Err(not_supported("仅支持 plain 会话算法"));
+        }
+
+        let path = format!("{}{}", SESSION_PREFIX, self.backend.next_id.fetch_add(1, Ordering::Relaxed));
+        server.at(path.as_str(), SecretSession { backend: self.backend.clone() }).await?;
+        self.backend.sessions.lock().map_err(|e| e.to_string())?.insert(path.clone());
+
+        let output = Value::from("").try_to_owned().map_err(zbus::Error::from)?;
+        Ok((output, object_path(&path)))
+    }
+
+    /// There is a single collection; creating one returns it
+    #[zbus(out_args("collection", "prompt"))]
+    fn create_collection(&self, _properties: HashMap<String, OwnedValue>, _alias: &str) -> (OwnedObjectPath, OwnedObjectPath) {
+        (object_path(COLLECTION_PATH), no_prompt())
+    }
+
+    #[zbus(out_args("unlocked", "locked"))]
+    async fn search_items(
+        &self,
+        attributes: HashMap<String, String>,
+        #[zbus(object_server)] server: &ObjectServer,
+    ) -> Result<(Vec<OwnedObjectPath>, Vec<OwnedObjectPath>), SecretError> {
+        self.backend.refresh(server).await?;
+        let paths = self
+            .backend
+            .with_conn(|conn| store::search(conn, &attributes))?
+            .iter()
+            .map(|item| item_path(&item.entry.id))
+            .collect();
+
+        if self.backend.is_unlocked() {
+            Ok((paths, Vec::new()))
+        } else {
+            Ok((Vec::new(), paths))
+        }
+    }
+
+    #[zbus(out_args("unlocked", "prompt"))]
+    async fn unlock(
+        &self,
+        objects: Vec<OwnedObjectPath>,
+        #[zbus(object_server)] server: &ObjectServer,
+    ) -> Result<(Vec<OwnedObjectPath>, OwnedObjectPath), SecretError> {
+        if self.backend.is_unlocked() {
+            return Ok((objects, no_prompt()));
+        }
+        let prompt = self.backend.unlock_prompt(server, objects).await?;
+        Ok((Vec::new(), prompt))
+    }
+
+    /// Locks the whole vault, as everything shares the master password
+    #[zbus(out_args("locked", "prompt"))]
+    fn lock(&self, objects: Vec<OwnedObjectPath>) -> (Vec<OwnedObjectPath>, OwnedObjectPath) {
+        crate::commands::auth::lock_app(&self.backend.app);
+        (objects, no_prompt())
+    }
+
+    async fn get_secrets(
+        &self,
+        items: Vec<OwnedObjectPath>,
+        session: OwnedObjectPath,
+    ) -> Result<HashMap<OwnedObjectPath, Secret>, SecretError> {
+        self.backend.ensure_session(&session)?;
+        self.backend.ensure_unlocked()?;
+
+        // Unknown items are left out rather than failing the whole call
+        self.backend
+            .with_conn(|conn| {
+                let mut secrets = HashMap::new();
+                for path in items {
+                    let Some(id) = item_id(path.as_str()) else { continue };
+                    if let Some(item) = store::item(conn, &id)? {
+                        secrets.insert(path, plain_secret(session.clone(), &item.entry.password));
+                    }
+                }
+                Ok(secrets)
+            })
+            .map_err(SecretError::from)
+    }
+
+    fn read_alias(&self, name: &str) -> OwnedObjectPath {
+        match name {
+            "default" | "login" => object_path(COLLECTION_PATH),
+            _ => no_prompt(),
+        }
+    }
+
+    fn set_alias(&self, name: &str, collection: OwnedObjectPath) -> Result<(), SecretError> {
+        if name == "default" && is_collection(&collection) {
+            Ok(())
+        } else {
+            Err(not_supported("仅支持 default 别名"))
+        }
+    }
+
+    #[zbus(property)]
+    fn collections(&self) -> Vec<OwnedObjectPath> {
+        vec![object_path(COLLECTION_PATH)]
+    }
+}
+
+pub struct Collection {
+    pub backend: Backend,
+}
+
+#[interface(name = "org.freedesktop.Secret.Collection")]
+impl Collection {
+    fn delete(&self) -> Result<OwnedObjectPath, SecretError> {
+        Err(not_supported("不能删除系统密钥环集合"))
+    }
+
+    async fn search_items(
+        &self,
+        attributes: HashMap<String, String>,
+        #[zbus(object_server)] server: &ObjectServer,
+    ) -> Result<Vec<OwnedObjectPath>, SecretError> {
+        self.backend.refresh(server).await?;
+        Ok(self
+            .backend
+            .with_conn(|conn| store::search(conn, &attributes))?
+            .iter()
+            .map(|item| item_path(&item.entry.id))
+            .collect())
+    }
+
+    #[zbus(out_args("item", "prompt"))]
+    async fn create_item(
+        &self,
+        properties: HashMap<String, OwnedValue>,
+        secret: Secret,
+        replace: bool,
+        #[zbus(object_server)] server: &ObjectServer,
+    ) -> Result<(OwnedObjectPath, OwnedObjectPath), SecretError> {
+        self.backend.ensure_session(&secret.session)?;
+        self.backend.ensure_unlocked()?;
+
+        let label = properties
+            .get(LABEL_PROPERTY)
+            .and_then(|v| v.try_clone().ok())
+            .and_then(|v| String::try_from(v).ok())
+            .unwrap_or_default();
+        let attributes = properties
+            .get(ATTRIBUTES_PROPERTY)
+            .and_then(|v| v.try_clone().ok())
+            .and_then(|v| HashMap::<String, String>::try_from(v).ok())
+            .unwrap_or_default();
+        let value = secret_text(secret)?;
+
+        let id = self
+            .backend
+            .with_conn(|conn| store::create(conn, &label, &attributes, value, replace))?;
+        self.backend.refresh(server).await?;
+
+        Ok((item_path(&id), no_prompt()))
+    }
+
+    #[zbus(property)]
+    async fn items(&self, #[zbus(object_server)] server: &ObjectServer) -> fdo::Result<Vec<OwnedObjectPath>> {
+        let items = self
+            .backend
+            .refresh(server)
+            .await
+            .map_err(|e| fdo::Error::Failed(format!("{:?}", e)))?;
+        Ok(items.iter().map(|item| item_path(&item.entry.id)).collect())
+    }
+
+    #[zbus(property)]
+    fn label(&self) -> fdo::Result<String> {
+        self.backend
+            .with_conn(store::collection_group)
+            .map(|group| group.name)
+            .map_err(fdo::Error::Failed)
+    }
+
+    #[zbus(property)]
+    fn set_label(&mut self, label: String) -> fdo::Result<()> {
+        self.backend.ensure_unlocked_property()?;
+        self.backend
+            .with_conn(|conn| store::set_collection_label(conn, &label))
+            .map_err(fdo::Error::Failed)
+    }
+
+    #[zbus(property)]
+    fn locked(&self) -> bool {
+        !self.backend.is_unlocked()
+    }
+
+    #[zbus(property)]
+    fn created(&self) -> fdo::Result<u64> {
+        self.backend
+            .with_conn(store::collection_group)
+            .map(|group| group.created_at.max(0) as u64)
+            .map_err(fdo::Error::Failed)
+    }
+
+    #[zbus(property)]
+    fn modified(&self) -> fdo::Result<u64> {
+        self.backend
+            .with_conn(store::collection_group)
+            .map(|group| group.updated_at.max(0) as u64)
+            .map_err(fdo::Error::Failed)
+    }
+}
+
+pub struct Item {
+    backend: Backend,
+    id: String,
+}
+
+impl Item {
+    fn stored(&self) -> Result<StoredItem, SecretError> {
+        self.backend
+            .with_conn(|conn| store::item(conn, &self.id))?
+            .ok_or_else(|| SecretError::NoSuchObject(format!("条目不存在: {}", self.id)))
+    }
+
+    fn property<T>(&self, f: impl FnOnce(StoredItem) -> T) -> fdo::Result<T> {
+        self.stored()
+            .map(f)
+            .map_err(|e| fdo::Error::UnknownObject(format!("{:?}", e)))
+    }
+}
+
+#[interface(name = "org.freedesktop.Secret.Item")]
+impl Item {
+    /// The object itself is dropped on the next refresh
+    fn delete(&self) -> Result<OwnedObjectPath, SecretError> {
+        self.backend.ensure_unlocked()?;
+        self.stored()?;
+        self.backend.with_conn(|conn| store::delete(conn, &self.id))?;
+        Ok(no_prompt())
+    }
+
+    fn get_secret(&self, session: OwnedObjectPath) -> Result<(Secret,), SecretError> {
+        self.backend.ensure_session(&session)?;
+        self.backend.ensure_unlocked()?;
+        let item = self.stored()?;
+        Ok((plain_secret(session, &item.entry.password),))
+    }
+
+    fn set_secret(&self, secret: Secret) -> Result<(), SecretError> {
+        self.backend.ensure_session(&secret.session)?;
+        self.backend.ensure_unlocked()?;
+        self.stored()?;
+        let value = secret_text(secret)?;
+        self.backend.with_conn(|conn| store::set_secret(conn, &self.id, &value))?;
+        Ok(())
+    }
+
+    #[zbus(property)]
+    fn locked(&self) -> bool {
+        !self.backend.is_unlocked()
+    }
+
+    #[zbus(property)]
+    fn attributes(&self) -> fdo::Result<HashMap<String, String>> {
+        self.property(|item| item.attributes)
+    }
+
+    #[zbus(property)]
+    fn set_attributes(&mut self, attributes: HashMap<String, String>) -> fdo::Result<()> {
+        self.backend.ensure_unlocked_property()?;
+        self.backend
+            .with_conn(|conn| store::set_attributes(conn, &self.id, &attributes))
+            .map_err(fdo::Error::Failed)
+    }
+
+    #[zbus(property)]
+    fn label(&self) -> fdo::Result<String> {
+        self.property(|item| item.entry.title)
+    }
+
+    #[zbus(property)]
+    fn set_label(&mut self, label: String) -> fdo::Result<()> {
+        self.backend.ensure_unlocked_property()?;
+        self.backend
+            .with_conn(|conn| store::set_label(conn, &self.id, &label))
+            .map_err(fdo::Error::Failed)
+    }
+
+    #[zbus(property)]
+    fn created(&self) -> fdo::Result<u64> {
+        self.property(|item| item.entry.created_at.max(0) as u64)
+    }
+
+    #[zbus(property)]
+    fn modified(&self) -> fdo::Result<u64> {
+        self.property(|item| item.entry.updated_at.max(0) as u64)
+    }
+}
+
+pub struct SecretSession {
+    backend: Backend,
+}
+
+#[interface(name = "org.freedesktop.Secret.Session")]
+impl SecretSession {
+    fn close(&self, #[zbus(header)] header: Header<'_>, #[zbus(connection)] connection: &zbus::Connection) -> Result<(), SecretError> {
+        let Some(path) = header.path().map(|p| p.to_string()) else { return Ok(()) };
+        self.backend.sessions.lock().map_err(|e| e.to_string())?.remove(&path);
+
+        // Unexport once this call has returned
+        let connection = zbus::blocking::Connection::from(connection.clone());
+        thread::spawn(move || {
+            let _ = connection.object_server().remove::<SecretSession, _>(path.as_str());
+        });
+        Ok(())
+    }
+}
+
+pub struct Prompt {
+    backend: Backend,
+    objects: Vec<OwnedObjectPath>,
+    dismissed: Arc<AtomicBool>,
+}
+
+#[interface(name = "org.freedesktop.Secret.Prompt")]
+impl Prompt {
+    /// Route the request to the unlock dialog and report back once the vault is unlocked
+    fn prompt(
+        &self,
+        _window_id: &str,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] connection: &zbus::Connection,
+    ) -> Result<(), SecretError> {
+        let Some(path) = header.path().map(|p| p.to_string()) else { return Ok(()) };
+        self.backend.show_unlock_dialog();
+
+        let backend = self.backend.clone();
+        let objects = self.objects.clone();
+        let dismissed = self.dismissed.clone();
+        let connection = zbus::blocking::Connection::from(connection.clone());
+        thread::spawn(move || {
+            let started = Instant::now();
+            while !backend.is_unlocked() && !dismissed.load(Ordering::SeqCst) {
+                if started.elapsed() > PROMPT_TIMEOUT {
+                    break;
+                }
+                thread::sleep(PROMPT_POLL);
+            }
+
+            // `Dismiss` has already reported the outcome
+            if !dismissed.load(Ordering::SeqCst) {
+                let unlocked = backend.is_unlocked();
+                let result = if unlocked { objects } else { Vec::new() };
+                let _ = connection.emit_signal(
+                    None::<&str>,
+                    path.as_str(),
+                    "org.freedesktop.Secret.Prompt",
+                    "Completed",
+                    &(!unlocked, Value::from(result)),
+                );
+            }
+            let _ = connection.object_server().remove::<Prompt, _>(path.as_str());
+        });
+        Ok(())
+    }
+
+    async fn dismiss(&self, #[zbus(signal_emitter)] emitter: SignalEmitter<'_>) -> Result<(), SecretError> {
+        if !self.dismissed.swap(true, Ordering::SeqCst) {
+            Self::completed(&emitter, true, Value::from(Vec::<OwnedObjectPath>::new())).await?;
+        }
+        Ok(())
+    }
+
+    #[zbus(signal)]
+    async fn completed(emitter: &SignalEmitter<'_>, dismissed: bool, result: Value<'_>) -> zbus::Result<()>;
+}
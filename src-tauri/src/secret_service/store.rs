@@ -0,0 +1,206 @@
+use rusqlite::{Connection, OptionalExtension};
+use std::collections::HashMap;
+
+use crate::commands::entries::{apply_entry_update, insert_entry, query_entries};
+use crate::commands::groups::{group_from_row, upsert_group, GROUP_COLUMNS};
+use crate::models::{CreateEntryDto, Entry, EntryFilter, Group, UpdateEntryDto};
+
+const GROUP_SETTING: &str = "secret_service_group_id";
+
+/// An entry in the keyring group with its lookup attributes
+pub struct StoredItem {
+    pub entry: Entry,
+    pub attributes: HashMap<String, String>,
+}
+
+/// The group backing the keyring collection, created on first use
+pub fn collection_group(conn: &Connection) -> Result<Group, String> {
+    let id: Option<String> = conn
+        .query_row("SELECT value FROM settings WHERE key = ?1", [GROUP_SETTING], |row| row.get(0))
+        .optional()
+        .map_err(|e| e.to_string())?;
+
+    if let Some(id) = id {
+        let group = conn
+            .query_row(
+                &format!("SELECT {} FROM groups WHERE id = ?1", GROUP_COLUMNS),
+                [&id],
+                group_from_row,
+            )
+            .optional()
+            .map_err(|e| e.to_string())?;
+        if let Some(group) = group {
+            return Ok(group);
+        }
+    }
+
+    // Missing or deleted by the user: start a fresh group at the end of the list
+    let now = chrono::Utc::now().timestamp();
+    let sort_order: i32 = conn
+        .query_row("SELECT COALESCE(MAX(sort_order), -1) + 1 FROM groups", [], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+    let group = Group {
+        id: uuid::Uuid::new_v4().to_string(),
+        name: "系统密钥环".to_string(),
+        icon: "🔑".to_string(),
+        sort_order,
+        autotype_sequence: None,
+        created_at: now,
+        updated_at: now,
+    };
+    upsert_group(conn, &group)?;
+    conn.execute(
+        "INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)",
+        [GROUP_SETTING, &group.id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(group)
+}
+
+/// Rename the keyring group, i.e. the collection label
+pub fn set_collection_label(conn: &Connection, label: &str) -> Result<(), String> {
+    let mut group = collection_group(conn)?;
+    group.name = label.to_string();
+    group.updated_at = chrono::Utc::now().timestamp();
+    upsert_group(conn, &group)
+}
+
+/// All items in the keyring group
+pub fn items(conn: &Connection) -> Result<Vec<StoredItem>, String> {
+    let group = collection_group(conn)?;
+    let filter = EntryFilter {
+        group_ids: Some(vec![group.id]),
+        ..EntryFilter::default()
+    };
+
+    query_entries(conn, &filter)?
+        .into_iter()
+        .map(|entry| {
+            let attributes = attributes(conn, &entry.id)?;
+            Ok(StoredItem { entry, attributes })
+        })
+        .collect()
+}
+
+/// Items whose attributes include every given name/value pair
+pub fn search(conn: &Connection, query: &HashMap<String, String>) -> Result<Vec<StoredItem>, String> {
+    Ok(items(conn)?
+        .into_iter()
+        .filter(|item| query.iter().all(|(k, v)| item.attributes.get(k) == Some(v)))
+        .collect())
+}
+
+/// A single item, None when it is gone or no longer in the keyring group
+pub fn item(conn: &Connection, id: &str) -> Result<Option<StoredItem>, String> {
+    Ok(items(conn)?.into_iter().find(|item| item.entry.id == id))
+}
+
+/// Store a secret; with `replace` an item with the same attributes is overwritten
+pub fn create(
+    conn: &Connection,
+    label: &str,
+    attributes: &HashMap<String, String>,
+    secret: String,
+    replace: bool,
+) -> Result<String, String> {
+    let existing = if replace {
+        search(conn, attributes)?
+            .into_iter()
+            .find(|item| item.attributes.len() == attributes.len())
+    } else {
+        None
+    };
+
+    let id = match existing {
+        Some(item) => {
+            let update = UpdateEntryDto {
+                entry_type: None,
+                group_id: None,
+                title: Some(label.to_string()),
+                url: None,
+                username: None,
+                password: Some(secret),
+                notes: None,
+                url_match: None,
+                extra_urls: None,
+                fields: None,
+                is_favorite: None,
+                sort_order: None,
+                autotype_sequence: None,
+            };
+            apply_entry_update(conn, &item.entry.id, &update)?.id
+        }
+        None => {
+            let group = collection_group(conn)?;
+            // Well-known libsecret attributes fill the visible fields
+            let entry = CreateEntryDto {
+                entry_type: None,
+                group_id: Some(group.id),
+                title: label.to_string(),
+                url: attributes.get("server").or(attributes.get("host")).cloned(),
+                username: attributes.get("user").or(attributes.get("username")).cloned(),
+                password: Some(secret),
+                notes: None,
+                url_match: None,
+                extra_urls: None,
+                fields: None,
+                template_id: None,
+                autotype_sequence: None,
+            };
+            insert_entry(conn, entry)?.id
+        }
+    };
+
+    set_attributes(conn, &id, attributes)?;
+    Ok(id)
+}
+
+pub fn set_label(conn: &Connection, id: &str, label: &str) -> Result<(), String> {
+    update_field(conn, id, "title", label)
+}
+
+pub fn set_secret(conn: &Connection, id: &str, secret: &str) -> Result<(), String> {
+    update_field(conn, id, "password", secret)
+}
+
+pub fn set_attributes(conn: &Connection, id: &str, attributes: &HashMap<String, String>) -> Result<(), String> {
+    conn.execute("DELETE FROM secret_attributes WHERE entry_id = ?1", [id])
+        .map_err(|e| e.to_string())?;
+    for (name, value) in attributes {
+        conn.execute(
+            "INSERT INTO secret_attributes (entry_id, name, value) VALUES (?1, ?2, ?3)",
+            [id, name, value],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+pub fn delete(conn: &Connection, id: &str) -> Result<(), String> {
+    conn.execute("DELETE FROM entries WHERE id = ?1", [id])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn attributes(conn: &Connection, id: &str) -> Result<HashMap<String, String>, String> {
+    let mut stmt = conn
+        .prepare("SELECT name, value FROM secret_attributes WHERE entry_id = ?1")
+        .map_err(|e| e.to_string())?;
+    let attributes = stmt
+        .query_map([id], |row| Ok((row.get(0)?, row.get(1)?)))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<HashMap<_, _>, _>>()
+        .map_err(|e| e.to_string())?;
+    Ok(attributes)
+}
+
+/// Helper: update a text column and bump `updated_at`
+fn update_field(conn: &Connection, id: &str, column: &str, value: &str) -> Result<(), String> {
+    conn.execute(
+        &format!("UPDATE entries SET {} = ?1, updated_at = ?2 WHERE id = ?3", column),
+        rusqlite::params![value, chrono::Utc::now().timestamp(), id],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
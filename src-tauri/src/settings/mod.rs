@@ -0,0 +1,572 @@
+use rusqlite::{Connection, OptionalExtension};
+use std::collections::BTreeMap;
+use std::path::Path;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use tauri::{AppHandle, Manager};
+
+use crate::autotype::sequence;
+use crate::commands::entries::clear_usage;
+use crate::db::Database;
+use crate::models::ShortcutAction;
+use crate::policy::{self, SettingLock};
+use crate::{shortcuts, ssh, startup};
+
+/// Type, default and allowed values of a setting
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum SettingKind {
+    Bool { default: bool },
+    Int { default: i64, min: i64, max: i64 },
+    Choice { default: &'static str, options: &'static [&'static str] },
+    Text {
+        default: &'static str,
+        /// Rejects or normalizes a value, e.g. a shortcut that doesn't parse
+        #[serde(skip)]
+        check: Option<Check>,
+    },
+}
+
+type Check = fn(&str) -> Result<String, String>;
+/// Applies a saved value outside the settings table; on error the old value is put back
+type OnChange = fn(&AppHandle, &Value) -> Result<(), String>;
+/// Reads a setting kept outside the vault instead of a row of the settings table
+type Read = fn() -> Value;
+
+/// A setting stored as one row of the `settings` table
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SettingSpec {
+    /// Row key in the `settings` table
+    pub key: &'static str,
+    /// Name in the frontend and in change events
+    pub name: &'static str,
+    pub label: &'static str,
+    #[serde(flatten)]
+    pub kind: SettingKind,
+    /// Weakens protection when changed, so it can only be changed while the vault is unlocked
+    pub sensitive: bool,
+    /// Only changed by its own command, e.g. joining sync; `update_settings` and settings files leave it alone
+    pub managed: bool,
+    #[serde(skip)]
+    on_change: Option<OnChange>,
+    #[serde(skip)]
+    read: Option<Read>,
+}
+
+/// Fields most settings leave at their defaults, for `..PLAIN`
+const PLAIN: SettingSpec = SettingSpec {
+    key: "",
+    name: "",
+    label: "",
+    kind: SettingKind::Bool { default: false },
+    sensitive: false,
+    managed: false,
+    on_change: None,
+    read: None,
+};
+
+/// Every setting that `get_settings` and `update_settings` know about
+/// Adding a setting only takes an entry here and its field in the frontend `Settings` type.
+pub const SETTINGS: &[SettingSpec] = &[
+    SettingSpec {
+        key: "auto_lock_minutes",
+        name: "autoLockMinutes",
+        label: "自动锁定时间",
+        kind: SettingKind::Int { default: 5, min: 0, max: 1440 },
+        sensitive: true,
+        ..PLAIN
+    },
+    SettingSpec {
+        key: "clear_clipboard_seconds",
+        name: "clearClipboardSeconds",
+        label: "剪贴板清除时间",
+        kind: SettingKind::Int { default: 30, min: 0, max: 600 },
+        sensitive: true,
+        ..PLAIN
+    },
+    SettingSpec {
+        key: "theme",
+        name: "theme",
+        label: "主题",
+        kind: SettingKind::Choice { default: "system", options: &["system", "light", "dark"] },
+        ..PLAIN
+    },
+    SettingSpec {
+        key: "track_usage",
+        name: "trackUsage",
+        label: "记录使用情况",
+        kind: SettingKind::Bool { default: true },
+        on_change: Some(|app, value| {
+            // Turning tracking off also forgets what was recorded
+            if value == &Value::Bool(false) {
+                let db = app.state::<Database>();
+                let conn = db.conn.lock().map_err(|e| e.to_string())?;
+                clear_usage(&conn)?;
+            }
+            Ok(())
+        }),
+        ..PLAIN
+    },
+    SettingSpec {
+        key: "breach_check",
+        name: "breachCheck",
+        label: "在线泄露检查",
+        kind: SettingKind::Bool { default: false },
+        ..PLAIN
+    },
+    SettingSpec {
+        key: "launch_at_login",
+        name: "launchAtLogin",
+        label: "登录时自动启动",
+        kind: SettingKind::Bool { default: false },
+        on_change: Some(|_, value| startup::set_launch_at_login(value == &Value::Bool(true))),
+        // A property of the desktop session rather than of the vault, so it follows the autostart entry
+        read: Some(|| Value::from(startup::launch_at_login())),
+        ..PLAIN
+    },
+    SettingSpec {
+        key: "start_minimized",
+        name: "startMinimized",
+        label: "启动时最小化到托盘",
+        kind: SettingKind::Bool { default: false },
+        ..PLAIN
+    },
+    SettingSpec {
+        key: "quit_on_close",
+        name: "quitOnClose",
+        label: "关闭主窗口时退出",
+        kind: SettingKind::Bool { default: false },
+        ..PLAIN
+    },
+    SettingSpec {
+        key: "backup_enabled",
+        name: "backupEnabled",
+        label: "自动备份",
+        kind: SettingKind::Bool { default: false },
+        ..PLAIN
+    },
+    SettingSpec {
+        key: "backup_directory",
+        name: "backupDirectory",
+        label: "备份目录",
+        kind: SettingKind::Text { default: "", check: Some(absolute_path) },
+        ..PLAIN
+    },
+    SettingSpec {
+        key: "backup_interval_hours",
+        name: "backupIntervalHours",
+        label: "备份间隔",
+        kind: SettingKind::Int { default: 24, min: 1, max: 720 },
+        ..PLAIN
+    },
+    SettingSpec {
+        key: "backup_keep_daily",
+        name: "backupKeepDaily",
+        label: "每日备份保留数量",
+        kind: SettingKind::Int { default: 7, min: 0, max: 365 },
+        ..PLAIN
+    },
+    SettingSpec {
+        key: "backup_keep_weekly",
+        name: "backupKeepWeekly",
+        label: "每周备份保留数量",
+        kind: SettingKind::Int { default: 4, min: 0, max: 520 },
+        ..PLAIN
+    },
+    SettingSpec {
+        key: "backup_keep_monthly",
+        name: "backupKeepMonthly",
+        label: "每月备份保留数量",
+        kind: SettingKind::Int { default: 6, min: 0, max: 120 },
+        ..PLAIN
+    },
+    SettingSpec {
+        key: "sync_enabled",
+        name: "syncEnabled",
+        label: "同步",
+        kind: SettingKind::Bool { default: false },
+        managed: true,
+        ..PLAIN
+    },
+    SettingSpec {
+        key: "sync_backend",
+        name: "syncBackend",
+        label: "同步方式",
+        kind: SettingKind::Choice { default: "folder", options: &["folder", "webdav"] },
+        managed: true,
+        ..PLAIN
+    },
+    SettingSpec {
+        key: "sync_directory",
+        name: "syncDirectory",
+        label: "同步目录",
+        kind: SettingKind::Text { default: "", check: Some(absolute_path) },
+        managed: true,
+        ..PLAIN
+    },
+    SettingSpec {
+        key: "sync_webdav_url",
+        name: "syncWebdavUrl",
+        label: "WebDAV 地址",
+        kind: SettingKind::Text { default: "", check: None },
+        managed: true,
+        ..PLAIN
+    },
+    SettingSpec {
+        key: "sync_interval_minutes",
+        name: "syncIntervalMinutes",
+        label: "同步间隔",
+        kind: SettingKind::Int { default: 5, min: 1, max: 1440 },
+        managed: true,
+        ..PLAIN
+    },
+    SettingSpec {
+        key: "ssh_agent_enabled",
+        name: "sshAgentEnabled",
+        label: "SSH 代理",
+        kind: SettingKind::Bool { default: false },
+        sensitive: true,
+        on_change: Some(|app, value| ssh::set_agent_enabled(app, value == &Value::Bool(true))),
+        ..PLAIN
+    },
+    SettingSpec {
+        key: "secret_service_enabled",
+        name: "secretServiceEnabled",
+        label: "系统密钥环",
+        kind: SettingKind::Bool { default: false },
+        sensitive: true,
+        on_change: Some(|app, value| {
+            crate::commands::settings::apply_secret_service_enabled(app, value == &Value::Bool(true))
+        }),
+        ..PLAIN
+    },
+    // Unbinding comes first, so resetting all of them frees the default before it is bound again
+    SettingSpec {
+        key: "shortcut_show_main",
+        name: "shortcutShowMain",
+        label: "显示主窗口快捷键",
+        kind: SettingKind::Text { default: "", check: Some(shortcut) },
+        on_change: Some(|app, value| rebind(app, ShortcutAction::ShowMain, value)),
+        ..PLAIN
+    },
+    SettingSpec {
+        key: "shortcut_lock",
+        name: "shortcutLock",
+        label: "锁定快捷键",
+        kind: SettingKind::Text { default: "", check: Some(shortcut) },
+        on_change: Some(|app, value| rebind(app, ShortcutAction::Lock, value)),
+        ..PLAIN
+    },
+    SettingSpec {
+        key: "shortcut_copy_last_password",
+        name: "shortcutCopyLastPassword",
+        label: "复制最近使用的密码快捷键",
+        kind: SettingKind::Text { default: "", check: Some(shortcut) },
+        on_change: Some(|app, value| rebind(app, ShortcutAction::CopyLastPassword, value)),
+        ..PLAIN
+    },
+    SettingSpec {
+        key: "shortcut_auto_type",
+        name: "shortcutAutoType",
+        label: "自动输入快捷键",
+        kind: SettingKind::Text { default: "", check: Some(shortcut) },
+        on_change: Some(|app, value| rebind(app, ShortcutAction::AutoType, value)),
+        ..PLAIN
+    },
+    SettingSpec {
+        key: "shortcut_quick_access",
+        name: "shortcutQuickAccess",
+        label: "快速访问快捷键",
+        kind: SettingKind::Text { default: "ctrl+shift+p", check: Some(shortcut) },
+        on_change: Some(|app, value| rebind(app, ShortcutAction::QuickAccess, value)),
+        ..PLAIN
+    },
+    SettingSpec {
+        key: "autotype_default_sequence",
+        name: "autotypeDefaultSequence",
+        label: "默认自动输入序列",
+        kind: SettingKind::Text {
+            default: sequence::DEFAULT_SEQUENCE,
+            check: Some(|value| match value.trim() {
+                "" => Ok(sequence::DEFAULT_SEQUENCE.to_string()),
+                value => sequence::parse(value).map(|_| value.to_string()),
+            }),
+        },
+        ..PLAIN
+    },
+];
+
+/// Helper: directories are stored as absolute paths, empty meaning not chosen
+fn absolute_path(value: &str) -> Result<String, String> {
+    let value = value.trim();
+    if !value.is_empty() && !Path::new(value).is_absolute() {
+        return Err("目录必须是绝对路径".to_string());
+    }
+    Ok(value.to_string())
+}
+
+/// Helper: shortcuts are stored in lower case, empty meaning unbound
+fn shortcut(value: &str) -> Result<String, String> {
+    let value = value.trim().to_lowercase();
+    if !value.is_empty() {
+        shortcuts::parse(&value)?;
+    }
+    Ok(value)
+}
+
+fn rebind(app: &AppHandle, action: ShortcutAction, value: &Value) -> Result<(), String> {
+    shortcuts::rebind(app, action, value.as_str().unwrap_or_default()).map(|_| ())
+}
+
+/// A saved change whose side effects haven't run yet, see `apply`
+#[must_use]
+pub struct Change {
+    spec: &'static SettingSpec,
+    /// Row before the change, None if there was none
+    old: Option<String>,
+    value: Value,
+}
+
+impl SettingSpec {
+    pub fn default_value(&self) -> Value {
+        match self.kind {
+            SettingKind::Bool { default } => Value::from(default),
+            SettingKind::Int { default, .. } => Value::from(default),
+            SettingKind::Choice { default, .. } => Value::from(default),
+            SettingKind::Text { default, .. } => Value::from(default),
+        }
+    }
+
+    /// Check a value from the frontend, converting whole floats that JSON may produce
+    pub fn validate(&self, value: &Value) -> Result<Value, String> {
+        match self.kind {
+            SettingKind::Bool { .. } => value
+                .as_bool()
+                .map(Value::from)
+                .ok_or_else(|| format!("{}应为开关值", self.label)),
+            SettingKind::Int { min, max, .. } => {
+                let number = value
+                    .as_i64()
+                    .or_else(|| value.as_f64().filter(|f| f.fract() == 0.0).map(|f| f as i64))
+                    .ok_or_else(|| format!("{}应为整数", self.label))?;
+                if number < min || number > max {
+                    return Err(format!("{}应在 {} 到 {} 之间", self.label, min, max));
+                }
+                Ok(Value::from(number))
+            }
+            SettingKind::Choice { options, .. } => match value.as_str() {
+                Some(choice) if options.contains(&choice) => Ok(Value::from(choice)),
+                _ => Err(format!("{}只能是: {}", self.label, options.join(", "))),
+            },
+            SettingKind::Text { check, .. } => {
+                let text = value.as_str().ok_or_else(|| format!("{}应为文本", self.label))?;
+                match check {
+                    Some(check) => check(text).map(Value::from).map_err(|e| format!("{}: {}", self.label, e)),
+                    None => Ok(Value::from(text)),
+                }
+            }
+        }
+    }
+
+    /// Value of a stored row; rows that don't parse or are out of range read as the default
+    fn parse_stored(&self, raw: &str) -> Value {
+        let value = match self.kind {
+            SettingKind::Bool { .. } => raw.parse::<bool>().map(Value::from).ok(),
+            SettingKind::Int { .. } => raw.parse::<i64>().map(Value::from).ok(),
+            SettingKind::Choice { .. } | SettingKind::Text { .. } => Some(Value::from(raw)),
+        };
+        value
+            .and_then(|value| self.validate(&value).ok())
+            .unwrap_or_else(|| self.default_value())
+    }
+}
+
+fn to_stored(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+pub fn spec(key: &str) -> Option<&'static SettingSpec> {
+    SETTINGS.iter().find(|spec| spec.key == key)
+}
+
+fn spec_by_name(name: &str) -> Result<&'static SettingSpec, String> {
+    SETTINGS
+        .iter()
+        .find(|spec| spec.name == name)
+        .ok_or_else(|| format!("未知的设置: {}", name))
+}
+
+/// Current value of a registered setting, the default when unset, as limited by the admin policy
+/// Panics for keys missing from `SETTINGS`, which is a programming error.
+pub fn get(conn: &Connection, key: &str) -> Value {
+    let spec = spec(key).unwrap_or_else(|| panic!("unregistered setting {}", key));
+    if let Some(read) = spec.read {
+        return policy::current().enforce(spec, read());
+    }
+    conn.query_row("SELECT value FROM settings WHERE key = ?1", [key], |row| {
+        row.get::<_, String>(0)
+    })
+    .map(|raw| spec.parse_stored(&raw))
+    .map(|value| policy::current().enforce(spec, value))
+    .unwrap_or_else(|_| policy::current().enforce(spec, spec.default_value()))
+}
+
+pub fn get_bool(conn: &Connection, key: &str) -> bool {
+    get(conn, key).as_bool().unwrap_or(false)
+}
+
+pub fn get_int(conn: &Connection, key: &str) -> i64 {
+    get(conn, key).as_i64().unwrap_or(0)
+}
+
+pub fn get_text(conn: &Connection, key: &str) -> String {
+    match get(conn, key) {
+        Value::String(text) => text,
+        _ => String::new(),
+    }
+}
+
+/// All registered settings by name
+pub fn read_all(conn: &Connection) -> Map<String, Value> {
+    SETTINGS
+        .iter()
+        .map(|spec| (spec.name.to_string(), get(conn, spec.key)))
+        .collect()
+}
+
+/// Settings restricted by the admin policy, by name
+pub fn locks() -> BTreeMap<String, SettingLock> {
+    SETTINGS
+        .iter()
+        .filter_map(|spec| Some((spec.name.to_string(), policy::current().setting_lock(spec)?)))
+        .collect()
+}
+
+/// Save the given settings by name in one transaction, returning the ones whose value changed
+/// Every value is validated before anything is written, so a bad value changes nothing.
+/// Pass the result to `apply` once the connection is released.
+pub fn update(conn: &Connection, changes: &Map<String, Value>, unlocked: bool) -> Result<Vec<Change>, String> {
+    save(conn, changes, unlocked, false)
+}
+
+/// Put the named settings, or all of them, back to their defaults
+/// The rows are removed, so later changes to a default apply as well. Defaults the admin policy
+/// doesn't allow are reset to the closest allowed value.
+pub fn reset(conn: &Connection, names: Option<&[String]>, unlocked: bool) -> Result<Vec<Change>, String> {
+    let specs = match names {
+        Some(names) => names
+            .iter()
+            .map(|name| spec_by_name(name))
+            .collect::<Result<Vec<_>, _>>()?,
+        None => SETTINGS.iter().filter(|spec| !spec.managed).collect(),
+    };
+    let defaults = specs
+        .iter()
+        .map(|spec| (spec.name.to_string(), policy::current().enforce(spec, spec.default_value())))
+        .collect();
+    save(conn, &defaults, unlocked, true)
+}
+
+/// Set a setting managed by its own command, without side effects
+pub fn set(conn: &Connection, key: &str, value: &Value) -> Result<(), String> {
+    let spec = spec(key).unwrap_or_else(|| panic!("unregistered setting {}", key));
+    let value = spec.validate(value)?;
+    conn.execute(
+        "INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)",
+        (spec.key, to_stored(&value)),
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn save(conn: &Connection, changes: &Map<String, Value>, unlocked: bool, remove_rows: bool) -> Result<Vec<Change>, String> {
+    let mut changed = Vec::new();
+    for (name, value) in changes {
+        let spec = spec_by_name(name)?;
+        if spec.managed {
+            return Err(format!("{}不能在这里修改", spec.label));
+        }
+        let value = spec.validate(value)?;
+        policy::current().check_setting(spec, &value)?;
+        if value != get(conn, spec.key) {
+            if spec.sensitive && !unlocked {
+                return Err(format!("密码库已锁定，无法修改{}", spec.label));
+            }
+            changed.push((spec, value));
+        }
+    }
+
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let mut saved = Vec::new();
+    for (spec, value) in changed {
+        let old: Option<String> = tx
+            .query_row("SELECT value FROM settings WHERE key = ?1", [spec.key], |row| row.get(0))
+            .optional()
+            .map_err(|e| e.to_string())?;
+        if spec.read.is_none() {
+            tx.execute(
+                "INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)",
+                (spec.key, to_stored(&value)),
+            )
+            .map_err(|e| e.to_string())?;
+        }
+        saved.push(Change { spec, old, value });
+    }
+    if remove_rows {
+        for (name, _) in changes {
+            tx.execute("DELETE FROM settings WHERE key = ?1", [spec_by_name(name)?.key])
+                .map_err(|e| e.to_string())?;
+        }
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(saved)
+}
+
+/// Run the side effects of saved changes, e.g. starting the SSH agent
+/// A setting whose side effect fails gets its old value back. Returns the changes that took
+/// effect by name, and the first error.
+pub fn apply(app: &AppHandle, changes: Vec<Change>) -> (Map<String, Value>, Result<(), String>) {
+    let mut applied = Map::new();
+    let mut result = Ok(());
+    for change in changes {
+        let Some(on_change) = change.spec.on_change else {
+            applied.insert(change.spec.name.to_string(), change.value);
+            continue;
+        };
+        match on_change(app, &change.value) {
+            Ok(()) => {
+                applied.insert(change.spec.name.to_string(), change.value);
+            }
+            Err(e) => {
+                if let Err(restore) = restore(app, &change) {
+                    eprintln!("Failed to restore setting {}: {}", change.spec.key, restore);
+                }
+                if result.is_ok() {
+                    result = Err(e);
+                }
+            }
+        }
+    }
+    (applied, result)
+}
+
+fn restore(app: &AppHandle, change: &Change) -> Result<(), String> {
+    if change.spec.read.is_some() {
+        return Ok(());
+    }
+    let db = app.state::<Database>();
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    match &change.old {
+        Some(old) => conn.execute(
+            "INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)",
+            (change.spec.key, old),
+        ),
+        None => conn.execute("DELETE FROM settings WHERE key = ?1", [change.spec.key]),
+    }
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
@@ -0,0 +1,226 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Mutex;
+use rusqlite::Connection;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutEvent, ShortcutState};
+use crate::commands::entries::record_use;
+use crate::crypto::Session;
+use crate::db::Database;
+use crate::models::{ShortcutAction, ShortcutStatus};
+use crate::settings;
+
+/// Shortcuts registered with the system, by shortcut id
+#[derive(Default)]
+pub struct ShortcutRegistry {
+    actions: Mutex<HashMap<u32, ShortcutAction>>,
+    statuses: Mutex<Vec<ShortcutStatus>>,
+}
+
+impl ShortcutRegistry {
+    pub fn statuses(&self) -> Vec<ShortcutStatus> {
+        self.statuses.lock().map(|s| s.clone()).unwrap_or_default()
+    }
+}
+
+/// Accelerators configured for each action, unbound actions have an empty string
+pub fn read_bindings(conn: &Connection) -> Vec<(ShortcutAction, String)> {
+    ShortcutAction::ALL
+        .into_iter()
+        .map(|action| {
+            (action, settings::get_text(conn, action.setting_key()))
+        })
+        .collect()
+}
+
+pub fn parse(accelerator: &str) -> Result<Shortcut, String> {
+    Shortcut::from_str(accelerator).map_err(|e| format!("无效的快捷键 \"{}\": {}", accelerator, e))
+}
+
+/// Replace all registered shortcuts with the ones stored in settings
+/// Failures are kept in the registry instead of aborting, so the other shortcuts still work.
+pub fn register_all(app: &AppHandle) {
+    let bindings = match app.state::<Database>().conn.lock() {
+        Ok(conn) => read_bindings(&conn),
+        Err(e) => {
+            eprintln!("Failed to read shortcuts: {}", e);
+            return;
+        }
+    };
+
+    let global = app.global_shortcut();
+    if let Err(e) = global.unregister_all() {
+        eprintln!("Failed to unregister shortcuts: {}", e);
+    }
+
+    let mut actions = HashMap::new();
+    let mut statuses = Vec::new();
+    for (action, accelerator) in bindings {
+        let mut status = ShortcutStatus {
+            action,
+            accelerator: accelerator.clone(),
+            registered: false,
+            error: None,
+        };
+        if !accelerator.is_empty() {
+            match parse(&accelerator).and_then(|shortcut| {
+                global
+                    .register(shortcut)
+                    .map(|_| shortcut)
+                    .map_err(|e| format!("快捷键 \"{}\" 注册失败，可能已被其他程序占用: {}", accelerator, e))
+            }) {
+                Ok(shortcut) => {
+                    actions.insert(shortcut.id(), action);
+                    status.registered = true;
+                }
+                Err(e) => {
+                    eprintln!("Warning: {}", e);
+                    status.error = Some(e);
+                }
+            }
+        }
+        statuses.push(status);
+    }
+
+    let registry = app.state::<ShortcutRegistry>();
+    if let Ok(mut current) = registry.actions.lock() {
+        *current = actions;
+    }
+    if let Ok(mut current) = registry.statuses.lock() {
+        *current = statuses;
+    };
+}
+
+/// Bind an action to a new accelerator, keeping the old binding when the new one can't be registered
+/// Called once the setting is saved, which `settings::apply` puts back on error.
+pub fn rebind(app: &AppHandle, action: ShortcutAction, accelerator: &str) -> Result<ShortcutStatus, String> {
+    let accelerator = accelerator.trim().to_lowercase();
+    let registry = app.state::<ShortcutRegistry>();
+    let statuses = registry.statuses();
+
+    let new_shortcut = if accelerator.is_empty() {
+        None
+    } else {
+        let shortcut = parse(&accelerator)?;
+        let taken_by = statuses.iter().find(|s| {
+            s.action != action && !s.accelerator.is_empty() && parse(&s.accelerator).is_ok_and(|other| other == shortcut)
+        });
+        if let Some(other) = taken_by {
+            return Err(format!("快捷键已被“{}”使用", other.action.label()));
+        }
+        Some(shortcut)
+    };
+
+    let global = app.global_shortcut();
+    let old_shortcut = statuses
+        .iter()
+        .find(|s| s.action == action && s.registered)
+        .and_then(|s| parse(&s.accelerator).ok());
+    // The same combination spelled differently stays registered as is
+    if old_shortcut != new_shortcut {
+        if let Some(old) = old_shortcut {
+            let _ = global.unregister(old);
+        }
+        if let Some(shortcut) = new_shortcut {
+            if let Err(e) = global.register(shortcut) {
+                // Put the previous combination back so the action stays reachable
+                if let Some(old) = old_shortcut {
+                    let _ = global.register(old);
+                }
+                return Err(format!("快捷键 \"{}\" 注册失败，可能已被其他程序占用: {}", accelerator, e));
+            }
+        }
+    }
+
+    let status = ShortcutStatus {
+        action,
+        accelerator,
+        registered: new_shortcut.is_some(),
+        error: None,
+    };
+    if let Ok(mut actions) = registry.actions.lock() {
+        actions.retain(|_, a| *a != action);
+        if let Some(shortcut) = new_shortcut {
+            actions.insert(shortcut.id(), action);
+        }
+    }
+    if let Ok(mut current) = registry.statuses.lock() {
+        match current.iter_mut().find(|s| s.action == action) {
+            Some(existing) => *existing = status.clone(),
+            None => current.push(status.clone()),
+        }
+    }
+    Ok(status)
+}
+
+/// Global shortcut handler installed with the plugin
+pub fn handle(app: &AppHandle, shortcut: &Shortcut, event: ShortcutEvent) {
+    if event.state != ShortcutState::Pressed {
+        return;
+    }
+    let action = app
+        .state::<ShortcutRegistry>()
+        .actions
+        .lock()
+        .ok()
+        .and_then(|actions| actions.get(&shortcut.id()).copied());
+    let Some(action) = action else {
+        return;
+    };
+
+    let handle = app.clone();
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = run(&handle, action).await {
+            eprintln!("Shortcut action {:?} failed: {}", action, e);
+        }
+    });
+}
+
+async fn run(app: &AppHandle, action: ShortcutAction) -> Result<(), String> {
+    match action {
+        ShortcutAction::QuickAccess => crate::commands::window::show_quick_access(app.clone()).await,
+        ShortcutAction::ShowMain => {
+            if let Some(window) = app.get_webview_window("main") {
+                window.show().map_err(|e| e.to_string())?;
+                window.set_focus().map_err(|e| e.to_string())?;
+            }
+            Ok(())
+        }
+        ShortcutAction::Lock => {
+            crate::commands::auth::lock_app(app);
+            Ok(())
+        }
+        ShortcutAction::CopyLastPassword => {
+            // Locked: open Quick Access so the user can unlock first
+            if !app.state::<Session>().is_unlocked() {
+                return crate::commands::window::show_quick_access(app.clone()).await;
+            }
+            let password = last_used_password(app)?;
+            crate::clipboard::copy_secret(app, &password)
+        }
+        ShortcutAction::AutoType => {
+            crate::commands::window::show_quick_access(app.clone()).await?;
+            app.emit_to("quick-access", "quick-access-mode", "autoType")
+                .map_err(|e| e.to_string())
+        }
+    }
+}
+
+/// Password of the entry used last, counted as another use
+fn last_used_password(app: &AppHandle) -> Result<String, String> {
+    let db = app.state::<Database>();
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let (id, password): (String, String) = conn
+        .query_row(
+            "SELECT e.id, e.password FROM entries e
+             JOIN settings s ON s.key = 'last_used_entry_id' AND s.value = e.id",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .map_err(|_| "没有最近使用的条目".to_string())?;
+    if password.is_empty() {
+        return Err("最近使用的条目没有密码".to_string());
+    }
+    record_use(&conn, &id)?;
+    Ok(password)
+}
@@ -0,0 +1,296 @@
+use rsa::signature::{SignatureEncoding, Signer};
+use rsa::BigUint;
+use sha2::{Sha256, Sha512};
+use ssh_key::private::{KeypairData, RsaKeypair};
+use ssh_key::{Algorithm, HashAlg, PrivateKey, Signature};
+use std::fs;
+use std::io::{Read, Write};
+use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use tauri::{AppHandle, Manager};
+
+use crate::agent::server::peer_is_owner;
+use crate::browser::confirm;
+use crate::commands::entries::query_entries;
+use crate::crypto::Session;
+use crate::db::Database;
+use crate::models::{Entry, EntryFilter, EntryType};
+
+// Message numbers from draft-miller-ssh-agent
+const SSH_AGENT_FAILURE: u8 = 5;
+const SSH_AGENTC_REQUEST_IDENTITIES: u8 = 11;
+const SSH_AGENT_IDENTITIES_ANSWER: u8 = 12;
+const SSH_AGENTC_SIGN_REQUEST: u8 = 13;
+const SSH_AGENT_SIGN_RESPONSE: u8 = 14;
+const SSH_AGENT_RSA_SHA2_256: u32 = 2;
+const SSH_AGENT_RSA_SHA2_512: u32 = 4;
+
+/// Sign requests carry a session id and a few names; anything bigger is not a client of ours
+const MAX_MESSAGE_LEN: usize = 256 * 1024;
+
+/// The running agent, if any
+#[derive(Default)]
+pub struct SshAgentHandle(Mutex<Option<Running>>);
+
+struct Running {
+    path: PathBuf,
+    stopped: Arc<AtomicBool>,
+}
+
+/// `SSH_AUTH_SOCK` for the vault at `db_path`, next to the unlock agent's socket
+pub fn socket_path(db_path: &Path) -> PathBuf {
+    let agent = crate::agent::socket_path(db_path);
+    let name = agent.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    agent.with_file_name(format!("ssh-{}", name))
+}
+
+pub fn is_enabled(conn: &rusqlite::Connection) -> bool {
+    crate::settings::get_bool(conn, "ssh_agent_enabled")
+}
+
+pub fn is_running(app: &AppHandle) -> bool {
+    app.state::<SshAgentHandle>()
+        .0
+        .lock()
+        .map(|running| running.is_some())
+        .unwrap_or(false)
+}
+
+/// Serve the vault's SSH keys; keys are only listed while the vault is unlocked
+pub fn start(app: &AppHandle) -> Result<PathBuf, String> {
+    let handle = app.state::<SshAgentHandle>();
+    let mut running = handle.0.lock().map_err(|e| e.to_string())?;
+    if let Some(running) = running.as_ref() {
+        return Ok(running.path.clone());
+    }
+
+    let path = socket_path(&app.state::<Database>().path());
+    let listener = bind(&path)?;
+    let stopped = Arc::new(AtomicBool::new(false));
+
+    let app_handle = app.clone();
+    let stop_flag = stopped.clone();
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            if stop_flag.load(Ordering::SeqCst) {
+                break;
+            }
+            let Ok(stream) = stream else { continue };
+            if !peer_is_owner(&stream) {
+                continue;
+            }
+            let app = app_handle.clone();
+            thread::spawn(move || serve_connection(&app, stream));
+        }
+    });
+
+    *running = Some(Running { path: path.clone(), stopped });
+    Ok(path)
+}
+
+/// Stop accepting connections and remove the socket
+pub fn stop(app: &AppHandle) {
+    let handle = app.state::<SshAgentHandle>();
+    let Ok(mut running) = handle.0.lock() else { return };
+    if let Some(running) = running.take() {
+        running.stopped.store(true, Ordering::SeqCst);
+        // Wake the accept loop so it sees the flag
+        let _ = UnixStream::connect(&running.path);
+        let _ = fs::remove_file(&running.path);
+    }
+}
+
+/// Start the agent at launch or after a vault switch when the vault has it enabled
+pub fn start_for_app(app: &AppHandle) {
+    let enabled = {
+        let db = app.state::<Database>();
+        let Ok(conn) = db.conn.lock() else { return };
+        is_enabled(&conn)
+    };
+
+    if enabled {
+        if let Err(e) = start(app) {
+            eprintln!("Failed to start SSH agent: {}", e);
+        }
+    }
+}
+
+fn bind(path: &Path) -> Result<UnixListener, String> {
+    if let Some(dir) = path.parent() {
+        fs::DirBuilder::new()
+            .recursive(true)
+            .mode(0o700)
+            .create(dir)
+            .map_err(|e| format!("无法创建代理目录: {}", e))?;
+    }
+
+    // Only this app serves the path, so a leftover socket is stale
+    if path.exists() {
+        fs::remove_file(path).map_err(|e| format!("无法移除旧的 SSH 代理套接字: {}", e))?;
+    }
+
+    let listener = UnixListener::bind(path).map_err(|e| format!("无法启动 SSH 代理: {}", e))?;
+    fs::set_permissions(path, fs::Permissions::from_mode(0o600)).map_err(|e| e.to_string())?;
+    Ok(listener)
+}
+
+/// Answer requests until the client hangs up; `ssh` keeps one connection per session
+fn serve_connection(app: &AppHandle, mut stream: UnixStream) {
+    loop {
+        let mut len = [0u8; 4];
+        if stream.read_exact(&mut len).is_err() {
+            return;
+        }
+        let len = u32::from_be_bytes(len) as usize;
+        if len == 0 || len > MAX_MESSAGE_LEN {
+            return;
+        }
+
+        let mut message = vec![0u8; len];
+        if stream.read_exact(&mut message).is_err() {
+            return;
+        }
+
+        let response = handle(app, &message).unwrap_or_else(|| vec![SSH_AGENT_FAILURE]);
+        let mut framed = Vec::with_capacity(response.len() + 4);
+        framed.extend_from_slice(&(response.len() as u32).to_be_bytes());
+        framed.extend_from_slice(&response);
+        if stream.write_all(&framed).is_err() {
+            return;
+        }
+    }
+}
+
+/// Build the reply to one request; None answers with `SSH_AGENT_FAILURE`
+/// Adding, removing and locking keys through the agent is not supported, as keys live in the vault.
+fn handle(app: &AppHandle, message: &[u8]) -> Option<Vec<u8>> {
+    let (&kind, mut body) = message.split_first()?;
+    match kind {
+        SSH_AGENTC_REQUEST_IDENTITIES => {
+            let keys = vault_keys(app);
+            let mut reply = vec![SSH_AGENT_IDENTITIES_ANSWER];
+            reply.extend_from_slice(&(keys.len() as u32).to_be_bytes());
+            for (entry, key) in &keys {
+                put_string(&mut reply, &key.public_key().to_bytes().ok()?);
+                put_string(&mut reply, entry.title.as_bytes());
+            }
+            Some(reply)
+        }
+        SSH_AGENTC_SIGN_REQUEST => {
+            let blob = take_string(&mut body)?;
+            let data = take_string(&mut body)?;
+            let flags = take_u32(&mut body)?;
+
+            let (entry, key) = vault_keys(app)
+                .into_iter()
+                .find(|(_, key)| key.public_key().to_bytes().ok().as_deref() == Some(blob))?;
+            if !can_sign(&key, flags) {
+                return None;
+            }
+
+            let approved = confirm(
+                app,
+                "SSH 密钥使用请求",
+                &format!("是否允许使用 SSH 密钥「{}」进行签名？", entry.title),
+            );
+            if !approved || !app.state::<Session>().is_unlocked() {
+                return None;
+            }
+            app.state::<Session>().touch();
+
+            let signature = sign(&key, data, flags)?;
+            let mut encoded = Vec::new();
+            put_string(&mut encoded, signature.algorithm().as_str().as_bytes());
+            put_string(&mut encoded, signature.as_bytes());
+
+            let mut reply = vec![SSH_AGENT_SIGN_RESPONSE];
+            put_string(&mut reply, &encoded);
+            Some(reply)
+        }
+        _ => None,
+    }
+}
+
+/// SSH key entries of the open vault; none while it is locked
+fn vault_keys(app: &AppHandle) -> Vec<(Entry, PrivateKey)> {
+    if !app.state::<Session>().is_unlocked() {
+        return Vec::new();
+    }
+
+    let db = app.state::<Database>();
+    let Ok(conn) = db.conn.lock() else { return Vec::new() };
+    let Ok(entries) = query_entries(&conn, &EntryFilter::default()) else { return Vec::new() };
+
+    entries
+        .into_iter()
+        .filter(|entry| entry.entry_type == EntryType::SshKey)
+        .filter_map(|entry| {
+            let key = super::parse_private_key(&entry.password).ok()?;
+            Some((entry, key))
+        })
+        .collect()
+}
+
+/// An RSA request without flags asks for a legacy ssh-rsa (SHA-1) signature, which we don't
+/// make; answering with another algorithm would only fail on the server, so it is refused.
+fn can_sign(key: &PrivateKey, flags: u32) -> bool {
+    !matches!(key.key_data(), KeypairData::Rsa(_)) || flags & (SSH_AGENT_RSA_SHA2_256 | SSH_AGENT_RSA_SHA2_512) != 0
+}
+
+/// Sign with the hash the client asked for
+fn sign(key: &PrivateKey, data: &[u8], flags: u32) -> Option<Signature> {
+    let KeypairData::Rsa(keypair) = key.key_data() else {
+        return key.try_sign(data).ok();
+    };
+
+    let private_key = rsa_private_key(keypair)?;
+    let (hash, signature) = if flags & SSH_AGENT_RSA_SHA2_512 != 0 {
+        let signing_key = rsa::pkcs1v15::SigningKey::<Sha512>::new(private_key);
+        (HashAlg::Sha512, signing_key.try_sign(data).ok()?.to_vec())
+    } else if flags & SSH_AGENT_RSA_SHA2_256 != 0 {
+        let signing_key = rsa::pkcs1v15::SigningKey::<Sha256>::new(private_key);
+        (HashAlg::Sha256, signing_key.try_sign(data).ok()?.to_vec())
+    } else {
+        return None;
+    };
+    Signature::new(Algorithm::Rsa { hash: Some(hash) }, signature).ok()
+}
+
+/// Helper: ssh-key's own conversion passes `p` for both primes, which rsa rejects
+fn rsa_private_key(keypair: &RsaKeypair) -> Option<rsa::RsaPrivateKey> {
+    rsa::RsaPrivateKey::from_components(
+        BigUint::try_from(&keypair.public.n).ok()?,
+        BigUint::try_from(&keypair.public.e).ok()?,
+        BigUint::try_from(&keypair.private.d).ok()?,
+        vec![
+            BigUint::try_from(&keypair.private.p).ok()?,
+            BigUint::try_from(&keypair.private.q).ok()?,
+        ],
+    )
+    .ok()
+}
+
+fn put_string(out: &mut Vec<u8>, value: &[u8]) {
+    out.extend_from_slice(&(value.len() as u32).to_be_bytes());
+    out.extend_from_slice(value);
+}
+
+fn take_u32(input: &mut &[u8]) -> Option<u32> {
+    let (bytes, rest) = input.split_first_chunk::<4>()?;
+    *input = rest;
+    Some(u32::from_be_bytes(*bytes))
+}
+
+fn take_string<'a>(input: &mut &'a [u8]) -> Option<&'a [u8]> {
+    let len = take_u32(input)? as usize;
+    if input.len() < len {
+        return None;
+    }
+    let (value, rest) = input.split_at(len);
+    *input = rest;
+    Some(value)
+}
@@ -0,0 +1,79 @@
+#[cfg(unix)]
+pub mod agent;
+
+use chacha20poly1305::aead::OsRng;
+use ssh_key::{Algorithm, EcdsaCurve, HashAlg, LineEnding, PrivateKey};
+use tauri::AppHandle;
+
+use crate::models::{SshKeyAlgorithm, SshKeyInfo};
+
+/// Start or stop the built-in ssh-agent after its setting changed
+#[cfg(unix)]
+pub fn set_agent_enabled(app: &AppHandle, enabled: bool) -> Result<(), String> {
+    if enabled {
+        agent::start(app).map(|_| ())
+    } else {
+        agent::stop(app);
+        Ok(())
+    }
+}
+
+#[cfg(not(unix))]
+pub fn set_agent_enabled(_app: &AppHandle, enabled: bool) -> Result<(), String> {
+    if enabled {
+        Err("SSH 代理仅支持 macOS 和 Linux".to_string())
+    } else {
+        Ok(())
+    }
+}
+
+/// Parse a stored key; passphrase-protected keys are decrypted on import instead
+pub fn parse_private_key(text: &str) -> Result<PrivateKey, String> {
+    let key = PrivateKey::from_openssh(text.trim()).map_err(|e| format!("无效的 SSH 私钥: {}", e))?;
+    if key.is_encrypted() {
+        return Err("SSH 私钥受密码保护，请在导入时提供密码".to_string());
+    }
+    Ok(key)
+}
+
+/// Generate a new key, returned in OpenSSH format
+pub fn generate(algorithm: SshKeyAlgorithm, comment: &str) -> Result<String, String> {
+    let algorithm = match algorithm {
+        SshKeyAlgorithm::Ed25519 => Algorithm::Ed25519,
+        SshKeyAlgorithm::Rsa => Algorithm::Rsa { hash: None },
+        SshKeyAlgorithm::Ecdsa => Algorithm::Ecdsa { curve: EcdsaCurve::NistP256 },
+    };
+    let mut key = PrivateKey::random(&mut OsRng, algorithm).map_err(|e| format!("生成 SSH 密钥失败: {}", e))?;
+    key.set_comment(comment);
+    encode(&key)
+}
+
+/// Normalize an imported key to unencrypted OpenSSH format, removing its passphrase
+/// Keys are stored like every other secret in the vault, so a second passphrase adds nothing.
+pub fn import(text: &str, passphrase: Option<&str>) -> Result<String, String> {
+    let key = PrivateKey::from_openssh(text.trim()).map_err(|e| format!("无效的 SSH 私钥: {}", e))?;
+    let key = match (key.is_encrypted(), passphrase) {
+        (false, _) => key,
+        (true, Some(passphrase)) => key.decrypt(passphrase).map_err(|_| "SSH 私钥密码错误".to_string())?,
+        (true, None) => return Err("SSH 私钥受密码保护，请提供密码".to_string()),
+    };
+    encode(&key)
+}
+
+/// Public key and fingerprint of a stored key
+pub fn key_info(text: &str) -> Result<SshKeyInfo, String> {
+    let key = parse_private_key(text)?;
+    let public_key = key.public_key();
+    Ok(SshKeyInfo {
+        algorithm: key.algorithm().as_str().to_string(),
+        public_key: public_key.to_openssh().map_err(|e| e.to_string())?,
+        fingerprint: public_key.fingerprint(HashAlg::Sha256).to_string(),
+        comment: key.comment().to_string(),
+    })
+}
+
+fn encode(key: &PrivateKey) -> Result<String, String> {
+    key.to_openssh(LineEnding::LF)
+        .map(|pem| pem.to_string())
+        .map_err(|e| e.to_string())
+}
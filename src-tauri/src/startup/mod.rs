@@ -0,0 +1,120 @@
+#[cfg(target_os = "linux")]
+use std::fs;
+#[cfg(target_os = "linux")]
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager};
+
+use crate::db::Database;
+use crate::settings;
+
+#[cfg(target_os = "linux")]
+const DESKTOP_FILE: &str = "one-password.desktop";
+
+/// Apply the launch settings of the vault opened at startup
+/// The main window starts hidden (see tauri.conf.json) and is only shown here.
+pub fn apply(app: &AppHandle) {
+    let (launch_at_login, start_minimized) = match app.state::<Database>().conn.lock() {
+        Ok(conn) => (settings::get_bool(&conn, "launch_at_login"), settings::get_bool(&conn, "start_minimized")),
+        Err(_) => (false, false),
+    };
+
+    // Rewritten on every start so the entry follows the app when it is moved, and
+    // removed when the admin policy turns it off
+    if launch_at_login || self::launch_at_login() {
+        if let Err(e) = set_launch_at_login(launch_at_login) {
+            eprintln!("Warning: Failed to update autostart entry: {}", e);
+        }
+    }
+
+    if !start_minimized {
+        if let Some(window) = app.get_webview_window("main") {
+            let _ = window.show();
+            let _ = window.set_focus();
+        }
+    }
+}
+
+/// Whether the app starts when the user logs in, i.e. its autostart entry exists
+/// Kept outside the vault, since it applies whichever vault was opened last.
+#[cfg(target_os = "linux")]
+pub fn launch_at_login() -> bool {
+    desktop_file_path().is_ok_and(|path| path.exists())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn launch_at_login() -> bool {
+    false
+}
+
+/// Start the app when the user logs in, or stop doing so
+#[cfg(target_os = "linux")]
+pub fn set_launch_at_login(enabled: bool) -> Result<(), String> {
+    let path = desktop_file_path()?;
+    if !enabled {
+        return match fs::remove_file(&path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(format!("无法移除自启动项: {}", e)),
+            _ => Ok(()),
+        };
+    }
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("无法创建自启动目录: {}", e))?;
+    }
+    fs::write(&path, desktop_entry()?).map_err(|e| format!("无法写入自启动项: {}", e))
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn set_launch_at_login(enabled: bool) -> Result<(), String> {
+    if enabled {
+        Err("开机自启目前仅支持 Linux".to_string())
+    } else {
+        Ok(())
+    }
+}
+
+/// XDG autostart entry, see the Desktop Application Autostart Specification
+#[cfg(target_os = "linux")]
+fn desktop_file_path() -> Result<PathBuf, String> {
+    let config_dir = dirs::config_dir().ok_or("Failed to get config directory")?;
+    Ok(config_dir.join("autostart").join(DESKTOP_FILE))
+}
+
+#[cfg(target_os = "linux")]
+fn desktop_entry() -> Result<String, String> {
+    // An AppImage runs from a temporary mount, the image itself is what has to be started
+    let exe = match std::env::var_os("APPIMAGE") {
+        Some(path) => PathBuf::from(path),
+        None => std::env::current_exe().map_err(|e| e.to_string())?,
+    };
+    Ok(format!(
+        "[Desktop Entry]\n\
+         Type=Application\n\
+         Name=One-Password\n\
+         Comment=本地密码管理器\n\
+         Exec={}\n\
+         Terminal=false\n\
+         X-GNOME-Autostart-enabled=true\n",
+        quote_exec_arg(&exe.to_string_lossy())
+    ))
+}
+
+/// Quote an argument for the `Exec` key: reserved characters are escaped inside double
+/// quotes and `%` is doubled so it isn't read as a field code
+#[cfg(target_os = "linux")]
+fn quote_exec_arg(arg: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in arg.chars() {
+        match c {
+            // String values are unescaped before Exec quoting, so a backslash takes four
+            '\\' => quoted.push_str("\\\\\\\\"),
+            '"' | '`' | '$' => {
+                quoted.push('\\');
+                quoted.push(c);
+            }
+            '%' => quoted.push_str("%%"),
+            _ => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
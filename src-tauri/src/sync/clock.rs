@@ -0,0 +1,99 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// How two vector clocks relate
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Causality {
+    Equal,
+    Before,
+    After,
+    Concurrent,
+}
+
+/// Per-record vector clock: device id -> number of edits made on that device
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct VectorClock(BTreeMap<String, u64>);
+
+impl VectorClock {
+    pub fn parse(json: &str) -> Self {
+        serde_json::from_str(json).unwrap_or_default()
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_else(|_| "{}".to_string())
+    }
+
+    /// Record an edit made on `device`
+    pub fn increment(&mut self, device: &str) {
+        *self.0.entry(device.to_string()).or_insert(0) += 1;
+    }
+
+    /// Element-wise maximum of both clocks
+    pub fn merge(&mut self, other: &VectorClock) {
+        for (device, &count) in &other.0 {
+            let current = self.0.entry(device.clone()).or_insert(0);
+            *current = (*current).max(count);
+        }
+    }
+
+    /// Compare `self` against `other`
+    pub fn compare(&self, other: &VectorClock) -> Causality {
+        let mut less = false;
+        let mut greater = false;
+
+        for device in self.0.keys().chain(other.0.keys()) {
+            let a = self.0.get(device).copied().unwrap_or(0);
+            let b = other.0.get(device).copied().unwrap_or(0);
+            less |= a < b;
+            greater |= a > b;
+        }
+
+        match (less, greater) {
+            (false, false) => Causality::Equal,
+            (true, false) => Causality::Before,
+            (false, true) => Causality::After,
+            (true, true) => Causality::Concurrent,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clock(counts: &[(&str, u64)]) -> VectorClock {
+        VectorClock(counts.iter().map(|(device, count)| (device.to_string(), *count)).collect())
+    }
+
+    #[test]
+    fn compare_orders_clocks() {
+        let a = clock(&[("a", 1)]);
+        assert_eq!(a.compare(&a.clone()), Causality::Equal);
+        assert_eq!(a.compare(&clock(&[("a", 2)])), Causality::Before);
+        assert_eq!(clock(&[("a", 1), ("b", 1)]).compare(&a), Causality::After);
+        assert_eq!(a.compare(&clock(&[("b", 1)])), Causality::Concurrent);
+    }
+
+    #[test]
+    fn missing_devices_count_as_zero() {
+        assert_eq!(VectorClock::default().compare(&clock(&[("a", 0)])), Causality::Equal);
+        assert_eq!(VectorClock::default().compare(&clock(&[("a", 1)])), Causality::Before);
+    }
+
+    #[test]
+    fn merge_takes_the_maximum_per_device() {
+        let mut merged = clock(&[("a", 3), ("b", 1)]);
+        merged.merge(&clock(&[("b", 2), ("c", 1)]));
+        assert_eq!(merged, clock(&[("a", 3), ("b", 2), ("c", 1)]));
+    }
+
+    #[test]
+    fn increment_and_json_round_trip() {
+        let mut c = VectorClock::default();
+        c.increment("a");
+        c.increment("a");
+        assert_eq!(VectorClock::parse(&c.to_json()), clock(&[("a", 2)]));
+        assert_eq!(VectorClock::parse("not json"), VectorClock::default());
+    }
+}
@@ -0,0 +1,755 @@
+use rusqlite::{Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+
+use super::clock::{Causality, VectorClock};
+use super::transport::{self, SyncTransport};
+use crate::commands::entries::{entry_from_row, query_entries, upsert_entry, ENTRY_COLUMNS};
+use crate::commands::groups::{group_from_row, query_groups, upsert_group, GROUP_COLUMNS};
+use crate::crypto::{self, VaultKey};
+use crate::db::Database;
+use crate::models::{Entry, EntryFilter, Group, SyncReport};
+
+const LOG_VERSION: u32 = 1;
+const DEVICE_ID_FILE: &str = "device-id";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RecordKind {
+    Group,
+    Entry,
+}
+
+impl RecordKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RecordKind::Group => "group",
+            RecordKind::Entry => "entry",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "group" => Some(RecordKind::Group),
+            "entry" => Some(RecordKind::Entry),
+            _ => None,
+        }
+    }
+
+    fn table(self) -> &'static str {
+        match self {
+            RecordKind::Group => "groups",
+            RecordKind::Entry => "entries",
+        }
+    }
+}
+
+/// Latest known version of one record as published by a device
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordVersion {
+    pub kind: RecordKind,
+    pub id: String,
+    pub clock: VectorClock,
+    /// Tombstone: the record was deleted
+    pub deleted: bool,
+    pub updated_at: i64,
+    pub data: Option<serde_json::Value>,
+}
+
+/// Everything one device knows, written by that device only
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceLog {
+    pub version: u32,
+    pub device_id: String,
+    pub records: Vec<RecordVersion>,
+}
+
+/// Sync bookkeeping of a local record
+struct LocalState {
+    clock: VectorClock,
+    content_hash: String,
+    deleted: bool,
+    updated_at: i64,
+}
+
+/// Merge remote change logs into the database and publish the local log
+pub fn sync(db: &Database, key: &VaultKey, transport: &dyn SyncTransport) -> Result<SyncReport, String> {
+    let device_id = device_id()?;
+    let mut report = SyncReport::default();
+
+    transport::verify(transport, key)?;
+
+    // Fetch remote logs before taking the database lock. A log that doesn't decrypt is still
+    // under an old master password; that device has to join again and is reported, the
+    // others keep syncing. A corrupt log fails the sync instead of being silently left out.
+    let mut remote_logs = Vec::new();
+    for (device, data) in transport.read_logs(&device_id)? {
+        let Ok(plaintext) = crypto::decrypt(key, &data) else {
+            report.stale_devices.push(device);
+            continue;
+        };
+        let log = parse_log(&plaintext).map_err(|e| format!("无法读取设备 {} 的同步日志: {}", device, e))?;
+        remote_logs.push((device, log));
+    }
+
+    let (plaintext, log_changed) = {
+        let mut conn = db.conn.lock().map_err(|e| e.to_string())?;
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+        report.pushed = record_local_changes(&tx, &device_id)?;
+
+        // Groups first so merged entries can reference them
+        for kind in [RecordKind::Group, RecordKind::Entry] {
+            for (device, log) in &remote_logs {
+                for version in log.records.iter().filter(|r| r.kind == kind) {
+                    merge_version(&tx, &device_id, device, version, &mut report)?;
+                }
+            }
+        }
+
+        let log = build_log(&tx, &device_id)?;
+        let plaintext = serde_json::to_vec(&log).map_err(|e| e.to_string())?;
+
+        // Skip rewriting an unchanged log so sync tools don't transfer it again
+        let log_hash = content_hash(&plaintext);
+        let last_hash: Option<String> = tx
+            .query_row("SELECT value FROM settings WHERE key = 'sync_log_hash'", [], |row| row.get(0))
+            .optional()
+            .map_err(|e| e.to_string())?;
+        // Our own log showing up means someone else replaced it on the server
+        let overwritten = remote_logs.iter().any(|(device, _)| *device == device_id);
+        let log_changed = overwritten || last_hash.as_deref() != Some(log_hash.as_str());
+        if log_changed {
+            tx.execute(
+                "INSERT OR REPLACE INTO settings (key, value) VALUES ('sync_log_hash', ?1)",
+                [&log_hash],
+            )
+            .map_err(|e| e.to_string())?;
+        }
+
+        // Writing the log happens after commit; a failed write is retried because
+        // the stored hash is cleared below
+        tx.commit().map_err(|e| e.to_string())?;
+        (plaintext, log_changed)
+    };
+
+    if log_changed {
+        let written = crypto::encrypt(key, &plaintext).and_then(|data| transport.write_log(&device_id, &data));
+        if let Err(e) = written {
+            if let Ok(conn) = db.conn.lock() {
+                let _ = conn.execute("DELETE FROM settings WHERE key = 'sync_log_hash'", []);
+            }
+            return Err(e);
+        }
+    }
+
+    report.synced_at = chrono::Utc::now().timestamp();
+    Ok(report)
+}
+
+/// Publish this device's log under the key of a new master password, then switch the target to it
+/// The stored log hash is cleared first, so if anything fails the next sync publishes the log
+/// again under the key still in use.
+pub fn rekey(db: &Database, old_key: &VaultKey, new_key: &VaultKey, transport: &dyn SyncTransport) -> Result<(), String> {
+    let device_id = device_id()?;
+    transport::verify(transport, old_key)?;
+
+    let plaintext = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        conn.execute("DELETE FROM settings WHERE key = 'sync_log_hash'", [])
+            .map_err(|e| e.to_string())?;
+        serde_json::to_vec(&build_log(&conn, &device_id)?).map_err(|e| e.to_string())?
+    };
+
+    transport.write_log(&device_id, &crypto::encrypt(new_key, &plaintext)?)?;
+    transport::rekey(transport, new_key)
+}
+
+/// Identifier of this device, shared by all vaults on the machine
+pub fn device_id() -> Result<String, String> {
+    let path: PathBuf = dirs::config_dir()
+        .ok_or("Failed to get config directory")?
+        .join("one-password")
+        .join(DEVICE_ID_FILE);
+
+    if let Ok(id) = fs::read_to_string(&path) {
+        let id = id.trim();
+        if !id.is_empty() {
+            return Ok(id.to_string());
+        }
+    }
+
+    let id = uuid::Uuid::new_v4().to_string();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    fs::write(&path, &id).map_err(|e| format!("无法保存设备标识: {}", e))?;
+    Ok(id)
+}
+
+/// Write a record version into the entries/groups tables, or delete it for tombstones
+pub fn apply_version(conn: &Connection, kind: RecordKind, id: &str, data: Option<&serde_json::Value>) -> Result<(), String> {
+    let Some(data) = data else {
+        conn.execute(&format!("DELETE FROM {} WHERE id = ?1", kind.table()), [id])
+            .map_err(|e| e.to_string())?;
+        return Ok(());
+    };
+
+    match kind {
+        RecordKind::Group => {
+            let group: Group = serde_json::from_value(data.clone()).map_err(|e| format!("无效的分组数据: {}", e))?;
+            upsert_group(conn, &group)
+        }
+        RecordKind::Entry => {
+            let mut entry: Entry = serde_json::from_value(data.clone()).map_err(|e| format!("无效的条目数据: {}", e))?;
+            // The group may have been deleted on this device
+            if let Some(ref gid) = entry.group_id {
+                let exists: bool = conn
+                    .query_row("SELECT EXISTS(SELECT 1 FROM groups WHERE id = ?1)", [gid], |row| row.get(0))
+                    .map_err(|e| e.to_string())?;
+                if !exists {
+                    entry.group_id = None;
+                }
+            }
+            upsert_entry(conn, &entry)
+        }
+    }
+}
+
+/// Whether the vault has been synced, so local changes have clocks to bump
+pub(crate) fn has_state(conn: &Connection) -> Result<bool, String> {
+    conn.query_row("SELECT EXISTS(SELECT 1 FROM sync_state)", [], |row| row.get(0))
+        .map_err(|e| e.to_string())
+}
+
+/// Bump clocks of records edited, created or deleted since the last sync
+pub(crate) fn record_local_changes(conn: &Connection, device_id: &str) -> Result<usize, String> {
+    let states = load_states(conn)?;
+    let mut seen: HashSet<(RecordKind, String)> = HashSet::new();
+    let mut changed = 0;
+
+    for (kind, id, json, updated_at) in current_records(conn)? {
+        let hash = content_hash(json.as_bytes());
+        let mut clock = match states.get(&(kind, id.clone())) {
+            Some(state) if !state.deleted && state.content_hash == hash => {
+                seen.insert((kind, id));
+                continue;
+            }
+            Some(state) => state.clock.clone(),
+            None => VectorClock::default(),
+        };
+
+        clock.increment(device_id);
+        save_state(conn, kind, &id, &clock, &hash, false, updated_at)?;
+        seen.insert((kind, id));
+        changed += 1;
+    }
+
+    // Records that disappeared become tombstones
+    let now = chrono::Utc::now().timestamp();
+    for ((kind, id), state) in states {
+        if state.deleted || seen.contains(&(kind, id.clone())) {
+            continue;
+        }
+        let mut clock = state.clock;
+        clock.increment(device_id);
+        save_state(conn, kind, &id, &clock, "", true, now)?;
+        changed += 1;
+    }
+
+    Ok(changed)
+}
+
+/// Helper: fold one remote record version into the local database
+fn merge_version(
+    conn: &Connection,
+    device_id: &str,
+    remote_device: &str,
+    remote: &RecordVersion,
+    report: &mut SyncReport,
+) -> Result<(), String> {
+    let remote_data = if remote.deleted { None } else { remote.data.as_ref() };
+    let remote_hash = match remote_data {
+        Some(data) => content_hash(canonical_json(remote.kind, data)?.as_bytes()),
+        None => String::new(),
+    };
+
+    let local = load_state(conn, remote.kind, &remote.id)?;
+    let Some(local) = local else {
+        // Unknown record; a tombstone only needs to be remembered
+        if remote_data.is_some() {
+            apply_version(conn, remote.kind, &remote.id, remote_data)?;
+            report.pulled += 1;
+        }
+        return save_state(conn, remote.kind, &remote.id, &remote.clock, &remote_hash, remote.deleted, remote.updated_at);
+    };
+
+    match remote.clock.compare(&local.clock) {
+        Causality::Equal | Causality::Before => Ok(()),
+        Causality::After => {
+            apply_version(conn, remote.kind, &remote.id, remote_data)?;
+            report.pulled += 1;
+            save_state(conn, remote.kind, &remote.id, &remote.clock, &remote_hash, remote.deleted, remote.updated_at)
+        }
+        Causality::Concurrent => {
+            let mut merged = local.clock.clone();
+            merged.merge(&remote.clock);
+
+            // Both devices made the same change independently
+            if local.deleted == remote.deleted && local.content_hash == remote_hash {
+                return save_state(conn, remote.kind, &remote.id, &merged, &local.content_hash, local.deleted, local.updated_at);
+            }
+
+            // The resolution is a new edit that supersedes both versions
+            merged.increment(device_id);
+
+            // Last writer wins, ties broken by device id so every device picks the same winner
+            let remote_wins = (remote.updated_at, remote_device) > (local.updated_at, device_id);
+            let (discarded_device, discarded_data) = if remote_wins {
+                let local_data = if local.deleted {
+                    None
+                } else {
+                    load_record(conn, remote.kind, &remote.id)?
+                };
+                apply_version(conn, remote.kind, &remote.id, remote_data)?;
+                report.pulled += 1;
+                save_state(conn, remote.kind, &remote.id, &merged, &remote_hash, remote.deleted, remote.updated_at)?;
+                (device_id, local_data)
+            } else {
+                save_state(conn, remote.kind, &remote.id, &merged, &local.content_hash, local.deleted, local.updated_at)?;
+                (remote_device, remote_data.cloned())
+            };
+
+            let title = discarded_data
+                .as_ref()
+                .or(remote_data)
+                .and_then(|d| d.get(if remote.kind == RecordKind::Entry { "title" } else { "name" }))
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string();
+
+            conn.execute(
+                "INSERT INTO sync_conflicts (id, record_type, record_id, title, discarded_device, discarded_data, detected_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                rusqlite::params![
+                    uuid::Uuid::new_v4().to_string(),
+                    remote.kind.as_str(),
+                    remote.id,
+                    title,
+                    discarded_device,
+                    discarded_data.map(|d| d.to_string()),
+                    chrono::Utc::now().timestamp(),
+                ],
+            )
+            .map_err(|e| e.to_string())?;
+            report.conflicts += 1;
+
+            Ok(())
+        }
+    }
+}
+
+/// Helper: the log this device publishes, covering every record and tombstone it knows
+fn build_log(conn: &Connection, device_id: &str) -> Result<DeviceLog, String> {
+    let mut data: HashMap<(RecordKind, String), serde_json::Value> = HashMap::new();
+    for (kind, id, json, _) in current_records(conn)? {
+        let value = serde_json::from_str(&json).map_err(|e| e.to_string())?;
+        data.insert((kind, id), value);
+    }
+
+    let mut records: Vec<RecordVersion> = load_states(conn)?
+        .into_iter()
+        .map(|((kind, id), state)| RecordVersion {
+            data: if state.deleted { None } else { data.remove(&(kind, id.clone())) },
+            kind,
+            id,
+            clock: state.clock,
+            deleted: state.deleted,
+            updated_at: state.updated_at,
+        })
+        .collect();
+    records.sort_by(|a, b| (a.kind.as_str(), &a.id).cmp(&(b.kind.as_str(), &b.id)));
+
+    Ok(DeviceLog {
+        version: LOG_VERSION,
+        device_id: device_id.to_string(),
+        records,
+    })
+}
+
+fn parse_log(plaintext: &[u8]) -> Result<DeviceLog, String> {
+    let log: DeviceLog = serde_json::from_slice(plaintext).map_err(|e| format!("同步日志格式错误: {}", e))?;
+    if log.version != LOG_VERSION {
+        return Err(format!("不支持的同步日志版本: {}", log.version));
+    }
+    Ok(log)
+}
+
+/// Helper: all local groups and entries as (kind, id, canonical JSON, updated_at)
+fn current_records(conn: &Connection) -> Result<Vec<(RecordKind, String, String, i64)>, String> {
+    let mut records = Vec::new();
+
+    for group in query_groups(conn)? {
+        let json = serde_json::to_string(&group).map_err(|e| e.to_string())?;
+        records.push((RecordKind::Group, group.id, json, group.updated_at));
+    }
+    for entry in query_entries(conn, &EntryFilter::default())? {
+        let json = serde_json::to_string(&entry).map_err(|e| e.to_string())?;
+        records.push((RecordKind::Entry, entry.id, json, entry.updated_at));
+    }
+
+    Ok(records)
+}
+
+/// Helper: current JSON of one local record
+fn load_record(conn: &Connection, kind: RecordKind, id: &str) -> Result<Option<serde_json::Value>, String> {
+    let value = match kind {
+        RecordKind::Group => conn
+            .query_row(
+                &format!("SELECT {} FROM groups WHERE id = ?1", GROUP_COLUMNS),
+                [id],
+                group_from_row,
+            )
+            .optional()
+            .map_err(|e| e.to_string())?
+            .map(serde_json::to_value),
+        RecordKind::Entry => conn
+            .query_row(
+                &format!("SELECT {} FROM entries WHERE id = ?1", ENTRY_COLUMNS),
+                [id],
+                entry_from_row,
+            )
+            .optional()
+            .map_err(|e| e.to_string())?
+            .map(serde_json::to_value),
+    };
+
+    value.transpose().map_err(|e| e.to_string())
+}
+
+/// Helper: serialize remote data the same way local records are, so hashes compare equal
+fn canonical_json(kind: RecordKind, data: &serde_json::Value) -> Result<String, String> {
+    match kind {
+        RecordKind::Group => serde_json::from_value::<Group>(data.clone()).and_then(|g| serde_json::to_string(&g)),
+        RecordKind::Entry => serde_json::from_value::<Entry>(data.clone()).and_then(|e| serde_json::to_string(&e)),
+    }
+    .map_err(|e| format!("无效的同步数据: {}", e))
+}
+
+fn load_states(conn: &Connection) -> Result<HashMap<(RecordKind, String), LocalState>, String> {
+    let mut stmt = conn
+        .prepare("SELECT record_type, record_id, clock, content_hash, deleted, updated_at FROM sync_state")
+        .map_err(|e| e.to_string())?;
+
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                LocalState {
+                    clock: VectorClock::parse(&row.get::<_, String>(2)?),
+                    content_hash: row.get(3)?,
+                    deleted: row.get::<_, i32>(4)? != 0,
+                    updated_at: row.get(5)?,
+                },
+            ))
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok(rows
+        .into_iter()
+        .filter_map(|(kind, id, state)| Some(((RecordKind::parse(&kind)?, id), state)))
+        .collect())
+}
+
+fn load_state(conn: &Connection, kind: RecordKind, id: &str) -> Result<Option<LocalState>, String> {
+    conn.query_row(
+        "SELECT clock, content_hash, deleted, updated_at FROM sync_state WHERE record_type = ?1 AND record_id = ?2",
+        [kind.as_str(), id],
+        |row| {
+            Ok(LocalState {
+                clock: VectorClock::parse(&row.get::<_, String>(0)?),
+                content_hash: row.get(1)?,
+                deleted: row.get::<_, i32>(2)? != 0,
+                updated_at: row.get(3)?,
+            })
+        },
+    )
+    .optional()
+    .map_err(|e| e.to_string())
+}
+
+fn save_state(
+    conn: &Connection,
+    kind: RecordKind,
+    id: &str,
+    clock: &VectorClock,
+    content_hash: &str,
+    deleted: bool,
+    updated_at: i64,
+) -> Result<(), String> {
+    conn.execute(
+        "INSERT OR REPLACE INTO sync_state (record_type, record_id, clock, content_hash, deleted, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        rusqlite::params![kind.as_str(), id, clock.to_json(), content_hash, deleted as i32, updated_at],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// Helper: FNV-1a, stable across builds unlike std's hasher
+pub(crate) fn content_hash(data: &[u8]) -> String {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in data {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    format!("{:016x}", hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn database() -> Database {
+        Database::new(Path::new(":memory:")).unwrap()
+    }
+
+    fn entry(id: &str, title: &str, updated_at: i64) -> Entry {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "groupId": null,
+            "title": title,
+            "url": "",
+            "username": "",
+            "password": "secret",
+            "notes": "",
+            "isFavorite": false,
+            "sortOrder": 0,
+            "createdAt": 1,
+            "updatedAt": updated_at,
+        }))
+        .unwrap()
+    }
+
+    fn remote(entry: Option<&Entry>, id: &str, clock: &[(&str, u64)], updated_at: i64) -> RecordVersion {
+        let mut version_clock = VectorClock::default();
+        for (device, count) in clock {
+            for _ in 0..*count {
+                version_clock.increment(device);
+            }
+        }
+        RecordVersion {
+            kind: RecordKind::Entry,
+            id: id.to_string(),
+            clock: version_clock,
+            deleted: entry.is_none(),
+            updated_at,
+            data: entry.map(|e| serde_json::to_value(e).unwrap()),
+        }
+    }
+
+    fn title(conn: &Connection, id: &str) -> Option<String> {
+        conn.query_row("SELECT title FROM entries WHERE id = ?1", [id], |row| row.get(0))
+            .optional()
+            .unwrap()
+    }
+
+    fn conflicts(conn: &Connection) -> i64 {
+        conn.query_row("SELECT COUNT(*) FROM sync_conflicts", [], |row| row.get(0)).unwrap()
+    }
+
+    #[test]
+    fn local_edits_bump_the_clock_once() {
+        let db = database();
+        let conn = db.conn.lock().unwrap();
+        upsert_entry(&conn, &entry("e1", "Mail", 10)).unwrap();
+
+        assert_eq!(record_local_changes(&conn, "a").unwrap(), 1);
+        assert_eq!(record_local_changes(&conn, "a").unwrap(), 0);
+
+        upsert_entry(&conn, &entry("e1", "Mail 2", 20)).unwrap();
+        assert_eq!(record_local_changes(&conn, "a").unwrap(), 1);
+        let state = load_state(&conn, RecordKind::Entry, "e1").unwrap().unwrap();
+        assert_eq!(state.clock.compare(&remote(None, "e1", &[("a", 2)], 0).clock), Causality::Equal);
+    }
+
+    #[test]
+    fn deleted_records_become_tombstones() {
+        let db = database();
+        let conn = db.conn.lock().unwrap();
+        upsert_entry(&conn, &entry("e1", "Mail", 10)).unwrap();
+        record_local_changes(&conn, "a").unwrap();
+
+        conn.execute("DELETE FROM entries WHERE id = 'e1'", []).unwrap();
+        assert_eq!(record_local_changes(&conn, "a").unwrap(), 1);
+        assert!(load_state(&conn, RecordKind::Entry, "e1").unwrap().unwrap().deleted);
+    }
+
+    #[test]
+    fn newer_remote_version_is_applied() {
+        let db = database();
+        let conn = db.conn.lock().unwrap();
+        upsert_entry(&conn, &entry("e1", "Mail", 10)).unwrap();
+        record_local_changes(&conn, "a").unwrap();
+
+        let mut report = SyncReport::default();
+        let newer = entry("e1", "Mail (b)", 20);
+        merge_version(&conn, "a", "b", &remote(Some(&newer), "e1", &[("a", 1), ("b", 1)], 20), &mut report).unwrap();
+
+        assert_eq!(title(&conn, "e1").as_deref(), Some("Mail (b)"));
+        assert_eq!((report.pulled, report.conflicts), (1, 0));
+    }
+
+    #[test]
+    fn older_remote_version_is_ignored() {
+        let db = database();
+        let conn = db.conn.lock().unwrap();
+        upsert_entry(&conn, &entry("e1", "Mail", 10)).unwrap();
+        record_local_changes(&conn, "a").unwrap();
+        upsert_entry(&conn, &entry("e1", "Mail 2", 20)).unwrap();
+        record_local_changes(&conn, "a").unwrap();
+
+        let mut report = SyncReport::default();
+        let stale = entry("e1", "Mail", 10);
+        merge_version(&conn, "a", "b", &remote(Some(&stale), "e1", &[("a", 1)], 10), &mut report).unwrap();
+
+        assert_eq!(title(&conn, "e1").as_deref(), Some("Mail 2"));
+        assert_eq!(report.pulled, 0);
+    }
+
+    #[test]
+    fn concurrent_edits_keep_the_latest_and_record_a_conflict() {
+        let db = database();
+        let conn = db.conn.lock().unwrap();
+        upsert_entry(&conn, &entry("e1", "Mail (a)", 10)).unwrap();
+        record_local_changes(&conn, "a").unwrap();
+
+        let mut report = SyncReport::default();
+        let theirs = entry("e1", "Mail (b)", 20);
+        merge_version(&conn, "a", "b", &remote(Some(&theirs), "e1", &[("b", 1)], 20), &mut report).unwrap();
+
+        assert_eq!(title(&conn, "e1").as_deref(), Some("Mail (b)"));
+        assert_eq!(report.conflicts, 1);
+        assert_eq!(conflicts(&conn), 1);
+        let discarded: String = conn
+            .query_row("SELECT discarded_device FROM sync_conflicts", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(discarded, "a");
+
+        // The resolution supersedes both versions
+        let state = load_state(&conn, RecordKind::Entry, "e1").unwrap().unwrap();
+        let both = remote(None, "e1", &[("a", 1), ("b", 1)], 0).clock;
+        assert_eq!(state.clock.compare(&both), Causality::After);
+    }
+
+    #[test]
+    fn concurrent_edits_with_equal_time_pick_the_same_winner_everywhere() {
+        let db = database();
+        let conn = db.conn.lock().unwrap();
+        upsert_entry(&conn, &entry("e1", "Mail (a)", 10)).unwrap();
+        record_local_changes(&conn, "a").unwrap();
+
+        let mut report = SyncReport::default();
+        let theirs = entry("e1", "Mail (b)", 10);
+        merge_version(&conn, "a", "b", &remote(Some(&theirs), "e1", &[("b", 1)], 10), &mut report).unwrap();
+
+        // Ties go to the higher device id on both sides
+        assert_eq!(title(&conn, "e1").as_deref(), Some("Mail (b)"));
+    }
+
+    #[test]
+    fn newer_remote_edit_wins_over_local_delete() {
+        let db = database();
+        let conn = db.conn.lock().unwrap();
+        upsert_entry(&conn, &entry("e1", "Mail", 10)).unwrap();
+        record_local_changes(&conn, "a").unwrap();
+        conn.execute("DELETE FROM entries WHERE id = 'e1'", []).unwrap();
+        record_local_changes(&conn, "a").unwrap();
+        let deleted_at = load_state(&conn, RecordKind::Entry, "e1").unwrap().unwrap().updated_at;
+
+        let mut report = SyncReport::default();
+        let edited = entry("e1", "Mail (b)", deleted_at + 60);
+        let version = remote(Some(&edited), "e1", &[("a", 1), ("b", 1)], deleted_at + 60);
+        merge_version(&conn, "a", "b", &version, &mut report).unwrap();
+
+        assert_eq!(title(&conn, "e1").as_deref(), Some("Mail (b)"));
+        assert_eq!(report.conflicts, 1);
+    }
+
+    #[test]
+    fn newer_local_delete_wins_over_remote_edit() {
+        let db = database();
+        let conn = db.conn.lock().unwrap();
+        upsert_entry(&conn, &entry("e1", "Mail", 10)).unwrap();
+        record_local_changes(&conn, "a").unwrap();
+        conn.execute("DELETE FROM entries WHERE id = 'e1'", []).unwrap();
+        record_local_changes(&conn, "a").unwrap();
+
+        let mut report = SyncReport::default();
+        let edited = entry("e1", "Mail (b)", 20);
+        merge_version(&conn, "a", "b", &remote(Some(&edited), "e1", &[("a", 1), ("b", 1)], 20), &mut report).unwrap();
+
+        assert_eq!(title(&conn, "e1"), None);
+        assert_eq!(report.conflicts, 1);
+        assert!(load_state(&conn, RecordKind::Entry, "e1").unwrap().unwrap().deleted);
+    }
+
+    #[test]
+    fn remote_delete_after_the_local_version_removes_the_record() {
+        let db = database();
+        let conn = db.conn.lock().unwrap();
+        upsert_entry(&conn, &entry("e1", "Mail", 10)).unwrap();
+        record_local_changes(&conn, "a").unwrap();
+
+        let mut report = SyncReport::default();
+        merge_version(&conn, "a", "b", &remote(None, "e1", &[("a", 1), ("b", 1)], 20), &mut report).unwrap();
+
+        assert_eq!(title(&conn, "e1"), None);
+        assert_eq!(report.conflicts, 0);
+    }
+
+    #[test]
+    fn identical_content_from_two_devices_is_not_a_conflict() {
+        let db = database();
+        let conn = db.conn.lock().unwrap();
+        let same = entry("e1", "Mail", 10);
+        upsert_entry(&conn, &same).unwrap();
+        record_local_changes(&conn, "a").unwrap();
+
+        let mut report = SyncReport::default();
+        merge_version(&conn, "a", "b", &remote(Some(&same), "e1", &[("b", 1)], 10), &mut report).unwrap();
+
+        assert_eq!((report.pulled, report.conflicts), (0, 0));
+        assert_eq!(conflicts(&conn), 0);
+        let state = load_state(&conn, RecordKind::Entry, "e1").unwrap().unwrap();
+        let both = remote(None, "e1", &[("a", 1), ("b", 1)], 0).clock;
+        assert_eq!(state.clock.compare(&both), Causality::Equal);
+    }
+
+    #[test]
+    fn unknown_tombstones_are_only_remembered() {
+        let db = database();
+        let conn = db.conn.lock().unwrap();
+
+        let mut report = SyncReport::default();
+        merge_version(&conn, "a", "b", &remote(None, "e1", &[("b", 2)], 20), &mut report).unwrap();
+
+        assert_eq!(report.pulled, 0);
+        assert!(load_state(&conn, RecordKind::Entry, "e1").unwrap().unwrap().deleted);
+    }
+
+    #[test]
+    fn content_hash_is_stable() {
+        assert_eq!(content_hash(b""), "cbf29ce484222325");
+        assert_ne!(content_hash(b"a"), content_hash(b"b"));
+    }
+}
@@ -0,0 +1,76 @@
+use std::fs;
+use std::path::PathBuf;
+
+use super::transport::SyncTransport;
+
+const HEADER_FILE: &str = "sync.json";
+const LOGS_DIR: &str = "devices";
+const LOG_EXTENSION: &str = "oplog";
+
+/// Change logs exchanged through a shared folder (Syncthing, Nextcloud, NAS)
+pub struct FolderTransport {
+    dir: PathBuf,
+}
+
+impl FolderTransport {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        FolderTransport { dir: dir.into() }
+    }
+
+    fn logs_dir(&self) -> PathBuf {
+        self.dir.join(LOGS_DIR)
+    }
+}
+
+impl SyncTransport for FolderTransport {
+    fn read_logs(&self, own_device: &str) -> Result<Vec<(String, Vec<u8>)>, String> {
+        let dir = self.logs_dir();
+        if !dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut logs = Vec::new();
+        for item in fs::read_dir(&dir).map_err(|e| format!("无法读取同步文件夹: {}", e))? {
+            let path = item.map_err(|e| e.to_string())?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some(LOG_EXTENSION) {
+                continue;
+            }
+            // Skips conflict copies made by sync tools, e.g. <id>.sync-conflict-....oplog
+            let Some(device) = path.file_stem().and_then(|s| s.to_str()) else {
+                continue;
+            };
+            if device == own_device || device.contains('.') {
+                continue;
+            }
+
+            let data = fs::read(&path).map_err(|e| format!("无法读取 {}: {}", path.display(), e))?;
+            logs.push((device.to_string(), data));
+        }
+
+        Ok(logs)
+    }
+
+    fn write_log(&self, device: &str, data: &[u8]) -> Result<(), String> {
+        let dir = self.logs_dir();
+        fs::create_dir_all(&dir).map_err(|e| format!("无法创建同步文件夹: {}", e))?;
+
+        // Replace atomically so other devices never read a half-written log
+        let path = dir.join(format!("{}.{}", device, LOG_EXTENSION));
+        let partial = dir.join(format!(".{}.partial", device));
+        fs::write(&partial, data).map_err(|e| format!("无法写入同步日志: {}", e))?;
+        fs::rename(&partial, &path).map_err(|e| format!("无法写入同步日志: {}", e))
+    }
+
+    fn read_header(&self) -> Result<Option<Vec<u8>>, String> {
+        let path = self.dir.join(HEADER_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+        fs::read(&path).map(Some).map_err(|e| format!("无法读取同步文件夹: {}", e))
+    }
+
+    fn write_header(&self, data: &[u8], _replace: bool) -> Result<(), String> {
+        fs::create_dir_all(&self.dir).map_err(|e| format!("无法创建同步文件夹: {}", e))?;
+        fs::write(self.dir.join(HEADER_FILE), data).map_err(|e| format!("无法写入同步文件夹: {}", e))
+    }
+}
@@ -0,0 +1,6 @@
+pub mod clock;
+pub mod engine;
+pub mod folder;
+pub mod scheduler;
+pub mod transport;
+pub mod webdav;
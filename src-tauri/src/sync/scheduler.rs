@@ -0,0 +1,141 @@
+use rusqlite::OptionalExtension;
+use std::sync::{Mutex, MutexGuard};
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+use super::engine;
+use super::folder::FolderTransport;
+use super::webdav::{self, WebDavTransport};
+use crate::commands::settings::read_sync_settings;
+use crate::crypto::{Session, VaultKey};
+use crate::db::Database;
+use crate::models::{SyncBackend, SyncReport, SyncSettings};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Serializes scheduled and manual runs
+static RUNNING: Mutex<()> = Mutex::new(());
+
+/// Start the background thread that syncs on the configured interval
+/// A failed run, e.g. while offline, is retried on the next check.
+pub fn start(app: AppHandle) {
+    thread::spawn(move || {
+        let mut last_success = 0;
+        loop {
+            thread::sleep(CHECK_INTERVAL);
+
+            // Logs are encrypted with the vault key, which is wiped while locked
+            if !app.state::<Session>().is_unlocked() {
+                continue;
+            }
+
+            let interval = {
+                let db = app.state::<Database>();
+                let Ok(conn) = db.conn.lock() else { continue };
+                let settings = read_sync_settings(&conn);
+                if !settings.enabled {
+                    continue;
+                }
+                i64::from(settings.interval_minutes) * 60
+            };
+
+            let now = chrono::Utc::now().timestamp();
+            if now - last_success < interval {
+                continue;
+            }
+
+            match run_now(&app) {
+                Ok(_) => last_success = now,
+                Err(e) => eprintln!("Scheduled sync failed: {}", e),
+            }
+        }
+    });
+}
+
+/// Keep scheduled and manual runs from starting, e.g. while the master password changes
+/// Take it before the database lock, as runs do.
+pub fn pause() -> Result<MutexGuard<'static, ()>, String> {
+    RUNNING.lock().map_err(|e| e.to_string())
+}
+
+/// Move the configured target to the key of a new master password, before the change is saved
+/// Call while holding `pause()`; the database is not locked during transfers.
+pub fn rekey(db: &Database, old_key: &VaultKey, new_key: &VaultKey) -> Result<(), String> {
+    let settings = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        read_sync_settings(&conn)
+    };
+    if !settings.enabled {
+        return Ok(());
+    }
+
+    match settings.backend {
+        SyncBackend::Folder => engine::rekey(db, old_key, new_key, &FolderTransport::new(&settings.directory)),
+        SyncBackend::Webdav => {
+            let transport = webdav_transport(db, &settings, old_key)?;
+            engine::rekey(db, old_key, new_key, &transport)?;
+            save_webdav_etag(db, &transport)
+        }
+    }
+}
+
+/// Sync once with the configured target and notify the frontend
+pub fn run_now(app: &AppHandle) -> Result<SyncReport, String> {
+    let _guard = RUNNING.lock().map_err(|e| e.to_string())?;
+    let db = app.state::<Database>();
+    let session = app.state::<Session>();
+
+    let settings = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        read_sync_settings(&conn)
+    };
+    if !settings.enabled {
+        return Err("同步未启用".to_string());
+    }
+
+    // Logs can only be decrypted once the vault has been unlocked
+    let key = session.key().ok_or("请先解锁后再同步")?;
+
+    let report = match settings.backend {
+        SyncBackend::Folder => {
+            if settings.directory.is_empty() {
+                return Err("未设置同步文件夹".to_string());
+            }
+            engine::sync(&db, &key, &FolderTransport::new(&settings.directory))?
+        }
+        SyncBackend::Webdav => {
+            let transport = webdav_transport(&db, &settings, &key)?;
+            let report = engine::sync(&db, &key, &transport)?;
+            save_webdav_etag(&db, &transport)?;
+            report
+        }
+    };
+
+    let _ = app.emit("sync-completed", &report);
+    Ok(report)
+}
+
+/// Helper: the WebDAV target with the stored login and the last seen ETag of this device's log
+fn webdav_transport(db: &Database, settings: &SyncSettings, key: &VaultKey) -> Result<WebDavTransport, String> {
+    let (credentials, etag) = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        let credentials = webdav::load_credentials(&conn, key)?.ok_or("未设置 WebDAV 登录信息")?;
+        let etag: Option<String> = conn
+            .query_row("SELECT value FROM settings WHERE key = 'webdav_log_etag'", [], |row| row.get(0))
+            .optional()
+            .map_err(|e| e.to_string())?;
+        (credentials, etag.filter(|e| !e.is_empty()))
+    };
+    WebDavTransport::new(&settings.webdav_url, credentials, etag)
+}
+
+fn save_webdav_etag(db: &Database, transport: &WebDavTransport) -> Result<(), String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT OR REPLACE INTO settings (key, value) VALUES ('webdav_log_etag', ?1)",
+        [transport.own_etag().unwrap_or_default()],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
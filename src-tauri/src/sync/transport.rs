@@ -0,0 +1,97 @@
+use serde::{Deserialize, Serialize};
+
+use crate::crypto::{self, VaultKey};
+
+const HEADER_VERSION: u32 = 1;
+const CHECK_PLAINTEXT: &[u8] = b"one-password-sync";
+
+/// Where device change logs are exchanged
+/// Logs are opaque encrypted blobs; every device only ever writes its own log.
+pub trait SyncTransport {
+    /// Encrypted logs of all devices except `own_device`, as (device id, data)
+    fn read_logs(&self, own_device: &str) -> Result<Vec<(String, Vec<u8>)>, String>;
+
+    /// Publish the encrypted log of `device`
+    fn write_log(&self, device: &str, data: &[u8]) -> Result<(), String>;
+
+    /// Raw contents of the shared header, None before the first device joined
+    fn read_header(&self) -> Result<Option<Vec<u8>>, String>;
+
+    /// Create the header, or with `replace` overwrite the one last read
+    fn write_header(&self, data: &[u8], replace: bool) -> Result<(), String>;
+}
+
+/// Describes the key used by every device writing to the target
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SyncHeader {
+    version: u32,
+    /// Key salt shared by all devices, hex encoded
+    salt: String,
+    /// Known plaintext encrypted with the shared key, hex encoded
+    check: String,
+}
+
+/// Join a sync target, or initialize it with the current vault key
+/// Returns the key shared by all devices using the target.
+pub fn join(transport: &dyn SyncTransport, password: &str, current: &VaultKey) -> Result<VaultKey, String> {
+    if let Some(header) = read_header(transport)? {
+        let salt = crypto::salt_from_hex(&header.salt).ok_or("同步数据格式错误")?;
+        let key = crypto::derive_key(password, &salt)?;
+        if header.matches(&key) {
+            Ok(key)
+        } else {
+            Err("主密码与同步的其他设备不一致".to_string())
+        }
+    } else {
+        write_header(transport, current, false)?;
+        Ok(current.clone())
+    }
+}
+
+/// Make sure the target still uses `key`; it changes when another device changed the master password
+pub fn verify(transport: &dyn SyncTransport, key: &VaultKey) -> Result<(), String> {
+    match read_header(transport)? {
+        Some(header) if !header.matches(key) => {
+            Err("其他设备已修改主密码，请使用新的主密码重新加入同步".to_string())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Switch the target to a new key after the master password changed
+/// Other devices can't read logs under the new key until they join again with the new password.
+/// The header must have been read first, e.g. by `verify`.
+pub fn rekey(transport: &dyn SyncTransport, key: &VaultKey) -> Result<(), String> {
+    write_header(transport, key, true)
+}
+
+impl SyncHeader {
+    fn matches(&self, key: &VaultKey) -> bool {
+        crypto::from_hex(&self.check)
+            .and_then(|check| crypto::decrypt(key, &check).ok())
+            .is_some_and(|plain| plain == CHECK_PLAINTEXT)
+    }
+}
+
+fn read_header(transport: &dyn SyncTransport) -> Result<Option<SyncHeader>, String> {
+    let Some(content) = transport.read_header()? else {
+        return Ok(None);
+    };
+    let header: SyncHeader =
+        serde_json::from_slice(&content).map_err(|e| format!("同步数据格式错误: {}", e))?;
+    if header.version != HEADER_VERSION {
+        return Err(format!("不支持的同步数据版本: {}", header.version));
+    }
+    Ok(Some(header))
+}
+
+fn write_header(transport: &dyn SyncTransport, key: &VaultKey, replace: bool) -> Result<(), String> {
+    let header = SyncHeader {
+        version: HEADER_VERSION,
+        salt: crypto::to_hex(&key.salt),
+        check: crypto::to_hex(&crypto::encrypt(key, CHECK_PLAINTEXT)?),
+    };
+    let content = serde_json::to_vec_pretty(&header).map_err(|e| e.to_string())?;
+    transport.write_header(&content, replace)
+}
@@ -0,0 +1,429 @@
+use reqwest::blocking::{Client, RequestBuilder, Response};
+use reqwest::{Method, StatusCode};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use super::transport::SyncTransport;
+use crate::crypto::{self, VaultKey};
+
+const HEADER_FILE: &str = "sync.json";
+const LOGS_DIR: &str = "devices";
+const LOG_EXTENSION: &str = "oplog";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+const MAX_ATTEMPTS: u32 = 3;
+const PROPFIND_BODY: &str = r#"<?xml version="1.0" encoding="utf-8"?><d:propfind xmlns:d="DAV:"><d:prop><d:getetag/></d:prop></d:propfind>"#;
+
+/// Contents and ETag of a downloaded file
+type Download = (Vec<u8>, Option<String>);
+
+/// Login for the WebDAV server, kept encrypted with the vault key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebDavCredentials {
+    pub username: String,
+    pub password: String,
+}
+
+/// Change logs exchanged through a WebDAV server
+/// ETags guard this device's own log: a PUT only succeeds if nobody replaced it since
+/// it was last seen, and a log changed by someone else is merged like a remote one.
+pub struct WebDavTransport {
+    client: Client,
+    base_url: String,
+    credentials: WebDavCredentials,
+    /// ETag of this device's log on the server as last seen
+    own_etag: Mutex<Option<String>>,
+    /// Whether the last listing contained this device's log
+    own_exists: AtomicBool,
+    /// ETag of the header as last read, guarding its replacement
+    header_etag: Mutex<Option<String>>,
+}
+
+impl WebDavTransport {
+    pub fn new(url: &str, credentials: WebDavCredentials, own_etag: Option<String>) -> Result<Self, String> {
+        let url = url.trim();
+        if !url.starts_with("http://") && !url.starts_with("https://") {
+            return Err("WebDAV 地址必须以 http:// 或 https:// 开头".to_string());
+        }
+
+        let client = Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .map_err(|e| e.to_string())?;
+
+        Ok(WebDavTransport {
+            client,
+            base_url: format!("{}/", url.trim_end_matches('/')),
+            credentials,
+            own_exists: AtomicBool::new(own_etag.is_some()),
+            own_etag: Mutex::new(own_etag),
+            header_etag: Mutex::new(None),
+        })
+    }
+
+    /// ETag of this device's log after the last read or write
+    pub fn own_etag(&self) -> Option<String> {
+        self.own_etag.lock().ok().and_then(|etag| etag.clone())
+    }
+
+    fn set_own_etag(&self, etag: Option<String>) {
+        if let Ok(mut guard) = self.own_etag.lock() {
+            *guard = etag;
+        }
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        self.client
+            .request(method, self.url(path))
+            .basic_auth(&self.credentials.username, Some(&self.credentials.password))
+    }
+
+    /// Create a collection, ignoring "already exists"
+    fn ensure_collection(&self, path: &str) -> Result<(), String> {
+        let response = send(|| self.request(Method::from_bytes(b"MKCOL").expect("valid method"), path))?;
+        match response.status() {
+            s if s.is_success() => Ok(()),
+            StatusCode::METHOD_NOT_ALLOWED => Ok(()),
+            s => Err(status_error("无法创建 WebDAV 目录", s)),
+        }
+    }
+
+    /// (file name, ETag) of every log on the server
+    fn list_logs(&self) -> Result<Vec<(String, Option<String>)>, String> {
+        let dir = format!("{}/", LOGS_DIR);
+        let response = send(|| {
+            self.request(Method::from_bytes(b"PROPFIND").expect("valid method"), &dir)
+                .header("Depth", "1")
+                .header("Content-Type", "application/xml")
+                .body(PROPFIND_BODY)
+        })?;
+
+        match response.status() {
+            StatusCode::NOT_FOUND => return Ok(Vec::new()),
+            s if s.is_success() => {}
+            s => return Err(status_error("无法读取 WebDAV 目录", s)),
+        }
+
+        let body = response.text().map_err(|e| e.to_string())?;
+        Ok(parse_multistatus(&body)
+            .into_iter()
+            .filter_map(|(href, etag)| {
+                let name = href.trim_end_matches('/').rsplit('/').next()?.to_string();
+                Some((name, etag))
+            })
+            .collect())
+    }
+
+    fn get(&self, path: &str) -> Result<Option<Vec<u8>>, String> {
+        Ok(self.get_with_etag(path)?.map(|(data, _)| data))
+    }
+
+    fn get_with_etag(&self, path: &str) -> Result<Option<Download>, String> {
+        let response = send(|| self.request(Method::GET, path))?;
+        match response.status() {
+            StatusCode::NOT_FOUND => Ok(None),
+            s if s.is_success() => {
+                let etag = response
+                    .headers()
+                    .get("ETag")
+                    .and_then(|v| v.to_str().ok())
+                    .map(str::to_string);
+                let data = response.bytes().map_err(|e| e.to_string())?;
+                Ok(Some((data.to_vec(), etag)))
+            }
+            s => Err(status_error("无法下载同步数据", s)),
+        }
+    }
+}
+
+impl SyncTransport for WebDavTransport {
+    fn read_logs(&self, own_device: &str) -> Result<Vec<(String, Vec<u8>)>, String> {
+        let own_name = format!("{}.{}", own_device, LOG_EXTENSION);
+        let known_etag = self.own_etag();
+        let mut own_etag = None;
+        let mut own_exists = false;
+        let mut logs = Vec::new();
+
+        for (name, etag) in self.list_logs()? {
+            let Some(device) = name.strip_suffix(&format!(".{}", LOG_EXTENSION)) else {
+                continue;
+            };
+            if device.is_empty() || device.contains('.') {
+                continue;
+            }
+
+            // Our own log is only read back when someone else replaced it
+            if name == own_name {
+                own_exists = true;
+                own_etag = etag.clone();
+                if etag.is_some() && etag == known_etag {
+                    continue;
+                }
+            }
+
+            if let Some(data) = self.get(&format!("{}/{}", LOGS_DIR, name))? {
+                logs.push((device.to_string(), data));
+            }
+        }
+
+        self.set_own_etag(own_etag);
+        self.own_exists.store(own_exists, Ordering::SeqCst);
+        Ok(logs)
+    }
+
+    fn write_log(&self, device: &str, data: &[u8]) -> Result<(), String> {
+        self.ensure_collection("")?;
+        self.ensure_collection(&format!("{}/", LOGS_DIR))?;
+
+        let path = format!("{}/{}.{}", LOGS_DIR, device, LOG_EXTENSION);
+        let expected = self.own_etag();
+        let exists = self.own_exists.load(Ordering::SeqCst);
+        let response = send(|| {
+            let request = self.request(Method::PUT, &path).body(data.to_vec());
+            match expected {
+                Some(ref etag) => request.header("If-Match", etag),
+                // Servers without ETags only get the create-only guard
+                None if !exists => request.header("If-None-Match", "*"),
+                None => request,
+            }
+        })?;
+
+        match response.status() {
+            StatusCode::PRECONDITION_FAILED => {
+                // Read it back and merge on the next run
+                self.set_own_etag(None);
+                Err("同步日志已在服务器上被修改，将在下次同步时合并".to_string())
+            }
+            s if s.is_success() => {
+                let etag = response
+                    .headers()
+                    .get("ETag")
+                    .and_then(|v| v.to_str().ok())
+                    .map(str::to_string);
+                self.set_own_etag(etag);
+                self.own_exists.store(true, Ordering::SeqCst);
+                Ok(())
+            }
+            s => Err(status_error("无法上传同步日志", s)),
+        }
+    }
+
+    fn read_header(&self) -> Result<Option<Vec<u8>>, String> {
+        let header = self.get_with_etag(HEADER_FILE)?;
+        if let Ok(mut guard) = self.header_etag.lock() {
+            *guard = header.as_ref().and_then(|(_, etag)| etag.clone());
+        }
+        Ok(header.map(|(data, _)| data))
+    }
+
+    fn write_header(&self, data: &[u8], replace: bool) -> Result<(), String> {
+        self.ensure_collection("")?;
+        let expected = self.header_etag.lock().ok().and_then(|etag| etag.clone());
+        let response = send(|| {
+            let request = self.request(Method::PUT, HEADER_FILE).body(data.to_vec());
+            match (replace, &expected) {
+                (false, _) => request.header("If-None-Match", "*"),
+                (true, Some(etag)) => request.header("If-Match", etag),
+                // Servers without ETags replace it unguarded
+                (true, None) => request,
+            }
+        })?;
+
+        match response.status() {
+            s if s.is_success() => Ok(()),
+            StatusCode::PRECONDITION_FAILED if replace => Err("其他设备刚刚修改了同步数据，请重试".to_string()),
+            StatusCode::PRECONDITION_FAILED => Err("其他设备刚刚初始化了同步，请重试".to_string()),
+            s => Err(status_error("无法上传同步数据", s)),
+        }
+    }
+}
+
+/// Store the WebDAV login encrypted inside the vault
+pub fn save_credentials(conn: &Connection, key: &VaultKey, credentials: &WebDavCredentials) -> Result<(), String> {
+    let plaintext = serde_json::to_vec(credentials).map_err(|e| e.to_string())?;
+    let encrypted = crypto::to_hex(&crypto::encrypt(key, &plaintext)?);
+    conn.execute(
+        "INSERT OR REPLACE INTO settings (key, value) VALUES ('webdav_credentials', ?1)",
+        [&encrypted],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+pub fn load_credentials(conn: &Connection, key: &VaultKey) -> Result<Option<WebDavCredentials>, String> {
+    let stored: Option<String> = conn
+        .query_row(
+            "SELECT value FROM settings WHERE key = 'webdav_credentials'",
+            [],
+            |row| row.get(0),
+        )
+        .ok();
+    let Some(stored) = stored.filter(|s| !s.is_empty()) else {
+        return Ok(None);
+    };
+
+    let data = crypto::from_hex(&stored).ok_or("WebDAV 登录信息已损坏")?;
+    let plaintext = crypto::decrypt(key, &data)?;
+    serde_json::from_slice(&plaintext)
+        .map(Some)
+        .map_err(|e| format!("WebDAV 登录信息已损坏: {}", e))
+}
+
+/// Re-encrypt the stored login after the vault key changed
+pub fn reencrypt_credentials(conn: &Connection, old_key: &VaultKey, new_key: &VaultKey) -> Result<(), String> {
+    if let Some(credentials) = load_credentials(conn, old_key)? {
+        save_credentials(conn, new_key, &credentials)?;
+    }
+    Ok(())
+}
+
+/// Helper: send a request, retrying with backoff while the server is unreachable
+fn send(build: impl Fn() -> RequestBuilder) -> Result<Response, String> {
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        let retry_delay = Duration::from_secs(1 << attempt);
+
+        match build().send() {
+            Ok(response) => {
+                let status = response.status();
+                let transient = status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS;
+                if !transient || attempt >= MAX_ATTEMPTS {
+                    return match status {
+                        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
+                            Err("WebDAV 用户名或密码错误".to_string())
+                        }
+                        _ => Ok(response),
+                    };
+                }
+            }
+            Err(e) if attempt >= MAX_ATTEMPTS => {
+                return Err(format!("无法连接 WebDAV 服务器: {}", e));
+            }
+            Err(_) => {}
+        }
+
+        thread::sleep(retry_delay);
+    }
+}
+
+fn status_error(context: &str, status: StatusCode) -> String {
+    format!("{}: HTTP {}", context, status.as_u16())
+}
+
+/// Helper: (href, ETag) pairs of a PROPFIND multistatus body, whatever the namespace prefix
+fn parse_multistatus(body: &str) -> Vec<(String, Option<String>)> {
+    element_texts(body, "response")
+        .into_iter()
+        .filter_map(|response| {
+            let href = element_texts(response, "href").into_iter().next()?;
+            let etag = element_texts(response, "getetag").into_iter().next();
+            Some((unescape(href.trim()), etag.map(|e| unescape(e.trim()))))
+        })
+        .collect()
+}
+
+/// Helper: inner text of every element with the given local name
+fn element_texts<'a>(xml: &'a str, local_name: &str) -> Vec<&'a str> {
+    let mut texts = Vec::new();
+    let mut rest = xml;
+
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start + 1..];
+        let Some(end) = rest.find('>') else { break };
+        let tag = &rest[..end];
+        rest = &rest[end + 1..];
+
+        if tag.starts_with('/') || tag.ends_with('/') {
+            continue;
+        }
+        let name = tag.split_whitespace().next().unwrap_or_default();
+        if name.rsplit(':').next() != Some(local_name) {
+            continue;
+        }
+
+        let close = format!("</{}>", name);
+        if let Some(close_at) = rest.find(&close) {
+            texts.push(&rest[..close_at]);
+            rest = &rest[close_at + close.len()..];
+        }
+    }
+
+    texts
+}
+
+fn unescape(text: &str) -> String {
+    text.replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_prefixed_multistatus() {
+        let body = r#"<?xml version="1.0" encoding="utf-8"?>
+<d:multistatus xmlns:d="DAV:">
+  <d:response>
+    <d:href>/dav/one-password/logs/</d:href>
+    <d:propstat><d:prop><d:getetag/></d:prop></d:propstat>
+  </d:response>
+  <d:response>
+    <d:href>/dav/one-password/logs/a1.log</d:href>
+    <d:propstat><d:prop><d:getetag>&quot;abc&quot;</d:getetag></d:prop></d:propstat>
+  </d:response>
+</d:multistatus>"#;
+
+        assert_eq!(
+            parse_multistatus(body),
+            vec![
+                ("/dav/one-password/logs/".to_string(), None),
+                ("/dav/one-password/logs/a1.log".to_string(), Some("\"abc\"".to_string())),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_other_prefixes_and_attributes() {
+        let body = r#"<D:multistatus xmlns:D="DAV:"><D:response xmlns:lp1="DAV:">
+<D:href> /logs/b2.log </D:href>
+<D:propstat><D:prop><lp1:getetag>W/"1-2"</lp1:getetag></D:prop></D:propstat>
+</D:response></D:multistatus>"#;
+
+        assert_eq!(
+            parse_multistatus(body),
+            vec![("/logs/b2.log".to_string(), Some("W/\"1-2\"".to_string()))]
+        );
+    }
+
+    #[test]
+    fn parses_unprefixed_multistatus() {
+        let body = r#"<multistatus xmlns="DAV:"><response><href>/logs/c3.log</href></response></multistatus>"#;
+        assert_eq!(parse_multistatus(body), vec![("/logs/c3.log".to_string(), None)]);
+    }
+
+    #[test]
+    fn skips_responses_without_href() {
+        let body = r#"<d:multistatus><d:response><d:status>HTTP/1.1 200 OK</d:status></d:response></d:multistatus>"#;
+        assert!(parse_multistatus(body).is_empty());
+        assert!(parse_multistatus("").is_empty());
+        assert!(parse_multistatus("<d:response><d:href>/x").is_empty());
+    }
+
+    #[test]
+    fn unescapes_ampersand_last() {
+        assert_eq!(unescape("a&amp;lt;b &lt;c&gt; &apos;&quot;"), "a&lt;b <c> '\"");
+    }
+}
@@ -0,0 +1,408 @@
+use std::sync::mpsc::RecvTimeoutError;
+use std::thread;
+use std::time::Duration;
+use tauri::{
+    image::Image,
+    menu::{CheckMenuItem, IsMenuItem, Menu, MenuItem, PredefinedMenuItem, Submenu},
+    tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent},
+    AppHandle, Emitter, Manager, Wry,
+};
+use crate::commands;
+use crate::commands::entries::{entry_from_row, ENTRY_COLUMNS};
+use crate::crypto::Session;
+use crate::db::{self, Database};
+use crate::health::{self, HealthState};
+use crate::models::{Entry, EntryFilter, EntrySort};
+use crate::totp;
+
+const TRAY_ID: &str = "main";
+const FAVORITES_IN_TRAY: usize = 20;
+const RECENT_IN_TRAY: usize = 5;
+/// Quiet time after an entry change before the menu is rebuilt, so bulk writes rebuild it once
+const REFRESH_DEBOUNCE: Duration = Duration::from_millis(300);
+/// How often to look for entries written by another process, e.g. the CLI
+const OUTSIDE_WRITES_INTERVAL: Duration = Duration::from_secs(5);
+const ALERT_COLOR: [u8; 4] = [0xdc, 0x26, 0x26, 0xff];
+
+/// What the icon, tooltip and menu show
+struct TrayState {
+    unlocked: bool,
+    /// Entries the health report flags; only counted while unlocked
+    alerts: usize,
+}
+
+impl TrayState {
+    fn current(app: &AppHandle) -> Self {
+        let unlocked = app.state::<Session>().is_unlocked();
+        let alerts = if unlocked {
+            let db = app.state::<Database>();
+            let report = db
+                .conn
+                .lock()
+                .map_err(|e| e.to_string())
+                .and_then(|conn| health::report(&conn, &app.state::<HealthState>()));
+            match report {
+                Ok(report) => report.alert_count(),
+                Err(e) => {
+                    eprintln!("Failed to check vault health for the tray: {}", e);
+                    0
+                }
+            }
+        } else {
+            0
+        };
+        TrayState { unlocked, alerts }
+    }
+
+    fn tooltip(&self) -> String {
+        match (self.unlocked, self.alerts) {
+            (false, _) => "One-Password（已锁定）".to_string(),
+            (true, 0) => "One-Password（已解锁）".to_string(),
+            (true, alerts) => format!("One-Password（已解锁，{} 个条目需要处理）", alerts),
+        }
+    }
+}
+
+/// Values the entry submenus copy
+#[derive(Debug, Clone, Copy)]
+enum CopyField {
+    Username,
+    Password,
+    Totp,
+}
+
+impl CopyField {
+    const ALL: [CopyField; 3] = [CopyField::Username, CopyField::Password, CopyField::Totp];
+
+    fn as_str(self) -> &'static str {
+        match self {
+            CopyField::Username => "username",
+            CopyField::Password => "password",
+            CopyField::Totp => "totp",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        CopyField::ALL.into_iter().find(|field| field.as_str() == value)
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            CopyField::Username => "复制用户名",
+            CopyField::Password => "复制密码",
+            CopyField::Totp => "复制一次性密码",
+        }
+    }
+
+    fn available(self, entry: &Entry) -> bool {
+        match self {
+            CopyField::Username => !entry.username.is_empty(),
+            CopyField::Password => !entry.password.is_empty(),
+            CopyField::Totp => totp::entry_secret(entry).is_some(),
+        }
+    }
+}
+
+pub fn create(app: &tauri::App) -> Result<(), Box<dyn std::error::Error>> {
+    let state = TrayState::current(app.handle());
+    let menu = build_menu(app.handle(), &state)?;
+
+    // Create the tray icon
+    let _tray = TrayIconBuilder::with_id(TRAY_ID)
+        .icon(state_icon(&state))
+        .menu(&menu)
+        .tooltip(state.tooltip())
+        .on_menu_event(|app, event| handle_menu_event(app, event.id.as_ref()))
+        .on_tray_icon_event(|tray, event| {
+            // Handle tray icon click - show window on left click
+            if let TrayIconEvent::Click {
+                button: MouseButton::Left,
+                button_state: MouseButtonState::Up,
+                ..
+            } = event
+            {
+                show_main_window(tray.app_handle());
+            }
+        })
+        .build(app)?;
+
+    refresh_on_entry_changes(app.handle().clone());
+    Ok(())
+}
+
+fn handle_menu_event(app: &AppHandle, id: &str) {
+    match id {
+        "show" => show_main_window(app),
+        "toggle-lock" => {
+            if app.state::<Session>().is_unlocked() {
+                crate::commands::auth::lock_app(app);
+            } else {
+                // The main window's lock screen is where the master password is entered
+                show_main_window(app);
+            }
+        }
+        "health" => {
+            show_main_window(app);
+            let _ = app.emit_to("main", "open-health", ());
+        }
+        "quit" => {
+            app.exit(0);
+        }
+        id => {
+            let parts: Vec<&str> = id.splitn(4, ':').collect();
+            match parts.as_slice() {
+                // Entry ids are UUIDs, so they come last and never contain the separator
+                ["open", _section, entry_id] => {
+                    show_main_window(app);
+                    let _ = app.emit_to("main", "open-entry", *entry_id);
+                }
+                ["copy", field, _section, entry_id] => {
+                    let Some(field) = CopyField::parse(field) else {
+                        return;
+                    };
+                    if let Err(e) = copy_field(app, field, entry_id) {
+                        eprintln!("Tray copy failed: {}", e);
+                    }
+                }
+                _ => {
+                    if let Some(name) = id.strip_prefix("vault:") {
+                        if let Err(e) = commands::vaults::switch_to(app, name) {
+                            eprintln!("Failed to switch vault: {}", e);
+                            // Undo the check mark toggled by the click
+                            refresh(app);
+                        }
+                        show_main_window(app);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn show_main_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
+
+/// Copy through the backend clipboard so it's cleared after the configured delay
+fn copy_field(app: &AppHandle, field: CopyField, id: &str) -> Result<(), String> {
+    let session = app.state::<Session>();
+    if !session.is_unlocked() {
+        return Err("密码库已锁定".to_string());
+    }
+    session.touch();
+
+    let value = {
+        let db = app.state::<Database>();
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        let entry = conn
+            .query_row(
+                &format!("SELECT {} FROM entries WHERE id = ?1", ENTRY_COLUMNS),
+                [id],
+                entry_from_row,
+            )
+            .map_err(|_| "条目不存在".to_string())?;
+        let value = match field {
+            CopyField::Username => entry.username,
+            CopyField::Password => entry.password,
+            CopyField::Totp => totp::entry_code(&entry)?,
+        };
+        if !matches!(field, CopyField::Username) {
+            commands::entries::record_use(&conn, id)?;
+        }
+        value
+    };
+    crate::clipboard::copy_secret(app, &value)
+}
+
+/// App icon, greyed out while locked and with a red dot while entries need attention
+fn state_icon(state: &TrayState) -> Image<'static> {
+    let base = Image::from_bytes(include_bytes!("../../icons/32x32.png")).expect("Failed to load tray icon");
+    let (width, height) = (base.width(), base.height());
+    let mut rgba = base.rgba().to_vec();
+
+    if !state.unlocked {
+        for pixel in rgba.chunks_exact_mut(4) {
+            let grey = (pixel[0] as u32 * 30 + pixel[1] as u32 * 59 + pixel[2] as u32 * 11) / 100;
+            pixel[..3].fill(grey as u8);
+            pixel[3] = (pixel[3] as u32 * 3 / 4) as u8;
+        }
+    }
+
+    if state.alerts > 0 {
+        // Dot in the top right corner, a third of the icon across
+        let radius = width.min(height) as f32 / 6.0;
+        let (center_x, center_y) = (width as f32 - radius, radius);
+        for y in 0..height {
+            for x in 0..width {
+                let (dx, dy) = (x as f32 + 0.5 - center_x, y as f32 + 0.5 - center_y);
+                if dx * dx + dy * dy <= radius * radius {
+                    let i = ((y * width + x) * 4) as usize;
+                    rgba[i..i + 4].copy_from_slice(&ALERT_COLOR);
+                }
+            }
+        }
+    }
+
+    Image::new_owned(rgba, width, height)
+}
+
+fn build_menu(app: &AppHandle, state: &TrayState) -> tauri::Result<Menu<Wry>> {
+    // Create tray menu items
+    let show_item = MenuItem::with_id(app, "show", "显示主窗口", true, None::<&str>)?;
+    let lock_label = if state.unlocked { "锁定" } else { "解锁..." };
+    let lock_item = MenuItem::with_id(app, "toggle-lock", lock_label, true, None::<&str>)?;
+    let separator = PredefinedMenuItem::separator(app)?;
+    let quit_item = MenuItem::with_id(app, "quit", "退出", true, None::<&str>)?;
+
+    // Entry titles stay out of the menu while the vault is locked
+    let (favorites, recent) = if state.unlocked {
+        match load_entries(app) {
+            Ok((favorites, recent)) => (Some(favorites), Some(recent)),
+            Err(e) => {
+                eprintln!("Failed to read entries for the tray: {}", e);
+                (None, None)
+            }
+        }
+    } else {
+        (None, None)
+    };
+    let favorites_menu = entries_menu(app, "favorites", "收藏", favorites.as_deref())?;
+    let recent_menu = entries_menu(app, "recent", "最近使用", recent.as_deref())?;
+
+    // Vault switcher, the open vault is checked
+    let current = app.state::<Database>().path();
+    let vault_items = db::vaults::VaultRegistry::load()
+        .map(|registry| registry.vaults)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|vault| {
+            CheckMenuItem::with_id(
+                app,
+                format!("vault:{}", vault.name),
+                &vault.name,
+                true,
+                vault.path == current,
+                None::<&str>,
+            )
+        })
+        .collect::<tauri::Result<Vec<_>>>()?;
+    let vault_refs: Vec<&dyn IsMenuItem<Wry>> = vault_items
+        .iter()
+        .map(|item| item as &dyn IsMenuItem<Wry>)
+        .collect();
+    let vault_menu = Submenu::with_id_and_items(app, "vaults", "切换保险库", true, &vault_refs)?;
+
+    // Only there while the health report flags entries
+    let health_item = (state.alerts > 0)
+        .then(|| {
+            let label = format!("⚠ {} 个条目已泄露或过期", state.alerts);
+            MenuItem::with_id(app, "health", label, true, None::<&str>)
+        })
+        .transpose()?;
+    let entries_separator = PredefinedMenuItem::separator(app)?;
+
+    // Create the menu
+    let mut items: Vec<&dyn IsMenuItem<Wry>> = vec![&show_item, &lock_item];
+    if let Some(item) = &health_item {
+        items.push(item);
+    }
+    items.extend([
+        &entries_separator as &dyn IsMenuItem<Wry>,
+        &favorites_menu,
+        &recent_menu,
+        &vault_menu,
+        &separator,
+        &quit_item,
+    ]);
+    Menu::with_items(app, &items)
+}
+
+/// Favorites by title and the most recently used entries
+fn load_entries(app: &AppHandle) -> Result<(Vec<Entry>, Vec<Entry>), String> {
+    let db = app.state::<Database>();
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let filter = EntryFilter {
+        favorites_only: Some(true),
+        sort: EntrySort::Title,
+        ..Default::default()
+    };
+    let mut favorites = commands::entries::query_entries(&conn, &filter)?;
+    favorites.truncate(FAVORITES_IN_TRAY);
+    let recent = commands::entries::query_recent_entries(&conn, RECENT_IN_TRAY)?;
+    Ok((favorites, recent))
+}
+
+/// Submenu with one copy submenu per entry; disabled when `entries` is `None` (locked) or empty
+fn entries_menu(app: &AppHandle, section: &str, title: &str, entries: Option<&[Entry]>) -> tauri::Result<Submenu<Wry>> {
+    let entries = entries.unwrap_or_default();
+    let submenus = entries
+        .iter()
+        .map(|entry| entry_menu(app, section, entry))
+        .collect::<tauri::Result<Vec<_>>>()?;
+    let refs: Vec<&dyn IsMenuItem<Wry>> = submenus.iter().map(|item| item as &dyn IsMenuItem<Wry>).collect();
+    Submenu::with_id_and_items(app, section, title, !entries.is_empty(), &refs)
+}
+
+fn entry_menu(app: &AppHandle, section: &str, entry: &Entry) -> tauri::Result<Submenu<Wry>> {
+    let mut items = Vec::new();
+    for field in CopyField::ALL {
+        items.push(MenuItem::with_id(
+            app,
+            format!("copy:{}:{}:{}", field.as_str(), section, entry.id),
+            field.label(),
+            field.available(entry),
+            None::<&str>,
+        )?);
+    }
+    let open_item = MenuItem::with_id(app, format!("open:{}:{}", section, entry.id), "在主窗口中打开", true, None::<&str>)?;
+    let separator = PredefinedMenuItem::separator(app)?;
+
+    let mut refs: Vec<&dyn IsMenuItem<Wry>> = items.iter().map(|item| item as &dyn IsMenuItem<Wry>).collect();
+    refs.push(&separator);
+    refs.push(&open_item);
+    Submenu::with_id_and_items(app, format!("{}:{}", section, entry.id), &entry.title, true, &refs)
+}
+
+/// Bring the icon, tooltip and menu up to date with the lock state, entries and health report
+pub fn refresh(app: &AppHandle) {
+    let Some(tray) = app.tray_by_id(TRAY_ID) else {
+        return;
+    };
+    let state = TrayState::current(app);
+    let _ = tray.set_icon(Some(state_icon(&state)));
+    let _ = tray.set_tooltip(Some(state.tooltip()));
+    match build_menu(app, &state) {
+        Ok(menu) => {
+            let _ = tray.set_menu(Some(menu));
+        }
+        Err(e) => eprintln!("Failed to rebuild tray menu: {}", e),
+    }
+}
+
+/// Keep the submenus and health alert current as entries are written from anywhere
+fn refresh_on_entry_changes(app: AppHandle) {
+    let changes = app.state::<Database>().subscribe_entry_changes();
+    let poll_app = app.clone();
+    thread::spawn(move || {
+        while changes.recv().is_ok() {
+            loop {
+                match changes.recv_timeout(REFRESH_DEBOUNCE) {
+                    Ok(()) => continue,
+                    Err(RecvTimeoutError::Timeout) => break,
+                    Err(RecvTimeoutError::Disconnected) => return,
+                }
+            }
+            refresh(&app);
+        }
+    });
+
+    thread::spawn(move || loop {
+        thread::sleep(OUTSIDE_WRITES_INTERVAL);
+        let db = poll_app.state::<Database>();
+        let Ok(conn) = db.conn.lock() else { continue };
+        db.check_outside_writes(&conn);
+    });
+}
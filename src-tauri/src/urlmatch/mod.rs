@@ -0,0 +1,261 @@
+mod psl;
+
+use regex::{Regex, RegexBuilder};
+use url::{Host, Url};
+
+use crate::models::{Entry, EntryMatch, UrlMatch};
+
+const ANDROID_SCHEME: &str = "androidapp://";
+/// Keeps user supplied patterns from compiling into huge automatons
+const REGEX_SIZE_LIMIT: usize = 1 << 20;
+
+const SCORE_EXACT: u32 = 100;
+const SCORE_STARTS_WITH: u32 = 80;
+const SCORE_REGEX: u32 = 70;
+const SCORE_SAME_HOST: u32 = 60;
+const SCORE_SAME_HOST_OTHER_PORT: u32 = 55;
+const SCORE_BASE_DOMAIN: u32 = 40;
+
+/// What credentials are requested for
+#[derive(Debug, Clone)]
+pub enum Target {
+    Web(Url),
+    /// Android package name
+    App(String),
+}
+
+impl Target {
+    /// Parse a page URL or `androidapp://<package>`; a missing scheme is read as https
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim();
+        if let Some(package) = text
+            .get(..ANDROID_SCHEME.len())
+            .filter(|scheme| scheme.eq_ignore_ascii_case(ANDROID_SCHEME))
+            .map(|_| &text[ANDROID_SCHEME.len()..])
+        {
+            let package = package.trim_end_matches('/').to_lowercase();
+            return (!package.is_empty()).then_some(Target::App(package));
+        }
+        parse_url(text).map(Target::Web)
+    }
+
+    /// Normalized text form that regex rules are matched against
+    fn as_text(&self) -> String {
+        match self {
+            Target::Web(url) => normalize(url),
+            Target::App(package) => format!("{}{}", ANDROID_SCHEME, package),
+        }
+    }
+}
+
+/// Parse a free-text URL; a missing scheme is read as https
+pub fn parse_url(text: &str) -> Option<Url> {
+    let text = text.trim();
+    if text.is_empty() {
+        return None;
+    }
+
+    let url = match Url::parse(text) {
+        Ok(url) if url.has_host() => url,
+        _ => Url::parse(&format!("https://{}", text)).ok()?,
+    };
+    url.host()?;
+    Some(url)
+}
+
+/// URL without fragment or trailing slash, with the host lowercased and default ports dropped
+pub fn normalize(url: &Url) -> String {
+    let mut url = url.clone();
+    url.set_fragment(None);
+    if let Some(host) = host(&url) {
+        let _ = url.set_host(Some(&host));
+    }
+    url.as_str().trim_end_matches('/').to_string()
+}
+
+/// Lowercase host without a trailing dot
+pub fn host(url: &Url) -> Option<String> {
+    url.host_str().map(|h| h.trim_end_matches('.').to_lowercase())
+}
+
+/// The part of a host a user can register, by the public suffix list
+/// e.g. `example.co.uk` for `login.example.co.uk`; IP addresses and single-label hosts
+/// such as `localhost` are returned as they are.
+pub fn registrable_domain(url: &Url) -> Option<String> {
+    let host = host(url)?;
+    if !matches!(url.host(), Some(Host::Domain(_))) {
+        return Some(host);
+    }
+    Some(psl::registrable_domain(&host).unwrap_or(host))
+}
+
+pub fn compile_regex(pattern: &str) -> Result<Regex, regex::Error> {
+    RegexBuilder::new(pattern)
+        .case_insensitive(true)
+        .size_limit(REGEX_SIZE_LIMIT)
+        .build()
+}
+
+/// How well a saved URL matches the target under the given mode, None for no match
+pub fn score(saved: &str, mode: UrlMatch, target: &Target) -> Option<u32> {
+    if saved.trim().is_empty() {
+        return None;
+    }
+
+    match mode {
+        UrlMatch::Never => return None,
+        UrlMatch::Regex => {
+            let regex = compile_regex(saved).ok()?;
+            return regex.is_match(&target.as_text()).then_some(SCORE_REGEX);
+        }
+        _ => {}
+    }
+
+    let (saved, page) = match (Target::parse(saved)?, target) {
+        // Apps are identified by their package name only
+        (Target::App(saved), Target::App(package)) => return (&saved == package).then_some(SCORE_EXACT),
+        (Target::Web(saved), Target::Web(page)) => (saved, page),
+        _ => return None,
+    };
+
+    // Never offer credentials saved for https to a plain http page
+    if saved.scheme() == "https" && page.scheme() != "https" {
+        return None;
+    }
+
+    let same_host = host(&saved) == host(page);
+    let same_port = saved.port_or_known_default() == page.port_or_known_default();
+
+    match mode {
+        UrlMatch::Exact => (normalize(&saved) == normalize(page)).then_some(SCORE_EXACT),
+        UrlMatch::StartsWith => {
+            (saved.scheme() == page.scheme() && same_host && same_port && path_starts_with(&saved, page))
+                .then_some(SCORE_STARTS_WITH)
+        }
+        UrlMatch::Host => (same_host && same_port).then_some(SCORE_SAME_HOST),
+        UrlMatch::BaseDomain => {
+            if same_host && normalize(&saved) == normalize(page) {
+                Some(SCORE_EXACT)
+            } else if same_host && same_port {
+                Some(SCORE_SAME_HOST)
+            } else if same_host {
+                Some(SCORE_SAME_HOST_OTHER_PORT)
+            } else if registrable_domain(&saved) == registrable_domain(page) {
+                Some(SCORE_BASE_DOMAIN)
+            } else {
+                None
+            }
+        }
+        UrlMatch::Regex | UrlMatch::Never => None,
+    }
+}
+
+/// Helper: whether the page lies under the saved path, continuing it only at a `/`
+/// A saved query has to lead the page's query, up to a `&`.
+fn path_starts_with(saved: &Url, page: &Url) -> bool {
+    let saved_path = saved.path().trim_end_matches('/');
+    let Some(rest) = page.path().strip_prefix(saved_path) else {
+        return false;
+    };
+    match saved.query().filter(|q| !q.is_empty()) {
+        None => rest.is_empty() || rest.starts_with('/'),
+        Some(query) => {
+            (rest.is_empty() || rest == "/")
+                && page
+                    .query()
+                    .and_then(|q| q.strip_prefix(query))
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with('&'))
+        }
+    }
+}
+
+/// Best score over all URLs of an entry, with the URL that produced it
+pub fn best_match(entry: &Entry, target: &Target) -> Option<(u32, String)> {
+    std::iter::once((entry.url.as_str(), entry.url_match))
+        .chain(
+            entry
+                .extra_urls
+                .iter()
+                .map(|u| (u.url.as_str(), u.match_mode.unwrap_or(entry.url_match))),
+        )
+        .filter_map(|(url, mode)| score(url, mode, target).map(|s| (s, url.to_string())))
+        .max_by_key(|(score, _)| *score)
+}
+
+/// Entries matching a page URL or app id, best matches first
+/// Ties are ordered favorites first, then most recently updated.
+pub fn rank_entries(entries: Vec<Entry>, url: &str) -> Vec<EntryMatch> {
+    let Some(target) = Target::parse(url) else {
+        return Vec::new();
+    };
+
+    let mut matches: Vec<EntryMatch> = entries
+        .into_iter()
+        .filter_map(|entry| {
+            let (score, matched_url) = best_match(&entry, &target)?;
+            Some(EntryMatch { entry, score, matched_url })
+        })
+        .collect();
+
+    matches.sort_by(|a, b| {
+        b.score
+            .cmp(&a.score)
+            .then(b.entry.is_favorite.cmp(&a.entry.is_favorite))
+            .then(b.entry.updated_at.cmp(&a.entry.updated_at))
+    });
+    matches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn web(url: &str) -> Target {
+        Target::parse(url).unwrap()
+    }
+
+    fn domain(url: &str) -> Option<String> {
+        registrable_domain(&parse_url(url).unwrap())
+    }
+
+    #[test]
+    fn starts_with_stays_on_the_saved_host() {
+        let saved = "https://bank.com";
+        assert_eq!(score(saved, UrlMatch::StartsWith, &web("https://bank.com/login")), Some(SCORE_STARTS_WITH));
+        assert_eq!(score(saved, UrlMatch::StartsWith, &web("https://BANK.com")), Some(SCORE_STARTS_WITH));
+        assert_eq!(score(saved, UrlMatch::StartsWith, &web("https://bank.com.evil.io/login")), None);
+        assert_eq!(score(saved, UrlMatch::StartsWith, &web("https://bank.community")), None);
+        assert_eq!(score(saved, UrlMatch::StartsWith, &web("https://bank.com:8443/")), None);
+    }
+
+    #[test]
+    fn starts_with_continues_the_path_at_a_slash() {
+        let saved = "https://bank.com/app/";
+        assert_eq!(score(saved, UrlMatch::StartsWith, &web("https://bank.com/app")), Some(SCORE_STARTS_WITH));
+        assert_eq!(score(saved, UrlMatch::StartsWith, &web("https://bank.com/app/login?next=1")), Some(SCORE_STARTS_WITH));
+        assert_eq!(score(saved, UrlMatch::StartsWith, &web("https://bank.com/application")), None);
+
+        let saved = "https://bank.com/login?site=a";
+        assert_eq!(score(saved, UrlMatch::StartsWith, &web("https://bank.com/login?site=a&lang=en")), Some(SCORE_STARTS_WITH));
+        assert_eq!(score(saved, UrlMatch::StartsWith, &web("https://bank.com/login?site=ab")), None);
+    }
+
+    #[test]
+    fn registrable_domain_follows_the_suffix_list() {
+        assert_eq!(domain("https://login.example.co.uk").as_deref(), Some("example.co.uk"));
+        assert_eq!(domain("https://co.uk").as_deref(), Some("co.uk"));
+        // Wildcard rule *.ck makes every second-level name a public suffix
+        assert_eq!(domain("https://a.shop.ck").as_deref(), Some("a.shop.ck"));
+        // Exception rule !www.ck
+        assert_eq!(domain("https://login.www.ck").as_deref(), Some("www.ck"));
+        assert_eq!(domain("http://localhost:8080").as_deref(), Some("localhost"));
+    }
+
+    #[test]
+    fn base_domain_does_not_cross_public_suffixes() {
+        let saved = "https://login.example.co.uk";
+        assert_eq!(score(saved, UrlMatch::BaseDomain, &web("https://www.example.co.uk")), Some(SCORE_BASE_DOMAIN));
+        assert_eq!(score(saved, UrlMatch::BaseDomain, &web("https://other.co.uk")), None);
+        assert_eq!(score("https://a.shop.ck", UrlMatch::BaseDomain, &web("https://b.shop.ck")), None);
+    }
+}
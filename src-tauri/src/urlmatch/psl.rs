@@ -0,0 +1,85 @@
+use std::collections::HashSet;
+use std::sync::OnceLock;
+
+/// Bundled copy of https://publicsuffix.org/list/public_suffix_list.dat, so matching works offline
+const LIST: &str = include_str!("public_suffix_list.dat");
+
+/// Parsed rules, all in ASCII (punycode) form like the hosts they are compared with
+struct SuffixList {
+    rules: HashSet<String>,
+    /// `*.ck` stored as `ck`
+    wildcards: HashSet<String>,
+    /// `!www.ck` stored as `www.ck`
+    exceptions: HashSet<String>,
+}
+
+fn list() -> &'static SuffixList {
+    static LIST_CELL: OnceLock<SuffixList> = OnceLock::new();
+    LIST_CELL.get_or_init(|| {
+        let mut list = SuffixList {
+            rules: HashSet::new(),
+            wildcards: HashSet::new(),
+            exceptions: HashSet::new(),
+        };
+
+        for line in LIST.lines() {
+            let Some(rule) = line.split_whitespace().next() else { continue };
+            if rule.starts_with("//") {
+                continue;
+            }
+
+            if let Some(rule) = rule.strip_prefix('!') {
+                list.exceptions.extend(to_ascii(rule));
+            } else if let Some(rule) = rule.strip_prefix("*.") {
+                list.wildcards.extend(to_ascii(rule));
+            } else {
+                list.rules.extend(to_ascii(rule));
+            }
+        }
+
+        list
+    })
+}
+
+/// Registrable domain of an ASCII host: the public suffix plus one label
+/// None when the host is itself a public suffix, e.g. `co.uk`.
+pub fn registrable_domain(host: &str) -> Option<String> {
+    let labels: Vec<&str> = host.split('.').collect();
+    let suffix_len = public_suffix_len(&labels);
+    if labels.len() <= suffix_len {
+        return None;
+    }
+    Some(labels[labels.len() - suffix_len - 1..].join("."))
+}
+
+/// Number of labels in the longest matching public suffix; unknown TLDs count as one label
+fn public_suffix_len(labels: &[&str]) -> usize {
+    let list = list();
+    let n = labels.len();
+
+    for i in 0..n {
+        let candidate = labels[i..].join(".");
+        if list.exceptions.contains(&candidate) {
+            return n - i - 1;
+        }
+        if list.rules.contains(&candidate) {
+            return n - i;
+        }
+        if i + 1 < n && list.wildcards.contains(&labels[i + 1..].join(".")) {
+            return n - i;
+        }
+    }
+
+    1
+}
+
+/// Helper: punycode form of an internationalized rule
+fn to_ascii(rule: &str) -> Option<String> {
+    if rule.is_ascii() {
+        return Some(rule.to_lowercase());
+    }
+    url::Url::parse(&format!("http://{}/", rule))
+        .ok()?
+        .host_str()
+        .map(str::to_string)
+}
@@ -1,2 +1,3 @@
 export { default as SettingsDialog } from "./SettingsDialog.vue";
 export { default as ChangePasswordDialog } from "./ChangePasswordDialog.vue";
+export { default as HealthDialog } from "./HealthDialog.vue";
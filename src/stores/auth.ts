@@ -1,6 +1,7 @@
 import { defineStore } from "pinia";
 import { ref, computed } from "vue";
 import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
 
 export const useAuthStore = defineStore("auth", () => {
   // State
@@ -61,6 +62,28 @@ export const useAuthStore = defineStore("auth", () => {
 
   function lock() {
     isUnlocked.value = false;
+    // Also locks the unlock agent serving the CLI
+    invoke("lock_session").catch((error) => {
+      console.error("Failed to lock session:", error);
+    });
+  }
+
+  // The backend owns the lock state; windows follow it
+  async function fetchLockState() {
+    try {
+      isUnlocked.value = await invoke<boolean>("get_lock_state");
+    } catch (error) {
+      console.error("Failed to get lock state:", error);
+    }
+  }
+
+  function listenLockState() {
+    return listen<boolean>("lock-state-changed", (event) => {
+      isUnlocked.value = event.payload;
+      if (event.payload) {
+        updateLastActivity();
+      }
+    });
   }
 
   function updateLastActivity() {
@@ -80,6 +103,8 @@ export const useAuthStore = defineStore("auth", () => {
     verifyPassword,
     changePassword,
     lock,
+    fetchLockState,
+    listenLockState,
     updateLastActivity,
   };
 });
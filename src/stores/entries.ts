@@ -1,7 +1,7 @@
 import { defineStore } from "pinia";
 import { ref, computed } from "vue";
 import { invoke } from "@tauri-apps/api/core";
-import type { Entry, CreateEntryDto, UpdateEntryDto } from "@/types";
+import type { Entry, CreateEntryDto, UpdateEntryDto, EntrySort, EntryType } from "@/types";
 
 export const useEntriesStore = defineStore("entries", () => {
   // State
@@ -9,6 +9,8 @@ export const useEntriesStore = defineStore("entries", () => {
   const selectedEntryId = ref<string | null>(null);
   const searchKeyword = ref("");
   const showFavoritesOnly = ref(false);
+  const entryTypeFilter = ref<EntryType | null>(null);
+  const sortBy = ref<EntrySort>("manual");
   const isLoading = ref(false);
 
   // Global counts (independent of current filter)
@@ -43,8 +45,8 @@ export const useEntriesStore = defineStore("entries", () => {
       if (a.isFavorite !== b.isFavorite) {
         return a.isFavorite ? -1 : 1;
       }
-      // Then by sort order
-      return a.sortOrder - b.sortOrder;
+      // Then by sort order, other orders come sorted from the backend
+      return sortBy.value === "manual" ? a.sortOrder - b.sortOrder : 0;
     });
   });
 
@@ -60,6 +62,8 @@ export const useEntriesStore = defineStore("entries", () => {
         groupId,
         search: searchKeyword.value || null,
         favoritesOnly: favoritesOnly ?? showFavoritesOnly.value,
+        entryType: entryTypeFilter.value,
+        sort: sortBy.value,
       });
     } catch (error) {
       console.error("Failed to fetch entries:", error);
@@ -154,6 +158,10 @@ export const useEntriesStore = defineStore("entries", () => {
     selectedEntryId.value = id;
   }
 
+  function setSortBy(sort: EntrySort) {
+    sortBy.value = sort;
+  }
+
   function setSearchKeyword(keyword: string) {
     searchKeyword.value = keyword;
   }
@@ -172,6 +180,8 @@ export const useEntriesStore = defineStore("entries", () => {
     selectedEntryId,
     searchKeyword,
     showFavoritesOnly,
+    entryTypeFilter,
+    sortBy,
     isLoading,
     totalCount,
     totalFavoriteCount,
@@ -187,6 +197,7 @@ export const useEntriesStore = defineStore("entries", () => {
     deleteEntry,
     toggleFavorite,
     selectEntry,
+    setSortBy,
     setSearchKeyword,
     setShowFavoritesOnly,
     clearSelection,
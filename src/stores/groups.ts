@@ -57,9 +57,9 @@ export const useGroupsStore = defineStore("groups", () => {
     }
   }
 
-  async function updateGroup(id: string, name?: string, icon?: string) {
+  async function updateGroup(id: string, name?: string, icon?: string, autotypeSequence?: string) {
     try {
-      const updated = await invoke<Group>("update_group", { id, name, icon });
+      const updated = await invoke<Group>("update_group", { id, name, icon, autotypeSequence });
       const index = groups.value.findIndex((g) => g.id === id);
       if (index !== -1) {
         groups.value[index] = updated;
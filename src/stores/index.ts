@@ -2,3 +2,4 @@ export { useAuthStore } from "./auth";
 export { useGroupsStore } from "./groups";
 export { useEntriesStore } from "./entries";
 export { useSettingsStore } from "./settings";
+export { useTemplatesStore } from "./templates";
@@ -1,7 +1,15 @@
 import { defineStore } from "pinia";
 import { ref } from "vue";
 import { invoke } from "@tauri-apps/api/core";
-import type { Settings } from "@/types";
+import { listen } from "@tauri-apps/api/event";
+import type {
+  Policy,
+  SettingLock,
+  Settings,
+  SettingsWithLocks,
+  ShortcutAction,
+  ShortcutStatus,
+} from "@/types";
 
 export const useSettingsStore = defineStore("settings", () => {
   // State
@@ -9,14 +17,44 @@ export const useSettingsStore = defineStore("settings", () => {
     autoLockMinutes: 5,
     clearClipboardSeconds: 30,
     theme: "system",
+    trackUsage: true,
+    breachCheck: false,
+    launchAtLogin: false,
+    startMinimized: false,
+    quitOnClose: false,
+    backupEnabled: false,
+    backupDirectory: "",
+    backupIntervalHours: 24,
+    backupKeepDaily: 7,
+    backupKeepWeekly: 4,
+    backupKeepMonthly: 6,
+    syncEnabled: false,
+    syncBackend: "folder",
+    syncDirectory: "",
+    syncWebdavUrl: "",
+    syncIntervalMinutes: 5,
+    sshAgentEnabled: false,
+    secretServiceEnabled: false,
+    shortcutShowMain: "",
+    shortcutLock: "",
+    shortcutCopyLastPassword: "",
+    shortcutAutoType: "",
+    shortcutQuickAccess: "ctrl+shift+p",
+    autotypeDefaultSequence: "{USERNAME}{TAB}{PASSWORD}{ENTER}",
   });
+  // Settings the admin policy fixes or limits
+  const lockedSettings = ref<SettingsWithLocks["locked"]>({});
+  const policy = ref<Policy | null>(null);
+  const shortcuts = ref<ShortcutStatus[]>([]);
   const isLoading = ref(false);
 
   // Actions
   async function fetchSettings() {
     isLoading.value = true;
     try {
-      settings.value = await invoke<Settings>("get_settings");
+      const { locked, ...values } = await invoke<SettingsWithLocks>("get_settings");
+      settings.value = values;
+      lockedSettings.value = locked;
       applyTheme(settings.value.theme);
     } catch (error) {
       console.error("Failed to fetch settings:", error);
@@ -26,22 +64,94 @@ export const useSettingsStore = defineStore("settings", () => {
     }
   }
 
+  // Only changed values are sent; the backend validates them and rejects the whole update on error
   async function updateSettings(newSettings: Partial<Settings>) {
+    const changes = Object.fromEntries(
+      Object.entries(newSettings).filter(
+        ([name, value]) => settings.value[name as keyof Settings] !== value
+      )
+    );
+    if (Object.keys(changes).length === 0) {
+      return;
+    }
     try {
-      const updated = { ...settings.value, ...newSettings };
-      await invoke("update_settings", { settings: updated });
-      settings.value = updated;
-
-      if (newSettings.theme) {
-        localStorage.setItem("one-password-theme", newSettings.theme);
-        applyTheme(newSettings.theme);
-      }
+      applyResponse(await invoke<SettingsWithLocks>("update_settings", { changes }));
     } catch (error) {
       console.error("Failed to update settings:", error);
       throw error;
     }
   }
 
+  async function resetSettings() {
+    applyResponse(await invoke<SettingsWithLocks>("reset_settings"));
+  }
+
+  async function exportSettings() {
+    const content = await invoke<string>("export_settings");
+    await invoke("save_settings_file", { content });
+  }
+
+  async function importSettings() {
+    const jsonData = await invoke<string>("load_settings_file");
+    applyResponse(await invoke<SettingsWithLocks>("import_settings", { jsonData }));
+  }
+
+  async function fetchPolicy() {
+    try {
+      policy.value = await invoke<Policy>("get_policy");
+    } catch (error) {
+      console.error("Failed to fetch policy:", error);
+    }
+  }
+
+  function settingLock(name: keyof Settings): SettingLock | undefined {
+    return lockedSettings.value[name];
+  }
+
+  // Changes made in any window, including this one
+  function listenSettingsChanges() {
+    return listen<Partial<Settings>>("settings-changed", (event) => {
+      applySettings({ ...settings.value, ...event.payload });
+    });
+  }
+
+  function applyResponse({ locked, ...values }: SettingsWithLocks) {
+    lockedSettings.value = locked;
+    applySettings(values);
+  }
+
+  function applySettings(updated: Settings) {
+    const themeChanged = updated.theme !== settings.value.theme;
+    settings.value = updated;
+    if (themeChanged) {
+      localStorage.setItem("one-password-theme", updated.theme);
+      applyTheme(updated.theme);
+    }
+  }
+
+  async function fetchShortcuts() {
+    try {
+      shortcuts.value = await invoke<ShortcutStatus[]>("get_shortcuts");
+    } catch (error) {
+      console.error("Failed to fetch shortcuts:", error);
+      throw error;
+    }
+  }
+
+  // Rejects with the registration error so the user can pick another combination
+  async function setShortcut(action: ShortcutAction, accelerator: string) {
+    const status = await invoke<ShortcutStatus>("set_shortcut", { action, accelerator });
+    const index = shortcuts.value.findIndex((s) => s.action === action);
+    if (index !== -1) {
+      shortcuts.value[index] = status;
+    }
+    return status;
+  }
+
+  async function resetShortcuts() {
+    shortcuts.value = await invoke<ShortcutStatus[]>("reset_shortcuts");
+  }
+
   function applyTheme(theme: Settings["theme"]) {
     const root = document.documentElement;
 
@@ -75,10 +185,22 @@ export const useSettingsStore = defineStore("settings", () => {
   return {
     // State
     settings,
+    lockedSettings,
+    policy,
+    shortcuts,
     isLoading,
     // Actions
     fetchSettings,
     updateSettings,
+    resetSettings,
+    exportSettings,
+    importSettings,
+    fetchPolicy,
+    settingLock,
+    listenSettingsChanges,
+    fetchShortcuts,
+    setShortcut,
+    resetShortcuts,
     applyTheme,
     initThemeWatcher,
   };
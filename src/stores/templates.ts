@@ -0,0 +1,88 @@
+import { defineStore } from "pinia";
+import { ref } from "vue";
+import { invoke } from "@tauri-apps/api/core";
+import type {
+  EntryTemplate,
+  CreateTemplateDto,
+  UpdateTemplateDto,
+  TemplateImportResult,
+} from "@/types";
+
+export const useTemplatesStore = defineStore("templates", () => {
+  // State
+  const templates = ref<EntryTemplate[]>([]);
+  const isLoading = ref(false);
+
+  // Actions
+  async function fetchTemplates() {
+    isLoading.value = true;
+    try {
+      templates.value = await invoke<EntryTemplate[]>("get_templates");
+    } catch (error) {
+      console.error("Failed to fetch templates:", error);
+      throw error;
+    } finally {
+      isLoading.value = false;
+    }
+  }
+
+  async function createTemplate(template: CreateTemplateDto) {
+    try {
+      const created = await invoke<EntryTemplate>("create_template", { template });
+      templates.value.push(created);
+      return created;
+    } catch (error) {
+      console.error("Failed to create template:", error);
+      throw error;
+    }
+  }
+
+  async function updateTemplate(id: string, template: UpdateTemplateDto) {
+    try {
+      const updated = await invoke<EntryTemplate>("update_template", { id, template });
+      const index = templates.value.findIndex((t) => t.id === id);
+      if (index !== -1) {
+        templates.value[index] = updated;
+      }
+      return updated;
+    } catch (error) {
+      console.error("Failed to update template:", error);
+      throw error;
+    }
+  }
+
+  async function deleteTemplate(id: string) {
+    try {
+      await invoke("delete_template", { id });
+      templates.value = templates.value.filter((t) => t.id !== id);
+    } catch (error) {
+      console.error("Failed to delete template:", error);
+      throw error;
+    }
+  }
+
+  async function exportTemplates(ids?: string[]) {
+    const content = await invoke<string>("export_templates", { ids: ids ?? null });
+    await invoke("save_templates_file", { content });
+  }
+
+  async function importTemplates() {
+    const jsonData = await invoke<string>("load_templates_file");
+    const result = await invoke<TemplateImportResult>("import_templates", { jsonData });
+    await fetchTemplates();
+    return result;
+  }
+
+  return {
+    // State
+    templates,
+    isLoading,
+    // Actions
+    fetchTemplates,
+    createTemplate,
+    updateTemplate,
+    deleteTemplate,
+    exportTemplates,
+    importTemplates,
+  };
+});
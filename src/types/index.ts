@@ -1,12 +1,78 @@
 // Entry types
+export type EntryType =
+  | 'login'
+  | 'secureNote'
+  | 'creditCard'
+  | 'bankAccount'
+  | 'identity'
+  | 'wifi'
+  | 'server'
+  | 'sshKey'
+  | 'softwareLicense'
+  | 'apiKey';
+
+export type FieldKind =
+  | 'text'
+  | 'multiline'
+  | 'concealed'
+  | 'url'
+  | 'email'
+  | 'phone'
+  | 'number'
+  | 'date'
+  | 'monthYear'
+  | 'cardNumber'
+  | 'cardCode'
+  | 'iban'
+  | 'bic'
+  | 'sshPrivateKey'
+  | 'totp';
+
+export interface EntryField {
+  /** Schema field name, or the label of a custom field */
+  name: string;
+  value: string;
+  kind?: FieldKind;
+}
+
+export interface FieldSpec {
+  name: string;
+  label: string;
+  kind: FieldKind;
+  required: boolean;
+}
+
+export interface EntryTypeSchema {
+  entryType: EntryType;
+  label: string;
+  icon: string;
+  fields: FieldSpec[];
+  /** Field shown under the title in lists and Quick Access */
+  summaryField: string | null;
+}
+
+export type UrlMatch = 'exact' | 'host' | 'baseDomain' | 'startsWith' | 'regex' | 'never';
+
+export interface EntryUrl {
+  url: string;
+  /** Overrides the entry's match mode for this URL */
+  match?: UrlMatch | null;
+}
+
 export interface Entry {
   id: string;
+  entryType: EntryType;
   groupId: string | null;
   title: string;
   url: string;
   username: string;
   password: string;
   notes: string;
+  urlMatch: UrlMatch;
+  extraUrls: EntryUrl[];
+  fields: EntryField[];
+  /** Keys typed by auto-type; null uses the group's sequence or the default */
+  autotypeSequence: string | null;
   isFavorite: boolean;
   sortOrder: number;
   createdAt: number;
@@ -14,30 +80,75 @@ export interface Entry {
 }
 
 export interface CreateEntryDto {
+  entryType?: EntryType;
   groupId?: string | null;
   title: string;
   url?: string;
   username?: string;
   password?: string;
   notes?: string;
+  urlMatch?: UrlMatch;
+  extraUrls?: EntryUrl[];
+  fields?: EntryField[];
+  /** Start from a user-defined template; set values take precedence over its defaults */
+  templateId?: string;
+  autotypeSequence?: string;
 }
 
 export interface UpdateEntryDto {
+  entryType?: EntryType;
   groupId?: string | null;
   title?: string;
   url?: string;
   username?: string;
   password?: string;
   notes?: string;
+  urlMatch?: UrlMatch;
+  extraUrls?: EntryUrl[];
+  fields?: EntryField[];
+  /** Empty string clears the entry's own sequence */
+  autotypeSequence?: string;
   isFavorite?: boolean;
   sortOrder?: number;
 }
 
+export interface EntryMatch {
+  entry: Entry;
+  score: number;
+  matchedUrl: string;
+}
+
+export type EntrySort = 'manual' | 'title' | 'updated' | 'created' | 'lastUsed' | 'useCount';
+
+/** Local to this device, never synced or exported */
+export interface EntryUsage {
+  entryId: string;
+  lastUsedAt: number | null;
+  useCount: number;
+}
+
+/** Quick Access search result, without secret values */
+export interface SearchHit {
+  id: string;
+  entryType: EntryType;
+  groupId: string | null;
+  title: string;
+  username: string;
+  url: string;
+  /** Username, or the type's summary field with card numbers masked */
+  subtitle: string;
+  hasPassword: boolean;
+  isFavorite: boolean;
+  score: number;
+}
+
 // Group types
 export interface Group {
   id: string;
   name: string;
   icon: string;
+  /** Auto-type sequence for entries without their own */
+  autotypeSequence: string | null;
   sortOrder: number;
   createdAt: number;
   updatedAt: number;
@@ -54,14 +165,140 @@ export interface UpdateGroupDto {
   sortOrder?: number;
 }
 
+// Template types
+export interface TemplateField {
+  name: string;
+  kind: FieldKind;
+  defaultValue: string;
+  required: boolean;
+}
+
+export interface EntryTemplate {
+  id: string;
+  name: string;
+  icon: string;
+  baseType: EntryType;
+  fields: TemplateField[];
+  createdAt: number;
+  updatedAt: number;
+}
+
+export interface CreateTemplateDto {
+  name: string;
+  icon?: string;
+  baseType?: EntryType;
+  fields?: TemplateField[];
+}
+
+export interface UpdateTemplateDto {
+  name?: string;
+  icon?: string;
+  baseType?: EntryType;
+  fields?: TemplateField[];
+}
+
+export interface TemplateImportResult {
+  imported: number;
+  updated: number;
+}
+
+// Shortcut types
+export type ShortcutAction =
+  | 'quickAccess'
+  | 'showMain'
+  | 'lock'
+  | 'copyLastPassword'
+  | 'autoType';
+
+export interface ShortcutStatus {
+  action: ShortcutAction;
+  /** e.g. `ctrl+shift+p`; empty when unbound */
+  accelerator: string;
+  registered: boolean;
+  /** Why registration failed, usually because another app holds the combination */
+  error: string | null;
+}
+
 // Settings types
 export interface Settings {
   autoLockMinutes: number;
   clearClipboardSeconds: number;
   theme: "light" | "dark" | "system";
+  /** Record when and how often entries are used; turning it off clears the record */
+  trackUsage: boolean;
+  /** Look up password hash prefixes at api.pwnedpasswords.com; off unless the user opts in */
+  breachCheck: boolean;
+  launchAtLogin: boolean;
+  startMinimized: boolean;
+  /** Closing the main window quits instead of hiding to the tray */
+  quitOnClose: boolean;
+  backupEnabled: boolean;
+  backupDirectory: string;
+  backupIntervalHours: number;
+  backupKeepDaily: number;
+  backupKeepWeekly: number;
+  backupKeepMonthly: number;
+  /** Sync settings only change by joining or leaving sync */
+  syncEnabled: boolean;
+  syncBackend: "folder" | "webdav";
+  syncDirectory: string;
+  syncWebdavUrl: string;
+  syncIntervalMinutes: number;
+  sshAgentEnabled: boolean;
+  secretServiceEnabled: boolean;
+  /** Accelerators such as `ctrl+shift+p`, empty when unbound; see `set_shortcut` */
+  shortcutShowMain: string;
+  shortcutLock: string;
+  shortcutCopyLastPassword: string;
+  shortcutAutoType: string;
+  shortcutQuickAccess: string;
+  autotypeDefaultSequence: string;
+}
+
+/** How the admin policy restricts a setting */
+export interface SettingLock {
+  reason: string;
+  /** Fixed to its current value */
+  fixed: boolean;
+  /** Highest value allowed; 0 (never) is not allowed either */
+  max?: number;
+}
+
+/** `get_settings` result: the values plus the settings restricted by the admin policy */
+export type SettingsWithLocks = Settings & {
+  locked: Partial<Record<keyof Settings, SettingLock>>;
+};
+
+/** Admin policy from /etc/one-password/policy.json */
+export interface Policy {
+  maxAutoLockMinutes: number | null;
+  maxClearClipboardSeconds: number | null;
+  masterPassword: {
+    minLength: number | null;
+    minCharacterClasses: number | null;
+  };
+  disablePlaintextExport: boolean;
+  settings: Partial<Settings>;
 }
 
 // App state types
+export interface HealthIssue {
+  entryId: string;
+  title: string;
+  detail: string;
+}
+
+export interface HealthReport {
+  breached: HealthIssue[];
+  expired: HealthIssue[];
+  breachCheckedAt: number | null;
+}
+
+// Navigation requested through an onepassword:// link
+export type DeepLink =
+  | { action: "open"; entryId: string }
+  | { action: "search"; query: string };
+
 export type ViewMode = "all" | "favorites" | "group";
 
 export interface AppState {